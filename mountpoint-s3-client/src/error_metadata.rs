@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 /// Additional data fetched from S3 response, which caused an error
 #[derive(Default, Debug, Clone, PartialEq)]
 pub struct ClientErrorMetadata {
@@ -7,6 +9,8 @@ pub struct ClientErrorMetadata {
     pub error_code: Option<String>,
     /// error message from the response xml body, e.g. "Access Denied"
     pub error_message: Option<String>,
+    /// delay before retrying the request, from the `Retry-After` header of the response
+    pub retry_after: Option<Duration>,
 }
 
 /// Allows using metadata of errors in generic implementations without knowing the exact type of an error,
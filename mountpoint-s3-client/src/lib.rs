@@ -85,11 +85,14 @@ pub mod config {
 pub mod types {
     pub use super::object_client::{
         Checksum, ChecksumAlgorithm, ChecksumMode, ClientBackpressureHandle, CopyObjectParams, CopyObjectResult,
-        DeleteObjectResult, ETag, GetBodyPart, GetObjectAttributesParts, GetObjectAttributesResult, GetObjectParams,
-        GetObjectResponse, HeadObjectParams, HeadObjectResult, ListObjectsResult, ObjectAttribute, ObjectClientResult,
-        ObjectInfo, ObjectPart, PutObjectParams, PutObjectResult, PutObjectSingleParams, PutObjectTrailingChecksums,
-        RestoreStatus, UploadChecksum, UploadReview, UploadReviewPart,
+        DeleteObjectResult, DeleteObjectsKeyError, DeleteObjectsResult, ETag, GetBodyPart, GetObjectAttributesParts,
+        GetObjectAttributesResult, GetObjectParams, GetObjectResponse, HeadObjectParams, HeadObjectResult,
+        ListObjectsResult, ObjectAttribute, ObjectClientResult, ObjectInfo, ObjectLockMode, ObjectLockRetention,
+        ObjectPart, PutObjectParams, PutObjectResult, PutObjectSingleParams, PutObjectTrailingChecksums, RestoreStatus,
+        UploadChecksum, UploadReview, UploadReviewPart, DELETE_OBJECTS_MAX_KEYS,
     };
+
+    pub use mountpoint_s3_crt::s3::client::BufferPoolUsageStats;
 }
 
 /// Errors returned by all object clients.
@@ -99,8 +102,8 @@ pub mod types {
 /// client errors. See its documentation for more details.
 pub mod error {
     pub use super::object_client::{
-        CopyObjectError, DeleteObjectError, GetObjectAttributesError, GetObjectError, HeadObjectError,
-        ListObjectsError, ObjectClientError, PutObjectError,
+        CopyObjectError, DeleteObjectError, DeleteObjectsError, GetObjectAttributesError, GetObjectError,
+        HeadObjectError, ListObjectsError, ObjectClientError, PutObjectError,
     };
     #[doc(hidden)]
     pub use super::s3_crt_client::CrtError;
@@ -28,12 +28,13 @@ use crate::checksums::{
 use crate::error_metadata::{ClientErrorMetadata, ProvideErrorMetadata};
 use crate::object_client::{
     Checksum, ChecksumAlgorithm, ChecksumMode, ClientBackpressureHandle, CopyObjectError, CopyObjectParams,
-    CopyObjectResult, DeleteObjectError, DeleteObjectResult, ETag, GetBodyPart, GetObjectAttributesError,
-    GetObjectAttributesParts, GetObjectAttributesResult, GetObjectError, GetObjectParams, GetObjectResponse,
-    HeadObjectError, HeadObjectParams, HeadObjectResult, ListObjectsError, ListObjectsResult, ObjectAttribute,
-    ObjectChecksumError, ObjectClient, ObjectClientError, ObjectClientResult, ObjectInfo, ObjectMetadata, ObjectPart,
-    PutObjectError, PutObjectParams, PutObjectRequest, PutObjectResult, PutObjectSingleParams,
-    PutObjectTrailingChecksums, RestoreStatus, UploadChecksum, UploadReview, UploadReviewPart,
+    CopyObjectResult, DeleteObjectError, DeleteObjectResult, DeleteObjectsError, DeleteObjectsKeyError,
+    DeleteObjectsResult, ETag, GetBodyPart, GetObjectAttributesError, GetObjectAttributesParts,
+    GetObjectAttributesResult, GetObjectError, GetObjectParams, GetObjectResponse, HeadObjectError, HeadObjectParams,
+    HeadObjectResult, ListObjectsError, ListObjectsResult, ObjectAttribute, ObjectChecksumError, ObjectClient,
+    ObjectClientError, ObjectClientResult, ObjectInfo, ObjectLockRetention, ObjectMetadata, ObjectPart, PutObjectError,
+    PutObjectParams, PutObjectRequest, PutObjectResult, PutObjectSingleParams, PutObjectTrailingChecksums,
+    RestoreStatus, UploadChecksum, UploadReview, UploadReviewPart, DELETE_OBJECTS_MAX_KEYS,
 };
 
 mod leaky_bucket;
@@ -70,6 +71,8 @@ pub struct MockClientConfig {
     pub enable_backpressure: bool,
     /// Initial backpressure read window size, ignored if enable_back_pressure is false
     pub initial_read_window_size: usize,
+    /// The region reported by [ObjectClient::region], or None for a client without region
+    pub region: Option<String>,
 }
 
 /// A mock implementation of an object client that we can manually add objects to, and then query
@@ -80,6 +83,8 @@ pub struct MockClient {
     objects: Arc<RwLock<BTreeMap<String, MockObject>>>,
     in_progress_uploads: Arc<RwLock<BTreeSet<String>>>,
     operation_counts: Arc<RwLock<HashMap<Operation, u64>>>,
+    removals_during_read: Arc<RwLock<HashMap<String, usize>>>,
+    reversed_reads: Arc<RwLock<HashSet<String>>>,
 }
 
 fn add_object(objects: &Arc<RwLock<BTreeMap<String, MockObject>>>, key: &str, value: MockObject) {
@@ -94,9 +99,26 @@ impl MockClient {
             objects: Default::default(),
             in_progress_uploads: Default::default(),
             operation_counts: Default::default(),
+            removals_during_read: Default::default(),
+            reversed_reads: Default::default(),
         }
     }
 
+    /// Remove an object once `parts` parts of the next GetObject stream for it have been read.
+    ///
+    /// The rest of that stream fails with `NoSuchKey`, as the requests for the remaining parts would
+    /// against S3. Other streams keep returning the object as it was when they were requested.
+    pub fn remove_object_during_read(&self, key: &str, parts: usize) {
+        self.removals_during_read.write().unwrap().insert(key.to_owned(), parts);
+    }
+
+    /// Return the parts of the next GetObject stream for an object in reverse order.
+    ///
+    /// Some S3-compatible stores deliver the parts of an object out of order, which this simulates.
+    pub fn reverse_parts_of_next_read(&self, key: &str) {
+        self.reversed_reads.write().unwrap().insert(key.to_owned());
+    }
+
     /// Add an object to this mock client's bucket
     pub fn add_object(&self, key: &str, value: MockObject) {
         add_object(&self.objects, key, value);
@@ -142,6 +164,15 @@ impl MockClient {
         }
     }
 
+    /// Returns error if object does not exist
+    pub fn get_object_lock_retention(&self, key: &str) -> Result<Option<ObjectLockRetention>, MockClientError> {
+        if let Some(mock_object) = self.objects.read().unwrap().get(key) {
+            Ok(mock_object.object_lock_retention.clone())
+        } else {
+            Err(MockClientError("object not found".into()))
+        }
+    }
+
     /// Returns error if object does not exist
     pub fn restore_object(&self, key: &str) -> Result<(), MockClientError> {
         match self.objects.write().unwrap().get_mut(key) {
@@ -185,6 +216,13 @@ impl MockClient {
         params: &PutObjectSingleParams,
         contents: impl AsRef<[u8]> + Send + 'a,
     ) -> ObjectClientResult<PutObjectResult, PutObjectError, MockClientError> {
+        if params
+            .content_length
+            .is_some_and(|len| len != contents.as_ref().len() as u64)
+        {
+            return mock_client_error("content length does not match the data");
+        }
+
         if let Some(offset) = params.write_offset_bytes {
             // Handle as an Append request.
             return self.append_object(key, offset, params, contents);
@@ -196,6 +234,7 @@ impl MockClient {
         object.set_storage_class(params.storage_class.clone());
         object.set_object_metadata(params.object_metadata.clone());
         object.set_checksum(checksum);
+        object.set_object_lock_retention(params.object_lock_retention.clone());
 
         let etag = object.etag.clone();
         add_object(&self.objects, key, object);
@@ -447,6 +486,7 @@ impl MockClient {
 #[derive(Debug, Eq, Hash, PartialEq)]
 pub enum Operation {
     DeleteObject,
+    DeleteObjects,
     HeadObject,
     GetObject,
     GetObjectAttributes,
@@ -490,6 +530,7 @@ pub struct MockObject {
     ///
     /// Typically, at most one of the checksums should be set.
     checksum: Checksum,
+    object_lock_retention: Option<ObjectLockRetention>,
 }
 
 impl MockObject {
@@ -510,6 +551,7 @@ impl MockObject {
             parts: None,
             object_metadata: HashMap::new(),
             checksum: Checksum::empty(),
+            object_lock_retention: None,
         }
     }
 
@@ -524,6 +566,7 @@ impl MockObject {
             parts: None,
             object_metadata: HashMap::new(),
             checksum: Checksum::empty(),
+            object_lock_retention: None,
         }
     }
 
@@ -548,6 +591,7 @@ impl MockObject {
             parts: None,
             object_metadata: HashMap::new(),
             checksum: Checksum::empty(),
+            object_lock_retention: None,
         }
     }
 
@@ -597,6 +641,17 @@ impl MockObject {
         self.checksum = checksum;
     }
 
+    pub fn set_object_lock_retention(&mut self, object_lock_retention: Option<ObjectLockRetention>) {
+        self.object_lock_retention = object_lock_retention;
+    }
+
+    /// Whether the object is protected from deletion by its Object Lock retention.
+    fn is_locked(&self) -> bool {
+        self.object_lock_retention
+            .as_ref()
+            .is_some_and(|retention| retention.retain_until_date > OffsetDateTime::now_utc())
+    }
+
     pub fn len(&self) -> usize {
         self.size
     }
@@ -698,6 +753,17 @@ pub struct MockGetObjectResponse {
     length: usize,
     part_size: usize,
     backpressure_handle: Option<MockBackpressureHandle>,
+    removal: Option<PendingRemoval>,
+    /// Return the parts from the last one, see [MockClient::reverse_parts_of_next_read].
+    reversed: bool,
+}
+
+/// Removal of the object of a [MockGetObjectResponse] scheduled with [MockClient::remove_object_during_read].
+#[derive(Debug)]
+struct PendingRemoval {
+    objects: Arc<RwLock<BTreeMap<String, MockObject>>>,
+    key: String,
+    parts_left: usize,
 }
 
 impl MockGetObjectResponse {
@@ -741,20 +807,35 @@ impl Stream for MockGetObjectResponse {
             return Poll::Ready(None);
         }
 
-        let next_read_size = self.part_size.min(self.length);
+        if let Some(removal) = &mut self.removal {
+            if removal.parts_left == 0 {
+                removal.objects.write().unwrap().remove(&removal.key);
+                return Poll::Ready(Some(Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey))));
+            }
+            removal.parts_left -= 1;
+        }
+
+        let (part_offset, next_read_size) = if self.reversed {
+            let last_part_start = (self.length - 1) / self.part_size * self.part_size;
+            (self.next_offset + last_part_start as u64, self.length - last_part_start)
+        } else {
+            (self.next_offset, self.part_size.min(self.length))
+        };
 
         // Simulate backpressure mechanism
         if let Some(handle) = &self.backpressure_handle {
-            if self.next_offset >= handle.read_window_end_offset() {
+            if part_offset >= handle.read_window_end_offset() {
                 return Poll::Ready(Some(Err(ObjectClientError::ClientError(MockClientError(
                     "empty read window".into(),
                 )))));
             }
         }
-        let next_part = self.object.read(self.next_offset, next_read_size);
+        let next_part = self.object.read(part_offset, next_read_size);
 
-        let result = (self.next_offset, next_part);
-        self.next_offset += next_read_size as u64;
+        let result = (part_offset, next_part);
+        if !self.reversed {
+            self.next_offset += next_read_size as u64;
+        }
         self.length -= next_read_size;
         Poll::Ready(Some(Ok(result)))
     }
@@ -805,6 +886,10 @@ impl ObjectClient for MockClient {
         None
     }
 
+    fn region(&self) -> Option<&str> {
+        self.config.region.as_deref()
+    }
+
     async fn delete_object(
         &self,
         bucket: &str,
@@ -817,11 +902,49 @@ impl ObjectClient for MockClient {
             return Err(ObjectClientError::ServiceError(DeleteObjectError::NoSuchBucket));
         }
 
+        // Unlike S3, the mock does not keep object versions, so it rejects any delete of a locked object.
+        if self.objects.read().unwrap().get(key).is_some_and(MockObject::is_locked) {
+            return Err(ObjectClientError::ServiceError(DeleteObjectError::ObjectLocked));
+        }
+
         self.remove_object(key);
 
         Ok(DeleteObjectResult {})
     }
 
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError> {
+        trace!(bucket, keys = keys.len(), "DeleteObjects");
+        self.inc_op_count(Operation::DeleteObjects);
+
+        if bucket != self.config.bucket {
+            return Err(ObjectClientError::ServiceError(DeleteObjectsError::NoSuchBucket));
+        }
+        if keys.len() > DELETE_OBJECTS_MAX_KEYS {
+            return Err(ObjectClientError::ServiceError(DeleteObjectsError::TooManyKeys(
+                keys.len(),
+            )));
+        }
+
+        let mut errors = Vec::new();
+        for key in keys {
+            if self.objects.read().unwrap().get(key).is_some_and(MockObject::is_locked) {
+                errors.push(DeleteObjectsKeyError {
+                    key: key.clone(),
+                    code: "AccessDenied".to_owned(),
+                    message: "Access Denied because object protected by object lock.".to_owned(),
+                });
+            } else {
+                self.remove_object(key);
+            }
+        }
+
+        Ok(DeleteObjectsResult { errors })
+    }
+
     async fn copy_object(
         &self,
         source_bucket: &str,
@@ -876,19 +999,32 @@ impl ObjectClient for MockClient {
             };
 
             let backpressure_handle = if self.config.enable_backpressure {
-                let read_window_end_offset = Arc::new(AtomicU64::new(
-                    next_offset + self.config.initial_read_window_size as u64,
-                ));
+                let initial_read_window = params
+                    .initial_read_window
+                    .unwrap_or(self.config.initial_read_window_size);
+                let read_window_end_offset = Arc::new(AtomicU64::new(next_offset + initial_read_window as u64));
                 Some(MockBackpressureHandle { read_window_end_offset })
             } else {
                 None
             };
+            let removal = self
+                .removals_during_read
+                .write()
+                .unwrap()
+                .remove(key)
+                .map(|parts_left| PendingRemoval {
+                    objects: self.objects.clone(),
+                    key: key.to_owned(),
+                    parts_left,
+                });
             Ok(MockGetObjectResponse {
                 object: object.clone(),
                 next_offset,
                 length,
                 part_size: self.config.part_size,
                 backpressure_handle,
+                removal,
+                reversed: self.reversed_reads.write().unwrap().remove(key),
             })
         } else {
             Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey))
@@ -1121,6 +1257,7 @@ impl MockPutObjectRequest {
         let mut object: MockObject = buffer.into();
         object.set_storage_class(self.params.storage_class.clone());
         object.set_object_metadata(self.params.object_metadata.clone());
+        object.set_object_lock_retention(self.params.object_lock_retention.clone());
 
         // For S3 Standard, part attributes are only available when additional checksums are used
         if self.params.trailing_checksums == PutObjectTrailingChecksums::Enabled {
@@ -1234,6 +1371,7 @@ mod tests {
     use test_case::test_case;
 
     use super::*;
+    use crate::object_client::ObjectLockMode;
 
     macro_rules! assert_client_error {
         ($e:expr, $err:expr) => {
@@ -1317,6 +1455,7 @@ mod tests {
             unordered_list_seed: None,
             enable_backpressure: true,
             initial_read_window_size: backpressure_read_window_size,
+            region: None,
         });
 
         let mut body = vec![0u8; size];
@@ -1428,6 +1567,7 @@ mod tests {
             unordered_list_seed: None,
             enable_backpressure: true,
             initial_read_window_size: 256,
+            region: None,
         });
 
         let part_size = client.read_part_size().unwrap();
@@ -1457,6 +1597,75 @@ mod tests {
         assert_client_error!(next, "empty read window");
     }
 
+    #[tokio::test]
+    async fn test_get_object_initial_read_window() {
+        let key = "key1";
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            unordered_list_seed: None,
+            enable_backpressure: true,
+            initial_read_window_size: 256,
+            region: None,
+        });
+        client.add_object(key, MockObject::constant(1, 4096, ETag::for_tests()));
+
+        let mut get_request = client
+            .get_object("test_bucket", key, &GetObjectParams::new())
+            .await
+            .expect("should not fail");
+        let backpressure_handle = get_request
+            .backpressure_handle()
+            .expect("backpressure should be enabled");
+        assert_eq!(backpressure_handle.read_window_end_offset(), 256);
+
+        let params = GetObjectParams::new()
+            .range(Some(1024..4096))
+            .initial_read_window(Some(2048));
+        let mut get_request = client
+            .get_object("test_bucket", key, &params)
+            .await
+            .expect("should not fail");
+        let backpressure_handle = get_request
+            .backpressure_handle()
+            .expect("backpressure should be enabled");
+        assert_eq!(backpressure_handle.read_window_end_offset(), 1024 + 2048);
+    }
+
+    #[tokio::test]
+    async fn test_reverse_parts_of_next_read() {
+        let key = "key1";
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            part_size: 1024,
+            ..Default::default()
+        });
+        let data: Vec<u8> = (0..2500).map(|i| i as u8).collect();
+        client.add_object(key, MockObject::from_bytes(&data, ETag::for_tests()));
+        client.reverse_parts_of_next_read(key);
+
+        let mut get_request = client
+            .get_object("test_bucket", key, &GetObjectParams::new())
+            .await
+            .expect("should not fail");
+        let mut parts = Vec::new();
+        while let Some((offset, part)) = get_request.next().await.transpose().expect("read should succeed") {
+            assert_eq!(&part[..], &data[offset as usize..offset as usize + part.len()]);
+            parts.push((offset, part.len()));
+        }
+        assert_eq!(parts, vec![(2048, 452), (1024, 1024), (0, 1024)]);
+
+        // Only the next read is reversed.
+        let body = client
+            .get_object("test_bucket", key, &GetObjectParams::new())
+            .await
+            .expect("should not fail")
+            .collect()
+            .await
+            .expect("read should succeed");
+        assert_eq!(&body[..], &data[..]);
+    }
+
     #[tokio::test]
     async fn test_copy_object() {
         let bucket = "test_bucket";
@@ -1945,6 +2154,94 @@ mod tests {
         assert_eq!(&content, &*actual);
     }
 
+    #[test_case(512, true; "matching")]
+    #[test_case(511, false; "shorter")]
+    #[test_case(513, false; "longer")]
+    #[tokio::test]
+    async fn test_put_object_single_content_length(content_length: u64, expect_success: bool) {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            ..Default::default()
+        });
+
+        let content = vec![42u8; 512];
+        let put_object_params = PutObjectSingleParams::new().content_length(content_length);
+        let result = client
+            .put_object_single("test_bucket", "key1", &put_object_params, &content)
+            .await;
+        assert_eq!(result.is_ok(), expect_success);
+        assert_eq!(client.contains_key("key1"), expect_success);
+    }
+
+    #[test_case(time::Duration::hours(1), false; "active retention")]
+    #[test_case(-time::Duration::hours(1), true; "expired retention")]
+    #[tokio::test]
+    async fn test_delete_object_with_retention(retain_for: time::Duration, expect_deleted: bool) {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            ..Default::default()
+        });
+
+        let retention = ObjectLockRetention {
+            mode: ObjectLockMode::Compliance,
+            retain_until_date: OffsetDateTime::now_utc() + retain_for,
+        };
+        let put_object_params = PutObjectSingleParams::new().object_lock_retention(Some(retention.clone()));
+        client
+            .put_object_single("test_bucket", "key1", &put_object_params, vec![42u8; 512])
+            .await
+            .expect("put_object failed");
+        assert_eq!(client.get_object_lock_retention("key1").unwrap(), Some(retention));
+
+        let result = client.delete_object("test_bucket", "key1").await;
+        if expect_deleted {
+            result.expect("delete should succeed after the retention period");
+        } else {
+            assert!(matches!(
+                result,
+                Err(ObjectClientError::ServiceError(DeleteObjectError::ObjectLocked))
+            ));
+        }
+        assert_eq!(client.contains_key("key1"), !expect_deleted);
+    }
+
+    #[tokio::test]
+    async fn test_delete_objects() {
+        let client = MockClient::new(MockClientConfig {
+            bucket: "test_bucket".to_string(),
+            ..Default::default()
+        });
+        for key in ["key1", "key2"] {
+            client.add_object(key, MockObject::constant(0u8, 10, ETag::for_tests()));
+        }
+        let retention = ObjectLockRetention {
+            mode: ObjectLockMode::Compliance,
+            retain_until_date: OffsetDateTime::now_utc() + time::Duration::hours(1),
+        };
+        let put_object_params = PutObjectSingleParams::new().object_lock_retention(Some(retention));
+        client
+            .put_object_single("test_bucket", "locked", &put_object_params, vec![42u8; 512])
+            .await
+            .expect("put_object failed");
+
+        let keys = ["key1", "key2", "locked", "missing"].map(String::from);
+        let result = client
+            .delete_objects("test_bucket", &keys)
+            .await
+            .expect("delete should succeed");
+        let failed: Vec<_> = result.errors.iter().map(|error| error.key.as_str()).collect();
+        assert_eq!(failed, ["locked"]);
+        assert_eq!(client.object_count(), 1);
+        assert!(client.contains_key("locked"));
+
+        let keys: Vec<_> = (0..=DELETE_OBJECTS_MAX_KEYS).map(|i| format!("key{i}")).collect();
+        let result = client.delete_objects("test_bucket", &keys).await;
+        assert!(matches!(
+            result,
+            Err(ObjectClientError::ServiceError(DeleteObjectsError::TooManyKeys(_)))
+        ));
+    }
+
     #[tokio::test]
     async fn test_checksums_set_after_single_put() {
         let client = MockClient::new(MockClientConfig {
@@ -13,11 +13,11 @@ use crate::mock_client::{
     MockClient, MockClientConfig, MockClientError, MockGetObjectResponse, MockObject, MockPutObjectRequest,
 };
 use crate::object_client::{
-    Checksum, CopyObjectError, CopyObjectParams, CopyObjectResult, DeleteObjectError, DeleteObjectResult, GetBodyPart,
-    GetObjectAttributesError, GetObjectAttributesResult, GetObjectError, GetObjectParams, GetObjectResponse,
-    HeadObjectError, HeadObjectParams, HeadObjectResult, ListObjectsError, ListObjectsResult, ObjectAttribute,
-    ObjectChecksumError, ObjectClient, ObjectClientResult, ObjectMetadata, PutObjectError, PutObjectParams,
-    PutObjectResult, PutObjectSingleParams,
+    Checksum, CopyObjectError, CopyObjectParams, CopyObjectResult, DeleteObjectError, DeleteObjectResult,
+    DeleteObjectsError, DeleteObjectsResult, GetBodyPart, GetObjectAttributesError, GetObjectAttributesResult,
+    GetObjectError, GetObjectParams, GetObjectResponse, HeadObjectError, HeadObjectParams, HeadObjectResult,
+    ListObjectsError, ListObjectsResult, ObjectAttribute, ObjectChecksumError, ObjectClient, ObjectClientResult,
+    ObjectMetadata, PutObjectError, PutObjectParams, PutObjectResult, PutObjectSingleParams,
 };
 
 use super::MockBackpressureHandle;
@@ -124,6 +124,10 @@ impl ObjectClient for ThroughputMockClient {
         self.inner.mem_usage_stats()
     }
 
+    fn region(&self) -> Option<&str> {
+        self.inner.region()
+    }
+
     async fn delete_object(
         &self,
         bucket: &str,
@@ -132,6 +136,14 @@ impl ObjectClient for ThroughputMockClient {
         self.inner.delete_object(bucket, key).await
     }
 
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError> {
+        self.inner.delete_objects(bucket, keys).await
+    }
+
     async fn copy_object(
         &self,
         source_bucket: &str,
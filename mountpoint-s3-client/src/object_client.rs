@@ -48,6 +48,10 @@ pub trait ObjectClient {
     /// does not record the stats.
     fn mem_usage_stats(&self) -> Option<BufferPoolUsageStats>;
 
+    /// Query the region this client sends requests to. This can be `None` if the client is not
+    /// bound to a region.
+    fn region(&self) -> Option<&str>;
+
     /// Delete a single object from the object store.
     ///
     /// DeleteObject will succeed even if the object within the bucket does not exist.
@@ -57,6 +61,16 @@ pub trait ObjectClient {
         key: &str,
     ) -> ObjectClientResult<DeleteObjectResult, DeleteObjectError, Self::ClientError>;
 
+    /// Delete up to [`DELETE_OBJECTS_MAX_KEYS`] objects from the object store in a single request.
+    ///
+    /// Like DeleteObject, deleting an object that does not exist succeeds. Objects that could not be
+    /// deleted are reported in [`DeleteObjectsResult::errors`] rather than failing the request.
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError>;
+
     /// Create a copy of an existing object. Currently, this functionality has the following limitations:
     /// - Supported only for copying between matching bucket types:
     ///     - Standard S3 to Standard S3 buckets.
@@ -191,6 +205,8 @@ pub struct GetObjectParams {
     pub range: Option<Range<u64>>,
     pub if_match: Option<ETag>,
     pub checksum_mode: Option<ChecksumMode>,
+    pub initial_read_window: Option<usize>,
+    pub custom_headers: Vec<(String, String)>,
 }
 
 impl GetObjectParams {
@@ -216,6 +232,21 @@ impl GetObjectParams {
         self.checksum_mode = value;
         self
     }
+
+    /// Set the initial read window of the request when backpressure is enabled, instead of the client's
+    /// `initial_read_window_size`.
+    ///
+    /// The CRT client can only grow the read window, so it uses the larger of the two values.
+    pub fn initial_read_window(mut self, value: Option<usize>) -> Self {
+        self.initial_read_window = value;
+        self
+    }
+
+    /// Add a custom header to the request.
+    pub fn add_custom_header(mut self, name: String, value: String) -> Self {
+        self.custom_headers.push((name, value));
+        self
+    }
 }
 
 /// Result of a [`list_objects`](ObjectClient::list_objects) request
@@ -332,6 +363,41 @@ pub struct DeleteObjectResult {}
 pub enum DeleteObjectError {
     #[error("The bucket does not exist")]
     NoSuchBucket,
+    #[error("The object is protected by an Object Lock retention period")]
+    ObjectLocked,
+}
+
+/// Maximum number of keys of a [`delete_objects`](ObjectClient::delete_objects) request
+pub const DELETE_OBJECTS_MAX_KEYS: usize = 1000;
+
+/// Result of a [`delete_objects`](ObjectClient::delete_objects) request
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct DeleteObjectsResult {
+    /// The objects that could not be deleted.
+    pub errors: Vec<DeleteObjectsKeyError>,
+}
+
+/// An object that a [`delete_objects`](ObjectClient::delete_objects) request could not delete
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeleteObjectsKeyError {
+    /// Key of the object.
+    pub key: String,
+    /// Error code returned for the object, e.g. `AccessDenied`.
+    pub code: String,
+    /// Error message returned for the object.
+    pub message: String,
+}
+
+/// Errors returned by a [`delete_objects`](ObjectClient::delete_objects) request
+#[derive(Debug, Error, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeleteObjectsError {
+    #[error("The bucket does not exist")]
+    NoSuchBucket,
+    #[error("Too many keys in a single request: {0}")]
+    TooManyKeys(usize),
 }
 
 /// Result of a [`copy_object`](ObjectClient::copy_object) request
@@ -407,11 +473,15 @@ pub struct PutObjectParams {
     pub trailing_checksums: PutObjectTrailingChecksums,
     /// Storage class to be used when creating new S3 object
     pub storage_class: Option<String>,
+    /// Canned ACL to apply to the new S3 object (for example, bucket-owner-full-control)
+    pub acl: Option<String>,
     /// The server-side encryption algorithm to be used for this object in Amazon S3 (for example, AES256, aws:kms, aws:kms:dsse)
     pub server_side_encryption: Option<String>,
     /// If `server_side_encryption` has a valid value of aws:kms or aws:kms:dsse, this value may be used to specify AWS KMS key ID to be used
     /// when creating new S3 object
     pub ssekms_key_id: Option<String>,
+    /// Object Lock retention to apply to the new object
+    pub object_lock_retention: Option<ObjectLockRetention>,
     /// Custom headers to add to the request
     pub custom_headers: Vec<(String, String)>,
     /// User-defined object metadata
@@ -436,6 +506,12 @@ impl PutObjectParams {
         self
     }
 
+    /// Set the canned ACL.
+    pub fn acl(mut self, value: Option<String>) -> Self {
+        self.acl = value;
+        self
+    }
+
     /// Set server-side encryption type.
     pub fn server_side_encryption(mut self, value: Option<String>) -> Self {
         self.server_side_encryption = value;
@@ -448,6 +524,12 @@ impl PutObjectParams {
         self
     }
 
+    /// Set the Object Lock retention of the new object.
+    pub fn object_lock_retention(mut self, value: Option<ObjectLockRetention>) -> Self {
+        self.object_lock_retention = value;
+        self
+    }
+
     /// Add a custom header to the request.
     pub fn add_custom_header(mut self, name: String, value: String) -> Self {
         self.custom_headers.push((name, value));
@@ -461,6 +543,37 @@ impl PutObjectParams {
     }
 }
 
+/// Object Lock retention of an object, preventing it from being deleted or overwritten until a given date.
+///
+/// The bucket must have Object Lock enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectLockRetention {
+    /// The retention mode.
+    pub mode: ObjectLockMode,
+    /// The date until which the object is retained.
+    pub retain_until_date: OffsetDateTime,
+}
+
+/// Object Lock retention mode, see
+/// <https://docs.aws.amazon.com/AmazonS3/latest/userguide/object-lock.html#object-lock-retention-modes>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectLockMode {
+    /// Users with special permissions can remove or shorten the retention.
+    Governance,
+    /// The retention cannot be removed or shortened by any user.
+    Compliance,
+}
+
+impl ObjectLockMode {
+    /// The value of the mode in S3 requests.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObjectLockMode::Governance => "GOVERNANCE",
+            ObjectLockMode::Compliance => "COMPLIANCE",
+        }
+    }
+}
+
 /// How CRC32c checksums are used for parts of a multi-part PutObject request
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum PutObjectTrailingChecksums {
@@ -490,6 +603,8 @@ pub struct PutObjectSingleParams {
     pub checksum: Option<UploadChecksum>,
     /// Storage class to be used when creating new S3 object
     pub storage_class: Option<String>,
+    /// Canned ACL to apply to the new S3 object (for example, bucket-owner-full-control)
+    pub acl: Option<String>,
     /// The server-side encryption algorithm to be used for this object in Amazon S3 (for example, AES256, aws:kms, aws:kms:dsse)
     pub server_side_encryption: Option<String>,
     /// If `server_side_encryption` has a valid value of aws:kms or aws:kms:dsse, this value may be used to specify AWS KMS key ID to be used
@@ -499,6 +614,10 @@ pub struct PutObjectSingleParams {
     pub if_match: Option<ETag>,
     /// Offset on the pre-existing object where to append the data in the request
     pub write_offset_bytes: Option<u64>,
+    /// Expected length of the data to upload. The request fails if the contents have a different length.
+    pub content_length: Option<u64>,
+    /// Object Lock retention to apply to the new object
+    pub object_lock_retention: Option<ObjectLockRetention>,
     /// Custom headers to add to the request
     pub custom_headers: Vec<(String, String)>,
     /// User-defined object metadata
@@ -528,6 +647,12 @@ impl PutObjectSingleParams {
         self
     }
 
+    /// Set the canned ACL.
+    pub fn acl(mut self, value: Option<String>) -> Self {
+        self.acl = value;
+        self
+    }
+
     /// Set server-side encryption type.
     pub fn server_side_encryption(mut self, value: Option<String>) -> Self {
         self.server_side_encryption = value;
@@ -552,6 +677,18 @@ impl PutObjectSingleParams {
         self
     }
 
+    /// Set the expected length of the data to upload.
+    pub fn content_length(mut self, value: u64) -> Self {
+        self.content_length = Some(value);
+        self
+    }
+
+    /// Set the Object Lock retention of the new object.
+    pub fn object_lock_retention(mut self, value: Option<ObjectLockRetention>) -> Self {
+        self.object_lock_retention = value;
+        self
+    }
+
     /// Add a custom header to the request.
     pub fn add_custom_header(mut self, name: String, value: String) -> Self {
         self.custom_headers.push((name, value));
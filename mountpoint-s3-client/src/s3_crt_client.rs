@@ -60,6 +60,7 @@ macro_rules! request_span {
 
 pub(crate) mod copy_object;
 pub(crate) mod delete_object;
+pub(crate) mod delete_objects;
 pub(crate) mod get_object;
 
 pub(crate) use get_object::S3GetObjectResponse;
@@ -848,6 +849,7 @@ enum ResponseHeadersError {
 #[derive(Debug, Clone, Copy)]
 enum S3Operation {
     DeleteObject,
+    DeleteObjects,
     GetObject,
     GetObjectAttributes,
     HeadBucket,
@@ -874,6 +876,7 @@ impl S3Operation {
     fn operation_name(&self) -> Option<&'static str> {
         match self {
             S3Operation::DeleteObject => Some("DeleteObject"),
+            S3Operation::DeleteObjects => Some("DeleteObjects"),
             S3Operation::GetObject => None,
             S3Operation::GetObjectAttributes => Some("GetObjectAttributes"),
             S3Operation::HeadBucket => Some("HeadBucket"),
@@ -1107,7 +1110,7 @@ pub enum S3RequestError {
 
     /// The request was throttled by S3
     #[error("Request throttled")]
-    Throttled,
+    Throttled(ClientErrorMetadata),
 
     /// Cannot fetch more data because current read window is exhausted. The read window must
     /// be advanced using [GetObjectRequest::increment_read_window(u64)] to continue fetching
@@ -1137,14 +1140,11 @@ impl ProvideErrorMetadata for S3RequestError {
                 };
                 ClientErrorMetadata {
                     http_code,
+                    retry_after: parse_retry_after(request_result),
                     ..Default::default()
                 }
             }
-            Self::Forbidden(_, metadata) => metadata.clone(),
-            Self::Throttled => ClientErrorMetadata {
-                http_code: Some(503),
-                ..Default::default()
-            },
+            Self::Forbidden(_, metadata) | Self::Throttled(metadata) => metadata.clone(),
             _ => Default::default(),
         }
     }
@@ -1218,6 +1218,14 @@ fn parse_checksum(headers: &Headers) -> Result<Checksum, HeadersError> {
     })
 }
 
+/// Parse the delay of the `Retry-After` header of a failing meta request, if given in seconds
+fn parse_retry_after(request_result: &MetaRequestResult) -> Option<Duration> {
+    let headers = request_result.error_response_headers.as_ref()?;
+    let header = headers.get("Retry-After").ok()?;
+    let seconds = header.value().to_owned().into_string().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
 /// Try to parse a modeled error out of a failing meta request
 fn try_parse_generic_error(request_result: &MetaRequestResult) -> Option<S3RequestError> {
     /// Look for a redirect header pointing to a different region for the bucket
@@ -1237,6 +1245,7 @@ fn try_parse_generic_error(request_result: &MetaRequestResult) -> Option<S3Reque
                 "<no message>".to_owned(),
                 ClientErrorMetadata {
                     http_code: Some(request_result.response_status),
+                    retry_after: parse_retry_after(request_result),
                     ..Default::default()
                 },
             ));
@@ -1262,6 +1271,7 @@ fn try_parse_generic_error(request_result: &MetaRequestResult) -> Option<S3Reque
                     http_code: Some(request_result.response_status),
                     error_code: Some(error_code_str.to_string()),
                     error_message: Some(message.into_owned()),
+                    retry_after: parse_retry_after(request_result),
                 },
             ))
         } else {
@@ -1282,7 +1292,11 @@ fn try_parse_generic_error(request_result: &MetaRequestResult) -> Option<S3Reque
     fn try_parse_throttled(request_result: &MetaRequestResult) -> Option<S3RequestError> {
         let crt_error_code = request_result.crt_error.raw_error();
         if crt_error_code == mountpoint_s3_crt::s3::ErrorCode::AWS_ERROR_S3_SLOW_DOWN as i32 {
-            Some(S3RequestError::Throttled)
+            Some(S3RequestError::Throttled(ClientErrorMetadata {
+                http_code: Some(503),
+                retry_after: parse_retry_after(request_result),
+                ..Default::default()
+            }))
         } else {
             None
         }
@@ -1356,6 +1370,10 @@ impl ObjectClient for S3CrtClient {
         Some(crt_buffer_pool_stats)
     }
 
+    fn region(&self) -> Option<&str> {
+        Some(self.inner.endpoint_config.get_region())
+    }
+
     async fn delete_object(
         &self,
         bucket: &str,
@@ -1364,6 +1382,14 @@ impl ObjectClient for S3CrtClient {
         self.delete_object(bucket, key).await
     }
 
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError> {
+        self.delete_objects(bucket, keys).await
+    }
+
     async fn copy_object(
         &self,
         source_bucket: &str,
@@ -1761,6 +1787,26 @@ mod tests {
         assert_eq!(error, error_code.into());
     }
 
+    #[test_case(Some("5"), Some(Duration::from_secs(5)); "seconds")]
+    #[test_case(Some("Wed, 21 Oct 2015 07:28:00 GMT"), None; "http date")]
+    #[test_case(None, None; "no header")]
+    fn parse_slow_down_retry_after(retry_after: Option<&str>, expected: Option<Duration>) {
+        let error_code = mountpoint_s3_crt::s3::ErrorCode::AWS_ERROR_S3_SLOW_DOWN as i32;
+        let mut result = make_crt_error_result(0, error_code.into());
+        result.error_response_headers = retry_after.map(|value| {
+            let mut headers = Headers::new(&Allocator::default()).unwrap();
+            headers.add_header(&Header::new("Retry-After", value)).unwrap();
+            headers
+        });
+        let result = try_parse_generic_error(&result);
+        let Some(error @ S3RequestError::Throttled(_)) = result else {
+            panic!("wrong result, got: {:?}", result);
+        };
+        let metadata = error.meta();
+        assert_eq!(metadata.http_code, Some(503));
+        assert_eq!(metadata.retry_after, expected);
+    }
+
     #[test]
     fn test_checksum_sha256() {
         let mut headers = Headers::new(&Allocator::default()).unwrap();
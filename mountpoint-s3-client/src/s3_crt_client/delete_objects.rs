@@ -0,0 +1,185 @@
+use std::ops::Deref;
+use std::os::unix::prelude::OsStrExt;
+
+use mountpoint_s3_crt::io::stream::InputStream;
+use mountpoint_s3_crt::s3::client::MetaRequestResult;
+use xmltree::Element;
+
+use crate::checksums::crc32c;
+use crate::object_client::{
+    DeleteObjectsError, DeleteObjectsKeyError, DeleteObjectsResult, ObjectClientError, ObjectClientResult,
+    UploadChecksum, DELETE_OBJECTS_MAX_KEYS,
+};
+
+use super::{S3CrtClient, S3Operation, S3RequestError};
+
+impl S3CrtClient {
+    /// Create and begin a new DeleteObjects request.
+    pub(super) async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, S3RequestError> {
+        if keys.len() > DELETE_OBJECTS_MAX_KEYS {
+            return Err(ObjectClientError::ServiceError(DeleteObjectsError::TooManyKeys(
+                keys.len(),
+            )));
+        }
+        if keys.is_empty() {
+            return Ok(DeleteObjectsResult::default());
+        }
+
+        let span = request_span!(self.inner, "delete_objects", bucket, keys = keys.len());
+        let body = build_request_body(keys);
+
+        // Scope the endpoint, message, etc. since otherwise rustc thinks we use Message across the await.
+        let request = {
+            let mut message = self
+                .inner
+                .new_request_template("POST", bucket)
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_request_path_and_query("/", [("delete", "")])
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_content_length_header(body.len())
+                .map_err(S3RequestError::construction_failure)?;
+            // DeleteObjects requires an integrity check of the body, which S3 Express One Zone only accepts
+            // as a checksum header rather than Content-MD5.
+            message
+                .set_checksum_header(&UploadChecksum::Crc32c(crc32c::checksum(body.as_bytes())))
+                .map_err(S3RequestError::construction_failure)?;
+
+            let body_input_stream = InputStream::new_from_slice(&self.inner.allocator, body.as_bytes())
+                .map_err(S3RequestError::CrtError)?;
+            message.set_body_stream(Some(body_input_stream));
+
+            self.inner.meta_request_with_body_payload(
+                message.into_options(S3Operation::DeleteObjects),
+                span,
+                parse_delete_objects_error,
+            )?
+        };
+
+        let response = request.await?;
+
+        parse_result_from_bytes(&response)
+            .map_err(|e| ObjectClientError::ClientError(S3RequestError::InternalError(e.into())))
+    }
+}
+
+/// Build the XML body of a quiet DeleteObjects request, to which S3 only responds with the keys that
+/// could not be deleted.
+fn build_request_body(keys: &[String]) -> String {
+    let mut body = String::from("<Delete><Quiet>true</Quiet>");
+    for key in keys {
+        body.push_str("<Object><Key>");
+        push_escaped(&mut body, key);
+        body.push_str("</Key></Object>");
+    }
+    body.push_str("</Delete>");
+    body
+}
+
+/// Append `text` to `body`, escaping the characters with a meaning in XML.
+fn push_escaped(body: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => body.push_str("&amp;"),
+            '<' => body.push_str("&lt;"),
+            '>' => body.push_str("&gt;"),
+            '"' => body.push_str("&quot;"),
+            '\'' => body.push_str("&apos;"),
+            c => body.push(c),
+        }
+    }
+}
+
+fn parse_result_from_bytes(bytes: &[u8]) -> Result<DeleteObjectsResult, xmltree::ParseError> {
+    let mut root = Element::parse(bytes)?;
+    let mut errors = Vec::new();
+    while let Some(error) = root.take_child("Error") {
+        let field = |name| {
+            error
+                .get_child(name)
+                .and_then(Element::get_text)
+                .map(|text| text.into_owned())
+                .unwrap_or_default()
+        };
+        errors.push(DeleteObjectsKeyError {
+            key: field("Key"),
+            code: field("Code"),
+            message: field("Message"),
+        });
+    }
+    Ok(DeleteObjectsResult { errors })
+}
+
+fn parse_delete_objects_error(result: &MetaRequestResult) -> Option<DeleteObjectsError> {
+    match result.response_status {
+        404 => {
+            let body = result.error_response_body.as_ref()?;
+            let root = Element::parse(body.as_bytes()).ok()?;
+            let error_code = root.get_child("Code")?;
+            let error_str = error_code.get_text()?;
+            match error_str.deref() {
+                "NoSuchBucket" => Some(DeleteObjectsError::NoSuchBucket),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{OsStr, OsString};
+
+    use super::*;
+
+    fn make_result(response_status: i32, body: impl Into<OsString>) -> MetaRequestResult {
+        MetaRequestResult {
+            response_status,
+            crt_error: 1i32.into(),
+            error_response_headers: None,
+            error_response_body: Some(body.into()),
+        }
+    }
+
+    #[test]
+    fn parse_404_no_such_bucket() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><Error><Code>NoSuchBucket</Code><Message>The specified bucket does not exist</Message><BucketName>djonesoa-nosuchbucket</BucketName><RequestId>BHCQ0FTYY0HKMV43</RequestId><HostId>ntCK1jQfPxY7sSNL/GB13RttgJLjSETfIuOiuRnwImO0dQP2ttj2Qqpn5S/jSLt3Ql0TgHWuYF0=</HostId></Error>"#;
+        let result = make_result(404, OsStr::from_bytes(&body[..]));
+        let result = parse_delete_objects_error(&result);
+        assert_eq!(result, Some(DeleteObjectsError::NoSuchBucket));
+    }
+
+    #[test]
+    fn parse_result_with_errors() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><DeleteResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Error><Key>locked</Key><Code>AccessDenied</Code><Message>Access Denied because object protected by object lock.</Message></Error><Error><Key>other</Key><Code>InternalError</Code><Message>We encountered an internal error. Please try again.</Message></Error></DeleteResult>"#;
+        let result = parse_result_from_bytes(body).expect("response should parse");
+        let keys: Vec<_> = result.errors.iter().map(|error| error.key.as_str()).collect();
+        assert_eq!(keys, ["locked", "other"]);
+        assert_eq!(result.errors[0].code, "AccessDenied");
+        assert_eq!(
+            result.errors[1].message,
+            "We encountered an internal error. Please try again."
+        );
+    }
+
+    #[test]
+    fn parse_result_without_errors() {
+        let body = br#"<?xml version="1.0" encoding="UTF-8"?><DeleteResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"></DeleteResult>"#;
+        let result = parse_result_from_bytes(body).expect("response should parse");
+        assert!(result.errors.is_empty());
+    }
+
+    #[test]
+    fn escape_keys_in_request_body() {
+        let body = build_request_body(&["a&b".to_string(), "<c>".to_string()]);
+        assert_eq!(
+            body,
+            "<Delete><Quiet>true</Quiet><Object><Key>a&amp;b</Key></Object><Object><Key>&lt;c&gt;</Key></Object></Delete>"
+        );
+    }
+}
@@ -36,7 +36,7 @@ impl S3CrtClient {
         let requested_checksums = params.checksum_mode.as_ref() == Some(&ChecksumMode::Enabled);
         let next_offset = params.range.as_ref().map(|r| r.start).unwrap_or(0);
         let (event_sender, mut event_receiver) = futures::channel::mpsc::unbounded();
-        let meta_request = {
+        let mut meta_request = {
             let span =
                 request_span!(self.inner, "get_object", bucket, key, range=?params.range, if_match=?params.if_match);
 
@@ -72,6 +72,13 @@ impl S3CrtClient {
                     .map_err(S3RequestError::construction_failure)?;
             }
 
+            for (name, value) in &params.custom_headers {
+                message
+                    .inner
+                    .add_header(&Header::new(name, value))
+                    .map_err(S3RequestError::construction_failure)?;
+            }
+
             let key = format!("/{key}");
             message
                 .set_request_path(key)
@@ -110,6 +117,16 @@ impl S3CrtClient {
             )?
         };
 
+        // The CRT only supports a client-wide initial read window, so grow it for this request if needed.
+        let initial_read_window = params
+            .initial_read_window
+            .map_or(self.inner.initial_read_window_size, |window| {
+                window.max(self.inner.initial_read_window_size)
+            });
+        if self.inner.enable_backpressure && initial_read_window > self.inner.initial_read_window_size {
+            meta_request.increment_read_window((initial_read_window - self.inner.initial_read_window_size) as u64);
+        }
+
         let headers = match event_receiver.next().await {
             Some(S3GetObjectEvent::Headers(headers)) => headers,
             Some(S3GetObjectEvent::Error(e)) => {
@@ -123,8 +140,7 @@ impl S3CrtClient {
         };
 
         let backpressure_handle = if self.inner.enable_backpressure {
-            let read_window_end_offset =
-                Arc::new(AtomicU64::new(next_offset + self.inner.initial_read_window_size as u64));
+            let read_window_end_offset = Arc::new(AtomicU64::new(next_offset + initial_read_window as u64));
             Some(S3BackpressureHandle {
                 read_window_end_offset,
                 meta_request: meta_request.clone(),
@@ -10,11 +10,13 @@ use mountpoint_s3_crt::http::request_response::{Header, Headers, HeadersError};
 use mountpoint_s3_crt::io::stream::InputStream;
 use mountpoint_s3_crt::s3::client::{ChecksumConfig, MetaRequestResult, RequestType, UploadReview};
 use thiserror::Error;
+use time::format_description::well_known::Rfc3339;
 use tracing::error;
 use xmltree::Element;
 
 use crate::object_client::{
-    ObjectClientResult, PutObjectError, PutObjectParams, PutObjectRequest, PutObjectResult, PutObjectSingleParams,
+    ObjectClientResult, ObjectLockRetention, PutObjectError, PutObjectParams, PutObjectRequest, PutObjectResult,
+    PutObjectSingleParams,
 };
 
 use super::{
@@ -43,9 +45,13 @@ impl S3CrtClient {
             let mut message = self.new_put_request(
                 bucket,
                 key,
-                params.storage_class.as_deref(),
-                params.server_side_encryption.as_deref(),
-                params.ssekms_key_id.as_deref(),
+                PutObjectHeaders {
+                    storage_class: params.storage_class.as_deref(),
+                    acl: params.acl.as_deref(),
+                    server_side_encryption: params.server_side_encryption.as_deref(),
+                    ssekms_key_id: params.ssekms_key_id.as_deref(),
+                    object_lock_retention: params.object_lock_retention.as_ref(),
+                },
             )?;
 
             let checksum_config = match params.trailing_checksums {
@@ -134,13 +140,20 @@ impl S3CrtClient {
 
         let slice = contents.as_ref();
         let content_length = slice.len();
+        if params.content_length.is_some_and(|len| len != content_length as u64) {
+            return Err(S3RequestError::InternalError("content length does not match the data".into()).into());
+        }
         let request = {
             let mut message = self.new_put_request(
                 bucket,
                 key,
-                params.storage_class.as_deref(),
-                params.server_side_encryption.as_deref(),
-                params.ssekms_key_id.as_deref(),
+                PutObjectHeaders {
+                    storage_class: params.storage_class.as_deref(),
+                    acl: params.acl.as_deref(),
+                    server_side_encryption: params.server_side_encryption.as_deref(),
+                    ssekms_key_id: params.ssekms_key_id.as_deref(),
+                    object_lock_retention: params.object_lock_retention.as_ref(),
+                },
             )?;
             message
                 .set_content_length_header(content_length)
@@ -195,9 +208,7 @@ impl S3CrtClient {
         &self,
         bucket: &str,
         key: &str,
-        storage_class: Option<&str>,
-        server_side_encryption: Option<&str>,
-        ssekms_key_id: Option<&str>,
+        headers: PutObjectHeaders<'_>,
     ) -> Result<S3Message<'_>, S3RequestError> {
         let mut message = self
             .inner
@@ -209,27 +220,55 @@ impl S3CrtClient {
             .set_request_path(&key)
             .map_err(S3RequestError::construction_failure)?;
 
-        if let Some(storage_class) = storage_class {
+        if let Some(storage_class) = headers.storage_class {
             message
                 .set_header(&Header::new("x-amz-storage-class", storage_class))
                 .map_err(S3RequestError::construction_failure)?;
         }
 
-        if let Some(sse) = server_side_encryption {
+        if let Some(acl) = headers.acl {
+            message
+                .set_header(&Header::new("x-amz-acl", acl))
+                .map_err(S3RequestError::construction_failure)?;
+        }
+
+        if let Some(sse) = headers.server_side_encryption {
             message
                 .set_header(&Header::new(SSE_TYPE_HEADER_NAME, sse))
                 .map_err(S3RequestError::construction_failure)?;
         }
-        if let Some(key_id) = ssekms_key_id {
+        if let Some(key_id) = headers.ssekms_key_id {
             message
                 .set_header(&Header::new(SSE_KEY_ID_HEADER_NAME, key_id))
                 .map_err(S3RequestError::construction_failure)?;
         }
 
+        if let Some(retention) = headers.object_lock_retention {
+            let retain_until_date = retention
+                .retain_until_date
+                .format(&Rfc3339)
+                .map_err(S3RequestError::internal_failure)?;
+            message
+                .set_header(&Header::new("x-amz-object-lock-mode", retention.mode.as_str()))
+                .map_err(S3RequestError::construction_failure)?;
+            message
+                .set_header(&Header::new("x-amz-object-lock-retain-until-date", retain_until_date))
+                .map_err(S3RequestError::construction_failure)?;
+        }
+
         Ok(message)
     }
 }
 
+/// Headers shared by the requests of [S3CrtClient::put_object] and [S3CrtClient::put_object_single].
+struct PutObjectHeaders<'a> {
+    storage_class: Option<&'a str>,
+    acl: Option<&'a str>,
+    server_side_encryption: Option<&'a str>,
+    ssekms_key_id: Option<&'a str>,
+    object_lock_retention: Option<&'a ObjectLockRetention>,
+}
+
 type ReviewCallback = dyn FnOnce(UploadReview) -> bool + Send;
 
 /// Holder for the upload review callback.
@@ -0,0 +1,63 @@
+#![cfg(feature = "s3_tests")]
+
+pub mod common;
+
+use aws_sdk_s3::primitives::ByteStream;
+use bytes::Bytes;
+use common::*;
+use mountpoint_s3_client::error::{DeleteObjectsError, ObjectClientError};
+use mountpoint_s3_client::{ObjectClient, S3CrtClient};
+
+#[tokio::test]
+async fn test_delete_objects() {
+    let sdk_client = get_test_sdk_client().await;
+    let (bucket, prefix) = get_test_bucket_and_prefix("test_delete_objects");
+
+    let keys: Vec<_> = (0..3).map(|i| format!("{prefix}/hello{i}")).collect();
+    for key in &keys {
+        sdk_client
+            .put_object()
+            .bucket(&bucket)
+            .key(key)
+            .body(ByteStream::from(Bytes::from_static(b"hello world!")))
+            .send()
+            .await
+            .unwrap();
+    }
+    // Deleting a missing object does not fail the request.
+    let mut request_keys = keys.clone();
+    request_keys.push(format!("{prefix}/nonexistent_key"));
+
+    let client: S3CrtClient = get_test_client();
+    let result = client
+        .delete_objects(&bucket, &request_keys)
+        .await
+        .expect("delete_objects should succeed");
+    assert!(result.errors.is_empty(), "no key should fail: {:?}", result.errors);
+
+    for key in &keys {
+        let head_obj_err = sdk_client
+            .head_object()
+            .bucket(&bucket)
+            .key(key)
+            .send()
+            .await
+            .expect_err("object should not exist");
+        assert!(head_obj_err.into_service_error().is_not_found());
+    }
+}
+
+#[tokio::test]
+async fn test_delete_objects_404_bucket() {
+    let (_bucket, prefix) = get_test_bucket_and_prefix("test_delete_objects_404_bucket");
+
+    let keys = vec![format!("{prefix}/nonexistent_key")];
+
+    let client: S3CrtClient = get_test_client();
+
+    let result = client.delete_objects("amzn-s3-demo-bucket", &keys).await;
+    assert!(matches!(
+        result,
+        Err(ObjectClientError::ServiceError(DeleteObjectsError::NoSuchBucket))
+    ));
+}
@@ -28,8 +28,8 @@ use regex::Regex;
 use sysinfo::{RefreshKind, System};
 
 use crate::data_cache::{
-    CacheLimit, DiskDataCache, DiskDataCacheConfig, ExpressDataCache, ExpressDataCacheConfig, ManagedCacheDir,
-    MultilevelDataCache,
+    BlockSizeMismatchPolicy, CacheLimit, DiskDataCache, DiskDataCacheConfig, ExpressDataCache, ExpressDataCacheConfig,
+    ManagedCacheDir, MultilevelDataCache,
 };
 use crate::fs::{CacheConfig, ServerSideEncryption, TimeToLive};
 use crate::fuse::session::FuseSession;
@@ -497,6 +497,8 @@ impl CliArgs {
                 let cache_config = DiskDataCacheConfig {
                     block_size: self.cache_block_size_in_bytes(),
                     limit: cache_limit,
+                    min_free_bytes: 0,
+                    block_size_mismatch: BlockSizeMismatchPolicy::Fail,
                 };
                 Some((cache_config, path.as_path()))
             }
@@ -870,7 +872,8 @@ fn create_disk_cache(
     let managed_cache_dir = ManagedCacheDir::new_from_parent_with_cache_key(cache_dir_path, cache_key)
         .context("failed to create cache directory")?;
     let cache_dir_path = managed_cache_dir.as_path_buf();
-    Ok((managed_cache_dir, DiskDataCache::new(cache_dir_path, cache_config)))
+    let cache = DiskDataCache::open(cache_dir_path, cache_config).context("failed to open disk cache")?;
+    Ok((managed_cache_dir, cache))
 }
 
 fn mount<ClientBuilder, Client, Runtime>(
@@ -969,7 +972,8 @@ where
     match (args.disk_data_cache_config(), args.express_data_cache_config()) {
         (None, Some((config, bucket_name, cache_bucket_name))) => {
             tracing::trace!("using S3 Express One Zone bucket as a cache for object content");
-            let express_cache = ExpressDataCache::new(client.clone(), config, bucket_name, cache_bucket_name);
+            let express_cache = ExpressDataCache::try_new(client.clone(), config, bucket_name, cache_bucket_name)
+                .context("invalid configuration of the shared cache")?;
             block_on(express_cache.verify_cache_valid())
                 .with_context(|| format!("initial PutObject failed for shared cache bucket {cache_bucket_name}"))?;
 
@@ -1006,7 +1010,8 @@ where
         (Some((disk_data_cache_config, cache_dir_path)), Some((config, bucket_name, cache_bucket_name))) => {
             tracing::trace!("using both local disk and S3 Express One Zone bucket as a cache for object content");
             let (managed_cache_dir, disk_cache) = create_disk_cache(cache_dir_path, disk_data_cache_config)?;
-            let express_cache = ExpressDataCache::new(client.clone(), config, bucket_name, cache_bucket_name);
+            let express_cache = ExpressDataCache::try_new(client.clone(), config, bucket_name, cache_bucket_name)
+                .context("invalid configuration of the shared cache")?;
             block_on(express_cache.verify_cache_valid())
                 .with_context(|| format!("initial PutObject failed for shared cache bucket {cache_bucket_name}"))?;
             let cache = MultilevelDataCache::new(Arc::new(disk_cache), express_cache, runtime.clone());
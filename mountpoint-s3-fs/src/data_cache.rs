@@ -4,21 +4,53 @@
 //! reducing both the number of requests as well as the latency for the reads.
 //! Ultimately, this means reduced cost in terms of S3 billing as well as compute time.
 
+mod blocking_data_cache;
 mod cache_directory;
+#[cfg(test)]
+pub(crate) mod cancellation;
+mod compression;
+#[cfg(test)]
+pub(crate) mod conformance;
 mod disk_data_cache;
 mod express_data_cache;
+#[cfg(test)]
+pub(crate) mod hook_client;
 mod in_memory_data_cache;
+mod lazy_block;
 mod multilevel_cache;
+mod priority_gate;
+mod rate_limiter;
+mod retry;
+mod swappable_data_cache;
+mod write_back_cache;
+
+use std::sync::Arc;
+use std::time::Instant;
 
 use async_trait::async_trait;
+use futures::future::{select, Either};
+use futures::pin_mut;
 use thiserror::Error;
 
 pub use crate::checksums::ChecksummedBytes;
+pub use crate::data_cache::blocking_data_cache::BlockingDataCache;
 pub use crate::data_cache::cache_directory::ManagedCacheDir;
-pub use crate::data_cache::disk_data_cache::{CacheLimit, DiskDataCache, DiskDataCacheConfig};
-pub use crate::data_cache::express_data_cache::{build_prefix, get_s3_key, ExpressDataCache, ExpressDataCacheConfig};
-pub use crate::data_cache::in_memory_data_cache::InMemoryDataCache;
+pub use crate::data_cache::disk_data_cache::{BlockSizeMismatchPolicy, CacheLimit, DiskDataCache, DiskDataCacheConfig};
+pub use crate::data_cache::express_data_cache::{
+    block_content_hash, build_prefix, get_s3_key, get_s3_key_prefix_for_object, get_s3_key_with_content_hash,
+    with_correlation_id, BlockChecksumAlgorithm, BlockCompression, BlockRetention, BucketNamePolicy, ChecksumLocation,
+    ChecksumMismatchPolicy, Clock, CompressionAlgorithm, CompressionDictionary, CompressionNegotiation,
+    CredentialsCheck, DetailedBlock, ExpressBlockWriter, ExpressDataCache, ExpressDataCacheConfig, FillCostEstimate,
+    InflightCounts, KeyEncoding, KeyFormat, KeyHashAlgorithm, KeySalt, LocalMirror, OriginSource, PutMode,
+    RegionMismatchPolicy, SystemClock, Tier, TierPolicy, VerifyReport, WarmReport, DEFAULT_GET_PRIORITY,
+};
+pub use crate::data_cache::in_memory_data_cache::{CacheState, InMemoryDataCache};
+pub use crate::data_cache::lazy_block::LazyBlock;
 pub use crate::data_cache::multilevel_cache::MultilevelDataCache;
+pub use crate::data_cache::priority_gate::AdaptiveConcurrency;
+pub use crate::data_cache::retry::RetryDecision;
+pub use crate::data_cache::swappable_data_cache::SwappableDataCache;
+pub use crate::data_cache::write_back_cache::WriteBackDataCache;
 
 use crate::object::ObjectId;
 
@@ -40,17 +72,29 @@ pub enum DataCacheError {
     InvalidBlockOffset,
     #[error("Error while trying to evict cache content")]
     EvictionFailure,
+    #[error("Block is larger than the maximum size of {0} bytes")]
+    BlockTooLarge(u64),
+    #[error("Blocking cache operation called from an async context")]
+    CalledFromAsyncContext,
+    #[error("Cache configuration is not valid: {0}")]
+    InvalidCacheConfig(String),
 }
 
 impl DataCacheError {
-    fn reason(&self) -> &'static str {
+    /// Stable identifier of the kind of error, for use as a metric label.
+    ///
+    /// Unlike the error message, codes do not change between releases.
+    pub fn code(&self) -> &'static str {
         match self {
             DataCacheError::IoFailure(_) => "io_failure",
             DataCacheError::InvalidBlockHeader(_) => "invalid_block_header",
-            DataCacheError::InvalidBlockChecksum => "invalid_block_checksum",
+            DataCacheError::InvalidBlockChecksum => "checksum_mismatch",
             DataCacheError::InvalidBlockContent => "invalid_block_content",
             DataCacheError::InvalidBlockOffset => "invalid_block_offset",
             DataCacheError::EvictionFailure => "eviction_failure",
+            DataCacheError::BlockTooLarge(_) => "block_too_large",
+            DataCacheError::CalledFromAsyncContext => "called_from_async_context",
+            DataCacheError::InvalidCacheConfig(_) => "invalid_cache_config",
         }
     }
 }
@@ -59,6 +103,9 @@ pub type DataCacheResult<Value> = Result<Value, DataCacheError>;
 
 /// Data cache for fixed-size checksummed buffers.
 ///
+/// The trait is object safe, so a backend chosen at runtime can be stored as
+/// `Arc<dyn DataCache + Send + Sync>`, which implements [DataCache] itself.
+///
 /// TODO: Deletion and eviction of cache entries.
 /// TODO: Some version information (ETag) independent from [ObjectId] to allow smarter eviction?
 #[async_trait]
@@ -74,6 +121,44 @@ pub trait DataCache {
         object_size: usize,
     ) -> DataCacheResult<Option<ChecksummedBytes>>;
 
+    /// Get block of data from the cache, giving up if it is not available by `deadline`.
+    ///
+    /// A block that could not be retrieved in time is reported as a miss ([None]) rather than an error,
+    /// so that the caller can fall back to the source without further delay.
+    async fn get_block_with_deadline(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+        deadline: Instant,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        let get_block = self.get_block(cache_key, block_idx, block_offset, object_size);
+        let timer = async_io::Timer::at(deadline);
+        pin_mut!(timer);
+        match select(get_block, timer).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => {
+                metrics::counter!("data_cache.deadline_exceeded", "cache" => self.backend_name()).increment(1);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Get a handle to a block of data in the cache, which is only fetched when first accessed.
+    fn get_block_lazy(
+        &self,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+    ) -> LazyBlock<'_, Self>
+    where
+        Self: Sized,
+    {
+        LazyBlock::new(self, cache_key, block_idx, block_offset, object_size)
+    }
+
     /// Put block of data to the cache for the given [ObjectId] and [BlockIndex].
     async fn put_block(
         &self,
@@ -86,4 +171,216 @@ pub trait DataCache {
 
     /// Returns the block size for the data cache.
     fn block_size(&self) -> u64;
+
+    /// Returns the largest block size the cache backend supports, to validate the configured block
+    /// size against at startup.
+    fn max_block_size(&self) -> u64;
+
+    /// Returns the name of the cache backend, e.g. `"disk"`, for logs and metric labels.
+    fn backend_name(&self) -> &'static str;
+
+    /// Returns whether the cache can efficiently read a range within a block,
+    /// instead of having to fetch the full block and slice it.
+    fn supports_ranged_reads(&self) -> bool {
+        false
+    }
+}
+
+#[async_trait]
+impl<Cache> DataCache for Arc<Cache>
+where
+    Cache: DataCache + Send + Sync + ?Sized,
+{
+    async fn get_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        (**self)
+            .get_block(cache_key, block_idx, block_offset, object_size)
+            .await
+    }
+
+    async fn put_block(
+        &self,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        bytes: ChecksummedBytes,
+        object_size: usize,
+    ) -> DataCacheResult<()> {
+        (**self)
+            .put_block(cache_key, block_idx, block_offset, bytes, object_size)
+            .await
+    }
+
+    fn block_size(&self) -> u64 {
+        (**self).block_size()
+    }
+
+    fn max_block_size(&self) -> u64 {
+        (**self).max_block_size()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        (**self).backend_name()
+    }
+
+    fn supports_ranged_reads(&self) -> bool {
+        (**self).supports_ranged_reads()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockClientError};
+    use mountpoint_s3_client::types::ETag;
+
+    use super::hook_client::{HookClient, HookedRequest, RequestHook};
+
+    const BLOCK_SIZE: u64 = 1024 * 1024;
+
+    async fn put_get(cache: &impl DataCache, cache_key: &ObjectId, data: &ChecksummedBytes) -> ChecksummedBytes {
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        cache
+            .get_block(cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned")
+    }
+
+    /// Hook delaying the GetObject requests of a [HookClient].
+    struct GetLatency {
+        delay: Arc<Mutex<Duration>>,
+    }
+
+    #[async_trait]
+    impl RequestHook<MockClientError> for GetLatency {
+        async fn before(&self, request: &HookedRequest<'_>) -> Result<(), MockClientError> {
+            if let HookedRequest::GetObject { .. } = request {
+                let delay = *self.delay.lock().unwrap();
+                async_io::Timer::after(delay).await;
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_block_with_deadline() {
+        let bucket = "test-bucket";
+        let client = MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * 1024 * 1024,
+            ..Default::default()
+        });
+        let delay = Arc::new(Mutex::new(Duration::from_secs(30)));
+        let hook = GetLatency { delay: delay.clone() };
+        let cache = ExpressDataCache::try_new(
+            HookClient::new(client, hook),
+            Default::default(),
+            "unique source description",
+            bucket,
+        )
+        .expect("cache config should be valid");
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        let start = Instant::now();
+        let block = cache
+            .get_block_with_deadline(&cache_key, 0, 0, data.len(), start + Duration::from_millis(50))
+            .await
+            .expect("deadline should not be an error");
+        assert!(block.is_none(), "block should be reported as a miss");
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "should not wait for the cache bucket"
+        );
+
+        *delay.lock().unwrap() = Duration::from_millis(1);
+        let block = cache
+            .get_block_with_deadline(&cache_key, 0, 0, data.len(), Instant::now() + Duration::from_secs(30))
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let errors = [
+            (DataCacheError::IoFailure(anyhow::anyhow!("error")), "io_failure"),
+            (
+                DataCacheError::InvalidBlockHeader("error".to_owned()),
+                "invalid_block_header",
+            ),
+            (DataCacheError::InvalidBlockChecksum, "checksum_mismatch"),
+            (DataCacheError::InvalidBlockContent, "invalid_block_content"),
+            (DataCacheError::InvalidBlockOffset, "invalid_block_offset"),
+            (DataCacheError::EvictionFailure, "eviction_failure"),
+            (DataCacheError::BlockTooLarge(1024), "block_too_large"),
+            (DataCacheError::CalledFromAsyncContext, "called_from_async_context"),
+            (
+                DataCacheError::InvalidCacheConfig("error".to_owned()),
+                "invalid_cache_config",
+            ),
+        ];
+        for (error, code) in errors {
+            assert_eq!(error.code(), code, "unexpected code for {error:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dyn_data_cache() {
+        let cache_directory = tempfile::tempdir().unwrap();
+        let disk_cache = DiskDataCache::new(
+            cache_directory.path().to_path_buf(),
+            DiskDataCacheConfig {
+                block_size: BLOCK_SIZE,
+                limit: CacheLimit::Unbounded,
+                min_free_bytes: 0,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
+            },
+        );
+        let bucket = "test-bucket";
+        let client = MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * 1024 * 1024,
+            enable_backpressure: true,
+            initial_read_window_size: 8 * 1024 * 1024,
+            ..Default::default()
+        });
+        let express_cache = ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let caches: Vec<Arc<dyn DataCache + Send + Sync>> = vec![
+            Arc::new(InMemoryDataCache::new(BLOCK_SIZE)),
+            Arc::new(disk_cache),
+            Arc::new(express_cache),
+        ];
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        for cache in &caches {
+            assert_eq!(cache.block_size(), BLOCK_SIZE);
+            let entry = put_get(cache, &cache_key, &data).await;
+            assert_eq!(data, entry);
+        }
+        let ranged_reads: Vec<_> = caches.iter().map(|cache| cache.supports_ranged_reads()).collect();
+        assert_eq!(ranged_reads, [false, false, false]);
+        let backend_names: Vec<_> = caches.iter().map(|cache| cache.backend_name()).collect();
+        assert_eq!(backend_names, ["memory", "disk", "express"]);
+    }
 }
@@ -0,0 +1,143 @@
+//! Module for a blocking interface to a [DataCache].
+
+use std::future::Future;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use futures::executor::{block_on, enter};
+use futures::task::Spawn;
+
+use crate::async_util::BoxRuntime;
+use crate::object::ObjectId;
+
+use super::{BlockIndex, ChecksummedBytes, DataCache, DataCacheError, DataCacheResult};
+
+/// Wrapper exposing the operations of a [DataCache] as blocking functions.
+///
+/// Operations are spawned on the given runtime and the calling thread blocks until they complete.
+/// Calls from a thread already running a `futures` executor, e.g. from within
+/// [block_on](futures::executor::block_on) or a task of the `futures` thread pool used by
+/// Mountpoint, fail with [DataCacheError::CalledFromAsyncContext], since blocking such a thread
+/// can deadlock it. Only `futures` executors are detected: calls from the worker threads of other
+/// runtimes, such as Tokio, are not rejected and must be avoided by the caller, e.g. with
+/// `tokio::task::spawn_blocking`.
+pub struct BlockingDataCache<Cache> {
+    cache: Arc<Cache>,
+    runtime: BoxRuntime,
+}
+
+impl<Cache> BlockingDataCache<Cache>
+where
+    Cache: DataCache + Send + Sync + 'static,
+{
+    /// Create a new instance wrapping `cache`.
+    pub fn new(cache: Cache, runtime: impl Spawn + Sync + Send + 'static) -> Self {
+        Self {
+            cache: Arc::new(cache),
+            runtime: BoxRuntime::new(runtime),
+        }
+    }
+
+    /// Get block of data from the cache, see [DataCache::get_block].
+    pub fn get_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        let cache = self.cache.clone();
+        let cache_key = cache_key.clone();
+        self.run(async move { cache.get_block(&cache_key, block_idx, block_offset, object_size).await })
+    }
+
+    /// Put block of data to the cache, see [DataCache::put_block].
+    pub fn put_block(
+        &self,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        bytes: ChecksummedBytes,
+        object_size: usize,
+    ) -> DataCacheResult<()> {
+        let cache = self.cache.clone();
+        self.run(async move {
+            cache
+                .put_block(cache_key, block_idx, block_offset, bytes, object_size)
+                .await
+        })
+    }
+
+    /// Returns the block size for the data cache.
+    pub fn block_size(&self) -> u64 {
+        self.cache.block_size()
+    }
+
+    /// Spawn `future` on the runtime and block until it completes, failing if the current thread is
+    /// running a `futures` executor.
+    fn run<T, F>(&self, future: F) -> DataCacheResult<T>
+    where
+        T: Send + 'static,
+        F: Future<Output = DataCacheResult<T>> + Send + 'static,
+    {
+        let in_executor = enter().is_err();
+        if in_executor {
+            return Err(DataCacheError::CalledFromAsyncContext);
+        }
+        let result = self
+            .runtime
+            .spawn_with_result(future)
+            .map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        block_on(result.into_inner())?.ok_or_else(|| DataCacheError::IoFailure(anyhow!("cache task was dropped")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::ThreadPool;
+    use mountpoint_s3_client::types::ETag;
+
+    use crate::data_cache::InMemoryDataCache;
+
+    fn new_blocking_cache() -> BlockingDataCache<InMemoryDataCache> {
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        BlockingDataCache::new(InMemoryDataCache::new(1024), runtime)
+    }
+
+    #[test]
+    fn test_blocking_put_get() {
+        let cache = Arc::new(new_blocking_cache());
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+
+        let thread = std::thread::spawn({
+            let cache = cache.clone();
+            let data = data.clone();
+            move || {
+                assert_eq!(cache.block_size(), 1024);
+                let block = cache
+                    .get_block(&cache_key, 0, 0, data.len())
+                    .expect("cache should be accessible");
+                assert!(block.is_none());
+                cache
+                    .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+                    .expect("put should succeed");
+                cache
+                    .get_block(&cache_key, 0, 0, data.len())
+                    .expect("cache should be accessible")
+            }
+        });
+        let block = thread.join().expect("thread should not panic");
+        assert_eq!(block, Some(data));
+    }
+
+    #[test]
+    fn test_blocking_in_async_context() {
+        let cache = new_blocking_cache();
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let result = block_on(async { cache.get_block(&cache_key, 0, 0, 3) });
+        assert!(matches!(result, Err(DataCacheError::CalledFromAsyncContext)));
+    }
+}
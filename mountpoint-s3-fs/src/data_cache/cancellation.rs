@@ -0,0 +1,67 @@
+//! Harness dropping cache futures at arbitrary await points, to check that cancelled operations do not
+//! leak resources.
+//!
+//! [run_with_cancellation] polls each future a random number of times, chosen from a seed so that failures
+//! can be reproduced, before dropping it. Wrapping the client in a
+//! [HookClient](super::hook_client::HookClient) with the [YieldBeforeRequest] hook adds an
+//! await point before every request, so that futures are dropped between any two requests.
+
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+
+use super::hook_client::{HookedRequest, RequestHook};
+use async_trait::async_trait;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+/// Run `iterations` futures created by `make_future`, dropping each after a number of polls chosen by the
+/// `seed`, up to `max_polls`.
+///
+/// `check` is called after each future was dropped or completed, with the iteration and whether the future
+/// completed, and should assert that no resource was leaked.
+pub fn run_with_cancellation<Fut: Future>(
+    seed: u64,
+    iterations: usize,
+    max_polls: usize,
+    mut make_future: impl FnMut(usize) -> Fut,
+    mut check: impl FnMut(usize, bool),
+) {
+    let mut rng = ChaCha20Rng::seed_from_u64(seed);
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    for iteration in 0..iterations {
+        let polls = rng.gen_range(0..=max_polls);
+        let completed = {
+            let mut future = pin!(make_future(iteration));
+            (0..polls).any(|_| future.as_mut().poll(&mut cx).is_ready())
+        };
+        check(iteration, completed);
+    }
+}
+
+/// Future returning [Poll::Pending] once before completing.
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Hook yielding to the executor before each request of a client.
+pub struct YieldBeforeRequest;
+
+#[async_trait]
+impl<ClientError: Send + Sync + 'static> RequestHook<ClientError> for YieldBeforeRequest {
+    async fn before(&self, _request: &HookedRequest<'_>) -> Result<(), ClientError> {
+        YieldNow(false).await;
+        Ok(())
+    }
+}
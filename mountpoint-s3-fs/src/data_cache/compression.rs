@@ -0,0 +1,103 @@
+//! Module for the codecs of the blocks compressed by [ExpressDataCache](super::ExpressDataCache), see
+//! [ExpressDataCacheConfig::compression](super::ExpressDataCacheConfig::compression).
+//!
+//! The codecs are only built with the `compression` feature. Without it, blocks cannot be compressed and
+//! compressed blocks fail to decompress, so caches are rejected if configured to use them.
+
+/// Whether the codecs are available.
+pub(super) const COMPRESSION_ENABLED: bool = cfg!(feature = "compression");
+
+#[cfg(feature = "compression")]
+mod codecs {
+    use std::io::{self, Read};
+
+    /// Zstd compression level used when none is configured.
+    pub(in super::super) const ZSTD_DEFAULT_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+    pub(in super::super) fn zstd_compress(data: &[u8], level: i32, dictionary: Option<&[u8]>) -> io::Result<Vec<u8>> {
+        let mut compressor = match dictionary {
+            Some(dictionary) => zstd::bulk::Compressor::with_dictionary(level, dictionary)?,
+            None => zstd::bulk::Compressor::new(level)?,
+        };
+        compressor.compress(data)
+    }
+
+    pub(in super::super) fn zstd_decompress(
+        data: &[u8],
+        capacity: usize,
+        dictionary: Option<&[u8]>,
+    ) -> io::Result<Vec<u8>> {
+        let mut decompressor = match dictionary {
+            Some(dictionary) => zstd::bulk::Decompressor::with_dictionary(dictionary)?,
+            None => zstd::bulk::Decompressor::new()?,
+        };
+        decompressor.decompress(data, capacity)
+    }
+
+    /// Compress `data` with LZ4, prefixed with its size as 4 little-endian bytes.
+    pub(in super::super) fn lz4_compress(data: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(lz4_flex::compress_prepend_size(data))
+    }
+
+    /// Decompress data compressed by [lz4_compress].
+    pub(in super::super) fn lz4_decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+        lz4_flex::decompress_size_prepended(data).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Decompress a single gzip member, failing if its CRC32 or size do not match or it is larger than `capacity`.
+    pub(in super::super) fn gzip_decompress(data: &[u8], capacity: usize) -> io::Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(data)
+            .take(capacity as u64 + 1)
+            .read_to_end(&mut decompressed)?;
+        if decompressed.len() > capacity {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "gzip member is too large"));
+        }
+        Ok(decompressed)
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+mod codecs {
+    use std::io;
+
+    /// Zstd's default compression level.
+    pub(in super::super) const ZSTD_DEFAULT_LEVEL: i32 = 3;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "block compression requires the compression feature",
+        )
+    }
+
+    pub(in super::super) fn zstd_compress(
+        _data: &[u8],
+        _level: i32,
+        _dictionary: Option<&[u8]>,
+    ) -> io::Result<Vec<u8>> {
+        Err(unsupported())
+    }
+
+    pub(in super::super) fn zstd_decompress(
+        _data: &[u8],
+        _capacity: usize,
+        _dictionary: Option<&[u8]>,
+    ) -> io::Result<Vec<u8>> {
+        Err(unsupported())
+    }
+
+    pub(in super::super) fn lz4_compress(_data: &[u8]) -> io::Result<Vec<u8>> {
+        Err(unsupported())
+    }
+
+    pub(in super::super) fn lz4_decompress(_data: &[u8]) -> io::Result<Vec<u8>> {
+        Err(unsupported())
+    }
+
+    pub(in super::super) fn gzip_decompress(_data: &[u8], _capacity: usize) -> io::Result<Vec<u8>> {
+        Err(unsupported())
+    }
+}
+
+pub(super) use codecs::*;
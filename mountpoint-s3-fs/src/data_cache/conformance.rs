@@ -0,0 +1,105 @@
+//! Conformance tests for [DataCache] implementations.
+//!
+//! [run_data_cache_conformance] checks the behavior every cache backend is expected to provide,
+//! so that a new backend, or [ExpressDataCache](super::ExpressDataCache) over a new
+//! [ObjectClient](mountpoint_s3_client::ObjectClient), can be validated by calling it from its tests.
+
+use std::str::FromStr;
+
+use bytes::Bytes;
+use mountpoint_s3_client::types::ETag;
+
+use crate::object::ObjectId;
+
+use super::{BlockIndex, ChecksummedBytes, DataCache, DataCacheError};
+
+/// Exercise the [DataCache] contract against an empty `cache`.
+///
+/// Writes the two blocks of an object of one and a half blocks, so the cache must accept objects of at
+/// least that size. Panics with a description of the first violation found.
+pub async fn run_data_cache_conformance(cache: &(impl DataCache + Sync)) {
+    let block_size = cache.block_size();
+    assert!(block_size > 0, "block size should not be 0");
+    let object_size = (block_size + block_size / 2) as usize;
+    let first_block = block_data(block_size as usize, 1);
+    let last_block = block_data(object_size - block_size as usize, 2);
+
+    let cache_key = ObjectId::new("conformance/object".into(), ETag::for_tests());
+    let result = cache.get_block(&cache_key, 0, 0, object_size).await;
+    assert!(matches!(result, Ok(None)), "empty cache should miss, got {result:?}");
+
+    cache
+        .put_block(cache_key.clone(), 0, 0, first_block.clone(), object_size)
+        .await
+        .expect("put of the first block should succeed");
+    cache
+        .put_block(cache_key.clone(), 1, block_size, last_block.clone(), object_size)
+        .await
+        .expect("put of the last block should succeed");
+    assert_block(cache, &cache_key, 0, object_size, &first_block).await;
+    assert_block(cache, &cache_key, 1, object_size, &last_block).await;
+
+    let result = cache.get_block(&cache_key, 2, 2 * block_size, object_size).await;
+    assert!(
+        matches!(result, Ok(None)),
+        "block never written should miss, got {result:?}"
+    );
+    let other_etag = ObjectId::new(cache_key.key().into(), ETag::from_str("\"other-etag\"").unwrap());
+    let result = cache.get_block(&other_etag, 0, 0, object_size).await;
+    assert!(
+        matches!(result, Ok(None)),
+        "block of another ETag should miss, got {result:?}"
+    );
+    let other_key = ObjectId::new("conformance/other-object".into(), cache_key.etag().clone());
+    let result = cache.get_block(&other_key, 0, 0, object_size).await;
+    assert!(
+        matches!(result, Ok(None)),
+        "block of another key should miss, got {result:?}"
+    );
+
+    let result = cache.get_block(&cache_key, 1, 0, object_size).await;
+    assert!(
+        matches!(result, Err(DataCacheError::InvalidBlockOffset)),
+        "get with a mismatched offset should fail, got {result:?}"
+    );
+    let result = cache
+        .put_block(cache_key.clone(), 1, 0, last_block.clone(), object_size)
+        .await;
+    assert!(
+        matches!(result, Err(DataCacheError::InvalidBlockOffset)),
+        "put with a mismatched offset should fail, got {result:?}"
+    );
+
+    let replacement = block_data(block_size as usize, 3);
+    cache
+        .put_block(cache_key.clone(), 0, 0, replacement.clone(), object_size)
+        .await
+        .expect("put replacing a block should succeed");
+    assert_block(cache, &cache_key, 0, object_size, &replacement).await;
+    assert_block(cache, &cache_key, 1, object_size, &last_block).await;
+}
+
+async fn assert_block(
+    cache: &(impl DataCache + Sync),
+    cache_key: &ObjectId,
+    block_idx: BlockIndex,
+    object_size: usize,
+    expected: &ChecksummedBytes,
+) {
+    let block = cache
+        .get_block(cache_key, block_idx, block_idx * cache.block_size(), object_size)
+        .await
+        .unwrap_or_else(|err| panic!("get of block {block_idx} should succeed, got {err:?}"))
+        .unwrap_or_else(|| panic!("block {block_idx} should be cached"));
+    let bytes = block.into_bytes().expect("cached block should pass validation");
+    assert_eq!(
+        bytes,
+        expected.clone().into_bytes().unwrap(),
+        "block {block_idx} content"
+    );
+}
+
+fn block_data(len: usize, seed: u8) -> ChecksummedBytes {
+    let data: Vec<u8> = (0..len).map(|i| (i as u8).wrapping_mul(seed)).collect();
+    ChecksummedBytes::new(Bytes::from(data))
+}
@@ -1,5 +1,6 @@
 //! Module for the on-disk data cache implementation.
 
+use std::collections::HashSet;
 use std::fs;
 use std::io::{ErrorKind, Read, Seek, Write};
 use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
@@ -25,6 +26,12 @@ use super::{BlockIndex, ChecksummedBytes, DataCache, DataCacheResult};
 /// Disk and file-layout versioning.
 const CACHE_VERSION: &str = "V1";
 
+/// Name of the file recording the block size of the cache directory, see [DiskDataCache::open].
+const BLOCK_SIZE_FILE: &str = "block_size";
+
+/// Largest block size of a [DiskDataCache], as blocks are read into memory in full.
+const MAX_BLOCK_SIZE: u64 = 1024 * 1024 * 1024;
+
 /// Index where hashed directory names for the cache are split to avoid FS-specific limits.
 const HASHED_DIR_SPLIT_INDEX: usize = 2;
 
@@ -34,6 +41,10 @@ pub struct DiskDataCache {
     config: DiskDataCacheConfig,
     /// Tracks blocks usage. `None` when no cache limit was set.
     usage: Option<Mutex<UsageInfo<DiskBlockKey>>>,
+    /// Hashed keys of the objects whose blocks are exempt from eviction.
+    pinned: Mutex<HashSet<[u8; 32]>>,
+    /// Returns the bytes available to unprivileged users on the cache's file system.
+    available_space: Box<dyn Fn(&Path) -> Option<u64> + Send + Sync>,
 }
 
 /// Configuration for a [DiskDataCache].
@@ -43,6 +54,23 @@ pub struct DiskDataCacheConfig {
     pub block_size: u64,
     /// How to limit the cache size.
     pub limit: CacheLimit,
+    /// Minimum number of bytes to leave free on the cache's file system.
+    ///
+    /// Puts that would leave less free space are skipped, after trying to evict blocks to make room.
+    /// A value of 0 disables the check.
+    pub min_free_bytes: u64,
+    /// What [DiskDataCache::open] does when the cache directory holds blocks of another block size.
+    pub block_size_mismatch: BlockSizeMismatchPolicy,
+}
+
+/// Handling of a cache directory populated with another block size, see [DiskDataCache::open].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BlockSizeMismatchPolicy {
+    /// Fail to open the cache.
+    #[default]
+    Fail,
+    /// Remove the blocks in the cache directory.
+    Clear,
 }
 
 /// Limit the cache size.
@@ -208,7 +236,14 @@ impl From<std::io::Error> for DataCacheError {
 
 impl DiskDataCache {
     /// Create a new instance of an [DiskDataCache] with the specified configuration.
+    ///
+    /// Panics if the block size is above [DataCache::max_block_size], see [DiskDataCache::open].
     pub fn new(cache_directory: PathBuf, config: DiskDataCacheConfig) -> Self {
+        assert!(
+            config.block_size <= MAX_BLOCK_SIZE,
+            "block size {} is above the maximum of {MAX_BLOCK_SIZE} bytes",
+            config.block_size
+        );
         let usage = match config.limit {
             CacheLimit::Unbounded => None,
             CacheLimit::TotalSize { .. } | CacheLimit::AvailableSpace { .. } => Some(Mutex::new(UsageInfo::new())),
@@ -217,7 +252,70 @@ impl DiskDataCache {
             cache_directory,
             config,
             usage,
+            pinned: Default::default(),
+            available_space: Box::new(available_space),
+        }
+    }
+
+    /// Create a new instance of a [DiskDataCache], checking the block size of the blocks already in the
+    /// cache directory.
+    ///
+    /// The block size is recorded in a file in the cache directory, and blocks written with another block
+    /// size are handled according to [DiskDataCacheConfig::block_size_mismatch]. Fails if the block size
+    /// is above [DataCache::max_block_size].
+    pub fn open(cache_directory: PathBuf, config: DiskDataCacheConfig) -> DataCacheResult<Self> {
+        if config.block_size > MAX_BLOCK_SIZE {
+            return Err(DataCacheError::InvalidCacheConfig(format!(
+                "block size {} is above the maximum of {MAX_BLOCK_SIZE} bytes",
+                config.block_size
+            )));
+        }
+        let block_size_path = cache_directory.join(BLOCK_SIZE_FILE);
+        match fs::read_to_string(&block_size_path) {
+            Ok(contents) => {
+                let found_block_size = contents.trim().parse::<u64>().ok();
+                if found_block_size != Some(config.block_size) {
+                    match config.block_size_mismatch {
+                        BlockSizeMismatchPolicy::Fail => {
+                            return Err(DataCacheError::IoFailure(anyhow::anyhow!(
+                                "cache directory holds blocks of size {:?}, expected {}",
+                                contents.trim(),
+                                config.block_size
+                            )));
+                        }
+                        BlockSizeMismatchPolicy::Clear => {
+                            warn!(
+                                found = contents.trim(),
+                                expected = config.block_size,
+                                "block size of the cache directory changed, removing its blocks"
+                            );
+                            match fs::remove_dir_all(cache_directory.join(CACHE_VERSION)) {
+                                Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
         }
+        fs::DirBuilder::new()
+            .mode(0o700)
+            .recursive(true)
+            .create(&cache_directory)?;
+        fs::write(&block_size_path, config.block_size.to_string())?;
+        Ok(Self::new(cache_directory, config))
+    }
+
+    /// Exempt the blocks of the object identified by `cache_key` from eviction, until it is unpinned.
+    pub fn pin(&self, cache_key: &ObjectId) {
+        self.pinned.lock().unwrap().insert(hash_cache_key_raw(cache_key));
+    }
+
+    /// Allow the blocks of the object identified by `cache_key` to be evicted again.
+    pub fn unpin(&self, cache_key: &ObjectId) {
+        self.pinned.lock().unwrap().remove(&hash_cache_key_raw(cache_key));
     }
 
     /// Get the relative path for the given block.
@@ -328,21 +426,60 @@ impl DiskDataCache {
         };
 
         while self.is_limit_exceeded(usage.lock().unwrap().size) {
-            let Some(to_remove) = usage.lock().unwrap().evict_lru() else {
+            if !self.evict_lru_block(usage) {
                 warn!("cache limit exceeded but nothing to evict");
                 return Err(DataCacheError::EvictionFailure);
-            };
-            let path_to_remove = self.get_path_for_block_key(&to_remove);
-            trace!("evicting block at {}", path_to_remove.display());
-            if let Err(remove_err) = fs::remove_file(&path_to_remove) {
-                if remove_err.kind() != ErrorKind::NotFound {
-                    warn!("unable to evict block: {:?}", remove_err);
-                }
             }
         }
         Ok(())
     }
 
+    /// Check that writing `size` bytes keeps at least [DiskDataCacheConfig::min_free_bytes] free,
+    /// evicting blocks to make room if needed. Returns `false` if not enough space could be freed.
+    fn ensure_free_space(&self, size: u64) -> bool {
+        if self.config.min_free_bytes == 0 {
+            return true;
+        }
+        let required = self.config.min_free_bytes.saturating_add(size);
+        loop {
+            let Some(available) = (self.available_space)(&self.cache_directory) else {
+                // Don't block writes if we cannot tell how much space is left.
+                return true;
+            };
+            if available >= required {
+                return true;
+            }
+            let evicted = match &self.usage {
+                Some(usage) => self.evict_lru_block(usage),
+                None => false,
+            };
+            if !evicted {
+                return false;
+            }
+        }
+    }
+
+    /// Remove the least recently used block that is not pinned. Returns `false` if there was none.
+    fn evict_lru_block(&self, usage: &Mutex<UsageInfo<DiskBlockKey>>) -> bool {
+        let pinned = self.pinned.lock().unwrap();
+        let to_remove = usage
+            .lock()
+            .unwrap()
+            .evict_lru_matching(|block_key| !pinned.contains(&block_key.hashed_key));
+        drop(pinned);
+        let Some(to_remove) = to_remove else {
+            return false;
+        };
+        let path_to_remove = self.get_path_for_block_key(&to_remove);
+        trace!("evicting block at {}", path_to_remove.display());
+        if let Err(remove_err) = fs::remove_file(&path_to_remove) {
+            if remove_err.kind() != ErrorKind::NotFound {
+                warn!("unable to evict block: {:?}", remove_err);
+            }
+        }
+        true
+    }
+
     fn remove_block_from_usage(&self, block_key: &DiskBlockKey) {
         if let Some(usage) = &self.usage {
             usage.lock().unwrap().remove(block_key);
@@ -350,6 +487,17 @@ impl DiskDataCache {
     }
 }
 
+/// Bytes available to unprivileged users on the file system containing `path`.
+fn available_space(path: &Path) -> Option<u64> {
+    match nix::sys::statvfs::statvfs(path) {
+        Ok(stats) => Some(stats.blocks_available() as u64 * stats.fragment_size() as u64),
+        Err(error) => {
+            warn!(?error, "unable to determine available space");
+            None
+        }
+    }
+}
+
 /// Hash the cache key using its fields as well as the [CACHE_VERSION].
 fn hash_cache_key_raw(cache_key: &ObjectId) -> [u8; 32] {
     let s3_key = cache_key.key();
@@ -442,6 +590,17 @@ impl DataCache for DiskDataCache {
             result
         }?;
 
+        if !self.ensure_free_space(bytes_len as u64) {
+            // Skip the block rather than risk running out of space mid-write.
+            warn!(
+                ?path,
+                min_free_bytes = self.config.min_free_bytes,
+                "not enough free space, skipping block"
+            );
+            metrics::counter!("disk_data_cache.put_skipped", "reason" => "low_free_space").increment(1);
+            return Ok(());
+        }
+
         let write_start = Instant::now();
         let size = self.write_block(path, block)?;
         metrics::histogram!("disk_data_cache.write_duration_us").record(write_start.elapsed().as_micros() as f64);
@@ -456,6 +615,14 @@ impl DataCache for DiskDataCache {
     fn block_size(&self) -> u64 {
         self.config.block_size
     }
+
+    fn max_block_size(&self) -> u64 {
+        MAX_BLOCK_SIZE
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "disk"
+    }
 }
 
 /// Key to identify a block in the disk cache, composed of a hash of the S3 key and Etag, and the block index.
@@ -533,11 +700,14 @@ where
         }
     }
 
-    /// Remove the least recently used key and update the total size.
-    /// Return `None` if empty.
-    fn evict_lru(&mut self) -> Option<K> {
-        let (key, size) = self.entries.pop_front()?;
-        self.size = self.size.saturating_sub(size);
+    /// Remove the least recently used key for which `can_evict` returns `true` and update the total size.
+    /// Return `None` if there is no such key.
+    fn evict_lru_matching(&mut self, can_evict: impl Fn(&K) -> bool) -> Option<K>
+    where
+        K: Clone,
+    {
+        let key = self.entries.keys().find(|key| can_evict(key)).cloned()?;
+        self.remove(&key);
         Some(key)
     }
 }
@@ -546,6 +716,8 @@ where
 mod tests {
     use std::ffi::OsString;
     use std::str::FromStr;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
 
     use super::*;
 
@@ -553,6 +725,7 @@ mod tests {
     use mountpoint_s3_client::types::ETag;
     use rand::{Rng, SeedableRng};
     use rand_chacha::ChaCha20Rng;
+    use test_case::test_case;
 
     #[test]
     fn test_block_format_version_requires_update() {
@@ -589,6 +762,8 @@ mod tests {
             DiskDataCacheConfig {
                 block_size: 1024,
                 limit: CacheLimit::Unbounded,
+                min_free_bytes: 0,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
             },
         );
 
@@ -619,6 +794,8 @@ mod tests {
             DiskDataCacheConfig {
                 block_size: 1024,
                 limit: CacheLimit::Unbounded,
+                min_free_bytes: 0,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
             },
         );
 
@@ -657,6 +834,8 @@ mod tests {
             DiskDataCacheConfig {
                 block_size,
                 limit: CacheLimit::Unbounded,
+                min_free_bytes: 0,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
             },
         );
         let cache_key_1 = ObjectId::new("a".into(), ETag::for_tests());
@@ -743,6 +922,8 @@ mod tests {
             DiskDataCacheConfig {
                 block_size: 8 * 1024 * 1024,
                 limit: CacheLimit::Unbounded,
+                min_free_bytes: 0,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
             },
         );
         let cache_key = ObjectId::new("a".into(), ETag::for_tests());
@@ -823,6 +1004,8 @@ mod tests {
             DiskDataCacheConfig {
                 block_size: BLOCK_SIZE as u64,
                 limit: CacheLimit::TotalSize { max_size: CACHE_LIMIT },
+                min_free_bytes: 0,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
             },
         );
 
@@ -878,6 +1061,208 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_eviction_skips_pinned_objects() {
+        const BLOCK_SIZE: usize = 1024;
+        const CACHE_LIMIT: usize = 6 * BLOCK_SIZE;
+
+        let cache_directory = tempfile::tempdir().unwrap();
+        let cache = DiskDataCache::new(
+            cache_directory.path().to_path_buf(),
+            DiskDataCacheConfig {
+                block_size: BLOCK_SIZE as u64,
+                limit: CacheLimit::TotalSize { max_size: CACHE_LIMIT },
+                min_free_bytes: 0,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
+            },
+        );
+
+        let pinned_key = ObjectId::new("pinned".into(), ETag::for_tests());
+        let other_key = ObjectId::new("other".into(), ETag::for_tests());
+        let data = ChecksummedBytes::new(vec![0u8; BLOCK_SIZE].into());
+        cache.pin(&pinned_key);
+
+        // The pinned object is the least recently used, so it would be evicted first.
+        for (cache_key, block_count) in [(&pinned_key, 2), (&other_key, 10)] {
+            for block_idx in 0..block_count {
+                cache
+                    .put_block(
+                        cache_key.clone(),
+                        block_idx,
+                        block_idx * BLOCK_SIZE as u64,
+                        data.clone(),
+                        block_count as usize * BLOCK_SIZE,
+                    )
+                    .await
+                    .expect("put should succeed");
+            }
+        }
+
+        for block_idx in 0..2 {
+            let block = cache
+                .get_block(&pinned_key, block_idx, block_idx * BLOCK_SIZE as u64, 2 * BLOCK_SIZE)
+                .await
+                .expect("cache should be accessible");
+            assert!(block.is_some(), "pinned block {block_idx} should not be evicted");
+        }
+        let block = cache
+            .get_block(&other_key, 0, 0, 10 * BLOCK_SIZE)
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none(), "unpinned blocks should be evicted");
+
+        // Once unpinned, the object's blocks can be evicted again.
+        cache.unpin(&pinned_key);
+        for block_idx in 10..20 {
+            cache
+                .put_block(
+                    other_key.clone(),
+                    block_idx,
+                    block_idx * BLOCK_SIZE as u64,
+                    data.clone(),
+                    20 * BLOCK_SIZE,
+                )
+                .await
+                .expect("put should succeed");
+        }
+        let block = cache
+            .get_block(&pinned_key, 0, 0, 2 * BLOCK_SIZE)
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none(), "unpinned block should be evicted");
+    }
+
+    #[test_case(BlockSizeMismatchPolicy::Fail; "fail")]
+    #[test_case(BlockSizeMismatchPolicy::Clear; "clear")]
+    #[tokio::test]
+    async fn test_block_size_mismatch(block_size_mismatch: BlockSizeMismatchPolicy) {
+        let cache_directory = tempfile::tempdir().unwrap();
+        let new_config = |block_size| DiskDataCacheConfig {
+            block_size,
+            limit: CacheLimit::Unbounded,
+            min_free_bytes: 0,
+            block_size_mismatch,
+        };
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let cache = DiskDataCache::open(cache_directory.path().to_path_buf(), new_config(1024)).unwrap();
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        drop(cache);
+
+        // Reopening with the same block size keeps the blocks.
+        let cache = DiskDataCache::open(cache_directory.path().to_path_buf(), new_config(1024)).unwrap();
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()));
+        drop(cache);
+
+        let result = DiskDataCache::open(cache_directory.path().to_path_buf(), new_config(2048));
+        match block_size_mismatch {
+            BlockSizeMismatchPolicy::Fail => {
+                assert!(matches!(result, Err(DataCacheError::IoFailure(_))));
+            }
+            BlockSizeMismatchPolicy::Clear => {
+                let cache = result.expect("cache should be cleared");
+                let block = cache
+                    .get_block(&cache_key, 0, 0, data.len())
+                    .await
+                    .expect("cache should be accessible");
+                assert!(block.is_none(), "blocks of the previous block size should be removed");
+                drop(cache);
+
+                // The new block size is recorded.
+                DiskDataCache::open(cache_directory.path().to_path_buf(), new_config(2048)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_block_size() {
+        let cache_directory = tempfile::tempdir().unwrap();
+        let new_config = |block_size| DiskDataCacheConfig {
+            block_size,
+            limit: CacheLimit::Unbounded,
+            min_free_bytes: 0,
+            block_size_mismatch: BlockSizeMismatchPolicy::Fail,
+        };
+        let cache = DiskDataCache::open(cache_directory.path().to_path_buf(), new_config(MAX_BLOCK_SIZE))
+            .expect("maximum block size should be accepted");
+        assert_eq!(cache.max_block_size(), MAX_BLOCK_SIZE);
+        drop(cache);
+
+        let result = DiskDataCache::open(cache_directory.path().to_path_buf(), new_config(MAX_BLOCK_SIZE + 1));
+        assert!(matches!(result, Err(DataCacheError::InvalidCacheConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_put_skipped_on_low_free_space() {
+        const BLOCK_SIZE: usize = 1024;
+        const MIN_FREE_BYTES: u64 = 4 * BLOCK_SIZE as u64;
+
+        let cache_directory = tempfile::tempdir().unwrap();
+        let mut cache = DiskDataCache::new(
+            cache_directory.path().to_path_buf(),
+            DiskDataCacheConfig {
+                block_size: BLOCK_SIZE as u64,
+                limit: CacheLimit::TotalSize { max_size: usize::MAX },
+                min_free_bytes: MIN_FREE_BYTES,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
+            },
+        );
+        let available = Arc::new(AtomicU64::new(u64::MAX));
+        cache.available_space = Box::new({
+            let available = available.clone();
+            move |_| Some(available.load(Ordering::SeqCst))
+        });
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let data = ChecksummedBytes::new(vec![0u8; BLOCK_SIZE].into());
+        let object_size = 3 * BLOCK_SIZE;
+        for block_idx in 0..2 {
+            cache
+                .put_block(
+                    cache_key.clone(),
+                    block_idx,
+                    block_idx * BLOCK_SIZE as u64,
+                    data.clone(),
+                    object_size,
+                )
+                .await
+                .expect("put should succeed");
+        }
+
+        // Evicting blocks does not free any space here, so the cache gives up and skips the put.
+        available.store(MIN_FREE_BYTES, Ordering::SeqCst);
+        cache
+            .put_block(cache_key.clone(), 2, 2 * BLOCK_SIZE as u64, data.clone(), object_size)
+            .await
+            .expect("put should succeed even when skipped");
+        for block_idx in 0..3 {
+            let block = cache
+                .get_block(&cache_key, block_idx, block_idx * BLOCK_SIZE as u64, object_size)
+                .await
+                .expect("cache should be accessible");
+            assert!(block.is_none(), "block {block_idx} should not be cached");
+        }
+
+        // Once there is enough space again, blocks are stored.
+        available.store(MIN_FREE_BYTES + BLOCK_SIZE as u64, Ordering::SeqCst);
+        cache
+            .put_block(cache_key.clone(), 2, 2 * BLOCK_SIZE as u64, data.clone(), object_size)
+            .await
+            .expect("put should succeed");
+        let block = cache
+            .get_block(&cache_key, 2, 2 * BLOCK_SIZE as u64, object_size)
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+    }
+
     #[test]
     fn data_block_extract_checks() {
         let data_1 = ChecksummedBytes::new("Foo".into());
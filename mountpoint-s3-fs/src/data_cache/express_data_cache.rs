@@ -1,25 +1,112 @@
-use super::{BlockIndex, ChecksummedBytes, DataCache, DataCacheError, DataCacheResult};
+use super::compression::{
+    gzip_decompress, lz4_compress, lz4_decompress, zstd_compress, zstd_decompress, COMPRESSION_ENABLED,
+    ZSTD_DEFAULT_LEVEL,
+};
+use super::priority_gate::{AdaptiveConcurrencyLimit, PriorityGate};
+use super::rate_limiter::RequestRateLimiter;
+use super::retry::{retry_get, RetryClassifier, MAX_GET_ATTEMPTS};
+use super::{
+    AdaptiveConcurrency, BlockIndex, ChecksummedBytes, DataCache, DataCacheError, DataCacheResult, RetryDecision,
+};
+use crate::async_util::BoxRuntime;
+use crate::checksums::combine_checksums;
 use crate::object::ObjectId;
+use crate::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::sync::{Arc, Mutex};
 use crate::ServerSideEncryption;
-use std::collections::HashMap;
-use std::time::Instant;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Debug;
+use std::fs;
+use std::future::Future;
+use std::io::{ErrorKind, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
+use async_stream::try_stream;
 use async_trait::async_trait;
 use base64ct::{Base64, Encoding};
 use bytes::{Bytes, BytesMut};
-use futures::{pin_mut, StreamExt};
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use futures::stream::FuturesUnordered;
+use futures::task::{Spawn, SpawnExt};
+use futures::{pin_mut, Stream, StreamExt};
+use linked_hash_map::LinkedHashMap;
 use mountpoint_s3_client::checksums::crc32c::{self, Crc32c};
-use mountpoint_s3_client::error::{GetObjectError, ObjectClientError};
+use mountpoint_s3_client::checksums::crc64nvme::{self, Crc64nvme, Crc64nvmeHasher};
+use mountpoint_s3_client::error::{GetObjectError, HeadObjectError, ListObjectsError, ObjectClientError};
+use mountpoint_s3_client::error_metadata::ProvideErrorMetadata;
 use mountpoint_s3_client::types::{
-    ChecksumMode, ClientBackpressureHandle, GetObjectParams, GetObjectResponse, PutObjectSingleParams, UploadChecksum,
+    Checksum, ChecksumMode, ClientBackpressureHandle, ETag, GetObjectParams, GetObjectResponse, HeadObjectParams,
+    ObjectClientResult, ObjectLockMode, ObjectLockRetention, PutObjectParams, PutObjectResult, PutObjectSingleParams,
+    PutObjectTrailingChecksums, UploadChecksum,
 };
-use mountpoint_s3_client::ObjectClient;
+use mountpoint_s3_client::{ObjectClient, PutObjectRequest};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use time::format_description::FormatItem;
+use time::OffsetDateTime;
 use tracing::Instrument;
 
-use mountpoint_s3_client::checksums::crc32c_from_base64;
+use mountpoint_s3_client::checksums::{crc32c_from_base64, crc64nvme_from_base64};
+
+const CACHE_VERSION: &str = "V3";
+
+/// Object metadata key recording the version of Mountpoint that wrote a block. Informational only, it is not
+/// validated on reads.
+const MP_VERSION_HEADER: &str = "mp-version";
+
+/// Object metadata key holding the Unix time from which a block is read as a miss, see
+/// [ExpressDataCacheConfig::block_expiry].
+const EXPIRES_AT_HEADER: &str = "expires-at";
+
+/// Object metadata key recording that the CRC32C checksum of a block is stored after its data, see
+/// [ChecksumLocation::TrailingBytes].
+const CHECKSUM_LOCATION_HEADER: &str = "checksum-location";
+const TRAILING: &str = "trailing";
+/// Length of the big-endian CRC32C checksum appended to blocks with [ChecksumLocation::TrailingBytes].
+const TRAILING_CHECKSUM_LEN: usize = 4;
+
+/// Format of the `Expires` header, as defined by RFC 9110.
+const HTTP_DATE_FORMAT: &[FormatItem<'static>] = time::macros::format_description!(
+    "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
+);
+
+/// Magic bytes starting the output of [ExpressDataCache::export_object].
+const EXPORT_MAGIC: &[u8; 8] = b"MPCACHE1";
 
-const CACHE_VERSION: &str = "V2";
+/// Length of the header of each block record in the output of [ExpressDataCache::export_object].
+const EXPORT_RECORD_HEADER_LEN: usize = 20;
+
+/// Maximum number of keys deleted per batch, matching the limit of the S3 DeleteObjects API.
+const DELETE_BATCH_SIZE: usize = 1000;
+
+/// Extension appended to the key of a block to get the key of its completion marker, see
+/// [ExpressDataCacheConfig::completion_markers]. Unlike content hashes, it is not hexadecimal.
+const COMPLETION_MARKER_EXTENSION: &str = "complete";
+
+/// Number of bytes of the SHA-256 content hash kept when appending it to an object key.
+const CONTENT_HASH_LEN: usize = 8;
+
+/// Maximum length of S3 object keys, in bytes.
+const MAX_KEY_LEN: usize = 1024;
+
+/// Maximum size of an S3 object, which bounds the block size.
+const MAX_OBJECT_SIZE: u64 = 5 * 1024 * 1024 * 1024 * 1024;
+
+/// Object metadata header recording the compression applied to a block.
+const COMPRESSION_HEADER: &str = "compression";
+/// Object metadata header recording the id of the dictionary a block was compressed with.
+const COMPRESSION_DICTIONARY_HEADER: &str = "compression-dictionary";
+const ZSTD: &str = "zstd";
+const LZ4: &str = "lz4";
+/// Magic bytes starting a Zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+/// Magic bytes starting a gzip member, followed by the deflate compression method.
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
 
 /// Configuration for a [ExpressDataCache].
 #[derive(Debug)]
@@ -30,6 +117,274 @@ pub struct ExpressDataCacheConfig {
     pub max_object_size: usize,
     /// The SSE to be used in PUT requests to the cache bucket.
     pub sse: ServerSideEncryption,
+    /// The canned ACL to be used in PUT requests to the cache bucket, e.g. `bucket-owner-full-control` when
+    /// the cache bucket is owned by another account.
+    pub acl: Option<String>,
+    /// Append a truncated hash of the block content to the object key when writing blocks.
+    ///
+    /// Blocks written with this option are found by [ExpressDataCache::get_block_with_content_hash] when the
+    /// expected content hash is provided. They are also written under the standard key, at the cost of a
+    /// second PutObject request, so that reads without an expected hash find them too.
+    pub content_hash_in_key: bool,
+    /// The maximum number of bytes downloaded for a single block, defaulting to `block_size`.
+    ///
+    /// Reads of larger objects fail with [DataCacheError::BlockTooLarge] instead of buffering them.
+    pub max_block_download_size: Option<u64>,
+    /// Number of most recent block lookups used to compute [ExpressDataCache::hit_ratio].
+    pub hit_ratio_window: usize,
+    /// Compress blocks before writing them to the cache bucket.
+    ///
+    /// Compressed blocks are readable regardless of this setting, as long as the dictionary they
+    /// were compressed with (if any) is configured. Compression requires the `compression` feature,
+    /// without which compressed blocks cannot be read and caches configured with it are rejected.
+    pub compression: Option<BlockCompression>,
+    /// Name of a read-only replica of the cache bucket.
+    ///
+    /// Reads fail over to the replica when the cache bucket is unavailable, and writes are skipped
+    /// until a read from the cache bucket succeeds again.
+    pub read_replica_bucket_name: Option<String>,
+    /// Blocks smaller than this size are not written to the cache, so reads for them go to the source bucket.
+    pub min_cacheable_block_size: u64,
+    /// Maximum number of concurrent DeleteObjects requests issued by [ExpressDataCache::clear].
+    pub delete_concurrency: usize,
+    /// Have [ExpressDataCache::clear] and [ExpressDataCache::invalidate_object] delete one object
+    /// subtree at a time, enumerating the subtrees with a delimited listing.
+    ///
+    /// Listing with a delimiter returns one entry per object (or per version, for an invalidation) rather
+    /// than one per block, and each subtree is then listed and deleted in turn. An interrupted clear thus
+    /// leaves fewer partially deleted objects. It needs more ListObjectsV2 requests than a flat listing
+    /// when subtrees hold few blocks, since each subtree is listed separately.
+    pub delete_by_subtree: bool,
+    /// Function applied to the source description before it is hashed into the cache prefix.
+    ///
+    /// Mounts whose descriptions normalize to the same value share cached blocks, so the function
+    /// must only discard parts of the description that do not affect the object content. Otherwise,
+    /// mounts of different sources may read each other's blocks.
+    pub source_description_normalizer: Option<fn(&str) -> String>,
+    /// Previous cache versions whose blocks can be read by this version, in order of preference.
+    ///
+    /// Blocks missing under the current version's prefix are looked up under the prefixes of these versions,
+    /// but new blocks are only written for the current version. Only versions with the same key layout and
+    /// block format as the current one should be listed.
+    pub compatible_cache_versions: Vec<String>,
+    /// Object Lock retention applied to new blocks, making them immutable for the retention period.
+    ///
+    /// The cache bucket must have Object Lock enabled. Invalidating or clearing locked blocks fails with
+    /// [DeleteObjectError::ObjectLocked](mountpoint_s3_client::error::DeleteObjectError::ObjectLocked).
+    pub block_retention: Option<BlockRetention>,
+    /// Number of blocks returned by [ExpressDataCache::suggested_prefetch] once sequential lookups
+    /// for an object are observed. Block lookups are only tracked if this is not 0.
+    pub prefetch_hint_blocks: usize,
+    /// Maintain a manifest for each object, listing the checksums of its blocks as they are written.
+    ///
+    /// The manifest allows [ExpressDataCache::verify_manifest] to check the cached blocks of an object
+    /// without downloading them. Blocks written with a multipart upload are not listed.
+    pub object_manifest: bool,
+    /// Layout of the hashed components of the keys of blocks in the cache bucket.
+    ///
+    /// Blocks written with one format are not visible to caches configured with another.
+    pub key_format: KeyFormat,
+    /// Encoding of the hashed components of the keys of blocks, including the cache prefix.
+    ///
+    /// This allows sharing a cache bucket with other tools expecting another encoding. Blocks written
+    /// with one encoding are not visible to caches configured with another.
+    pub key_encoding: KeyEncoding,
+    /// Hash algorithm of the cache prefix, computed from the cache version, source description and block size.
+    pub prefix_hash_algorithm: KeyHashAlgorithm,
+    /// Hash algorithm of the object key and ETag components of the keys of blocks.
+    ///
+    /// It can be chosen independently of [ExpressDataCacheConfig::prefix_hash_algorithm], for instance to keep
+    /// a cryptographic prefix while hashing the keys of blocks with a cheaper algorithm.
+    pub block_key_hash_algorithm: KeyHashAlgorithm,
+    /// Identifier of the account or tenant the cached objects belong to, such as an AWS account id.
+    ///
+    /// When set, it is folded into the prefix of the cache's keys, so that caches for different tenants
+    /// sharing a cache bucket never read each other's blocks, even for objects with the same key and ETag.
+    pub tenant_id: Option<String>,
+    /// Fold the region of the cache client, see [ObjectClient::region], into the prefix of the cache's keys,
+    /// so that the prefixes of caches of the same source in different regions can be told apart, e.g. in logs.
+    ///
+    /// Has no effect when the client has no region. Changing it makes the blocks written before unreachable.
+    pub region_in_prefix: bool,
+    /// Treat a block disappearing while it is being downloaded as an error rather than a cache miss.
+    ///
+    /// A block deleted after its download has started is reported as a miss by default, which can hide
+    /// a concurrent invalidation. When set, [DataCache::get_block] fails with
+    /// [DataCacheError::IoFailure] instead, so the caller can fetch the block again.
+    pub error_on_deleted_during_read: bool,
+    /// Accept the chunks of a block in any order, reassembling them by offset.
+    ///
+    /// By default, a chunk which does not start where the previous one ended fails the read with
+    /// [DataCacheError::InvalidBlockOffset]. When set, chunks arriving early are held until the data before
+    /// them is received, and the read only fails if a gap remains at the end. Some S3-compatible stores
+    /// deliver the parts of an object out of order.
+    pub reorder_chunks: bool,
+    /// Skip writing empty blocks, such as the only block of an empty object, instead of storing them.
+    ///
+    /// By default, an empty block is stored as a zero-byte object and read back as an empty block.
+    /// When set, it is not written, and reads for it are misses. This takes precedence over
+    /// [ExpressDataCacheConfig::min_cacheable_block_size].
+    pub skip_empty_blocks: bool,
+    /// Record blocks whose bytes are all zero as holes of their object instead of writing them.
+    ///
+    /// Each hole is recorded by a small marker stored next to the blocks of its object version. A read for a
+    /// block which is not in the cache bucket looks up its marker, and a hole is served as a buffer of zeros.
+    /// This saves storage and upload bandwidth for sparse objects, at the cost of an extra request for each
+    /// miss.
+    pub sparse_holes: bool,
+    /// Maximum number of distinct objects (key and ETag) this instance writes blocks for.
+    ///
+    /// Objects are tracked in memory in least-recently-used order, refreshed by writes and hits. When the
+    /// first block of a new object is written past the limit, the blocks of the least recently used
+    /// object are deleted from the cache bucket. Blocks written before the process started, or by other
+    /// instances, are not tracked. Content shared through [ExpressDataCacheConfig::deduplicate_blocks] is
+    /// kept.
+    pub max_cached_objects: Option<usize>,
+    /// Look up blocks rewritten by [ExpressDataCache::compact_object] when they are not found on their own.
+    ///
+    /// This needs an extra request for the pack index of the object on each miss.
+    pub compacted_blocks: bool,
+    /// How long the listing of the blocks of an object is reused by later queries of this instance.
+    ///
+    /// Listings are used by [ExpressDataCache::present_blocks], [ExpressDataCache::export_object],
+    /// [ExpressDataCache::verify_object] and [ExpressDataCache::compact_object]. The listing of an object is
+    /// dropped when this instance writes or deletes its blocks, but blocks written or deleted by others are
+    /// only seen once it expires, according to the [Clock] of the cache.
+    pub block_listing_ttl: Option<Duration>,
+    /// Store the content of blocks once under a key derived from its hash, shared by all objects of the
+    /// source bucket, with a small reference to it for each block. The hash is computed like the other keys,
+    /// including the [ExpressDataCacheConfig::key_salt].
+    ///
+    /// This saves storage when objects share identical blocks, at the cost of an extra request for each
+    /// write and read of a block. References are not counted, so stored content is never deleted along with
+    /// the blocks of an object, whether by [ExpressDataCache::invalidate_object] or by the eviction of
+    /// [ExpressDataCacheConfig::max_cached_objects], since other objects may reference it. It is only
+    /// deleted by [ExpressDataCache::clear], or by a lifecycle rule expiring the `content/` prefix of the cache.
+    pub deduplicate_blocks: bool,
+    /// How [ExpressDataCache::try_new_with_source_client] handles a cache client in another region than
+    /// the client of the source bucket, whose requests for blocks then cross regions, adding latency and
+    /// transfer costs.
+    ///
+    /// Only applies when both clients have a region, see [ObjectClient::region].
+    pub region_mismatch_policy: RegionMismatchPolicy,
+    /// How construction handles a cache bucket whose name does not follow the naming of S3 Express One Zone
+    /// directory buckets, `base-name--zone-id--x-s3`, e.g. when a general purpose bucket is configured.
+    pub bucket_name_policy: BucketNamePolicy,
+    /// Skip writes of blocks, so that [DataCache::put_block] succeeds without sending any request.
+    ///
+    /// Cached blocks are still read from the cache bucket, e.g. to replay a workload against a cache
+    /// populated beforehand without changing it.
+    pub read_only: bool,
+    /// Retain in memory a copy of up to this many blocks read from the cache bucket, in least-recently-used
+    /// order, to serve them when reading them again fails with [DataCacheError::IoFailure].
+    ///
+    /// A retained copy is served even if the block was since replaced, deleted or expired in the cache
+    /// bucket, and is flagged as [DetailedBlock::stale]. This holds up to this many blocks of `block_size`.
+    pub stale_on_error_blocks: Option<usize>,
+    /// Header carrying the correlation ID set with [with_correlation_id] on the requests of the cache.
+    ///
+    /// Requests made outside of [with_correlation_id] have no correlation ID and are sent without the header.
+    pub correlation_id_header: Option<String>,
+    /// Maximum number of attempts for a GetObject request for a block failing before its body is received.
+    ///
+    /// Only errors classified as [RetryDecision::Transient] are retried, see
+    /// [ExpressDataCache::with_retry_classifier].
+    pub max_get_attempts: u32,
+    /// Maximum number of attempts for a GetObject request for a block failing while its body is received.
+    ///
+    /// The data received so far is discarded and the request is sent again for the whole block, so this
+    /// is configured separately from [ExpressDataCacheConfig::max_get_attempts]. Only errors classified as
+    /// [RetryDecision::Transient] are retried.
+    pub max_mid_stream_attempts: u32,
+    /// Total time after which failed requests for a block are no longer retried, counted from the first attempt.
+    ///
+    /// Bounds the latency of a lookup when attempts are slow, even if fewer than the maximum number of
+    /// attempts were made. The error of the last attempt is returned. Only reads are retried (see
+    /// [ExpressDataCache::with_retry_classifier]), so this does not affect writes.
+    pub retry_deadline: Option<Duration>,
+    /// Longest `Retry-After` hint of a failed read that is waited for before retrying it.
+    ///
+    /// When set, reads failing with such a hint (typically throttled requests) are retried after the hinted
+    /// delay, unless a classifier set with [ExpressDataCache::with_retry_classifier] deems them permanent.
+    /// Reads with a longer hint, or whose retry would exceed [ExpressDataCacheConfig::retry_deadline], fail
+    /// right away, since waiting would take longer than reading from the source bucket.
+    pub max_retry_after: Option<Duration>,
+    /// Checksum algorithm S3 validates new blocks with when they are written.
+    pub checksum_algorithm: BlockChecksumAlgorithm,
+    /// Secret folded into the hashes of object keys and ETags in the keys of blocks.
+    ///
+    /// Without it, anyone able to list the cache bucket can tell whether a given object is cached by
+    /// computing its key. Blocks are only found by caches configured with the same salt.
+    pub key_salt: Option<KeySalt>,
+    /// Decompress blocks without compression metadata that start with a Zstd or gzip frame.
+    ///
+    /// Allows reading blocks compressed by other tools, which only add the metadata identifying the
+    /// block. Blocks without a recognized frame, or whose frame cannot be decompressed, are read as is.
+    /// This must not be enabled when caching objects that are themselves compressed, since their
+    /// content would be decompressed. Not applied by [ExpressDataCache::get_block_stream]. Like
+    /// [ExpressDataCacheConfig::compression], this requires the `compression` feature.
+    pub detect_compression_magic: bool,
+    /// Local directory holding copies of blocks, which is checked before the cache bucket.
+    pub local_mirror: Option<LocalMirror>,
+    /// Size above which blocks are written with a multipart upload rather than a single PutObject request.
+    ///
+    /// Defaults to the client's write part size, or the block size if the client does not report one.
+    /// Parts of multipart uploads are always of the client's write part size.
+    pub mpu_threshold_bytes: Option<usize>,
+    /// Whether blocks above [ExpressDataCacheConfig::mpu_threshold_bytes] are written with a multipart upload.
+    pub put_mode: PutMode,
+    /// Skip writing blocks of objects whose ETag is not a plain MD5 digest, such as the `-N` suffixed
+    /// ETags of objects uploaded with multipart uploads.
+    ///
+    /// Such ETags are not a hash of the object content, so they give weaker guarantees that a cached
+    /// block still matches the object. Blocks already in the cache are still read.
+    pub skip_unstable_etags: bool,
+    /// Lifetime of new blocks, after which they are read as misses.
+    ///
+    /// The expiry is advertised with the `Expires` header of the blocks, so that lifecycle tooling can
+    /// act on it, and recorded in their metadata. The recorded expiry applies to reads by any cache,
+    /// whatever its configuration. Not applied by [ExpressDataCache::get_block_stream].
+    pub block_expiry: Option<Duration>,
+    /// Maximum number of requests per second to the cache bucket, shared by all caches in the process
+    /// setting this option for the same bucket.
+    ///
+    /// The limit is set by the first cache created for the bucket, later caches configuring another
+    /// limit use it too. Requests to the read replica are not limited.
+    pub shared_requests_per_second: Option<u32>,
+    /// Maximum number of blocks looked up at a time by this instance.
+    ///
+    /// Lookups waiting for another to complete are served by priority, see
+    /// [ExpressDataCache::get_block_with_priority], then in the order they started.
+    pub max_concurrent_gets: Option<usize>,
+    /// Adapt the maximum number of blocks written at a time by [DataCache::put_block] to the failures of
+    /// the cache bucket, see [ExpressDataCache::put_concurrency_limit].
+    pub adaptive_put_concurrency: Option<AdaptiveConcurrency>,
+    /// Treat blocks other than the last block of the object as misses if they are not of `block_size`.
+    ///
+    /// This cheaply detects truncated blocks before their checksum is validated, so that they are
+    /// fetched again from the source bucket.
+    pub verify_block_length: bool,
+    /// How reads handle blocks whose content does not match their checksum.
+    pub checksum_mismatch_policy: ChecksumMismatchPolicy,
+    /// Write an empty completion marker after each block, and only read blocks that have one.
+    ///
+    /// Readers sharing the cache bucket then never read a block whose upload is still in progress, at the
+    /// cost of an additional request for each read and write. Blocks written without this option are read
+    /// as misses. Not applied by [ExpressDataCache::get_block_stream].
+    pub completion_markers: bool,
+    /// Where the checksum of blocks written with a single PutObject request is stored.
+    pub checksum_location: ChecksumLocation,
+    /// Treat block lookups as misses while the block reads in progress buffer at least this many bytes.
+    ///
+    /// This bounds the memory held by concurrent reads, see [ExpressDataCache::inflight_get_bytes]. Reads
+    /// already in progress are not interrupted, so the total can exceed the limit by up to one block per read.
+    pub max_inflight_get_bytes: Option<usize>,
+    /// Initial read window of the GetObject requests for blocks when the client has backpressure enabled,
+    /// defaulting to the client's initial read window.
+    ///
+    /// A window of at least the block size lets the block download without waiting for the window to be
+    /// incremented once the response is received, reducing the latency to the first byte.
+    pub initial_read_window: Option<usize>,
 }
 
 impl Default for ExpressDataCacheConfig {
@@ -38,696 +393,8748 @@ impl Default for ExpressDataCacheConfig {
             block_size: 1024 * 1024,      // 1 MiB
             max_object_size: 1024 * 1024, // 1 MiB
             sse: ServerSideEncryption::default(),
+            acl: None,
+            content_hash_in_key: false,
+            max_block_download_size: None,
+            hit_ratio_window: 1000,
+            compression: None,
+            read_replica_bucket_name: None,
+            min_cacheable_block_size: 0,
+            delete_concurrency: 16,
+            delete_by_subtree: false,
+            source_description_normalizer: None,
+            compatible_cache_versions: Vec::new(),
+            block_retention: None,
+            prefetch_hint_blocks: 0,
+            object_manifest: false,
+            key_format: KeyFormat::Standard,
+            key_encoding: KeyEncoding::LowerHex,
+            prefix_hash_algorithm: KeyHashAlgorithm::Sha256,
+            block_key_hash_algorithm: KeyHashAlgorithm::Sha256,
+            tenant_id: None,
+            region_in_prefix: false,
+            error_on_deleted_during_read: false,
+            reorder_chunks: false,
+            skip_empty_blocks: false,
+            sparse_holes: false,
+            max_cached_objects: None,
+            compacted_blocks: false,
+            block_listing_ttl: None,
+            deduplicate_blocks: false,
+            region_mismatch_policy: RegionMismatchPolicy::Warn,
+            bucket_name_policy: BucketNamePolicy::Ignore,
+            read_only: false,
+            stale_on_error_blocks: None,
+            correlation_id_header: None,
+            max_get_attempts: MAX_GET_ATTEMPTS,
+            max_mid_stream_attempts: 1,
+            retry_deadline: None,
+            max_retry_after: None,
+            checksum_algorithm: BlockChecksumAlgorithm::Crc32c,
+            key_salt: None,
+            detect_compression_magic: false,
+            local_mirror: None,
+            mpu_threshold_bytes: None,
+            put_mode: PutMode::Multipart,
+            skip_unstable_etags: false,
+            block_expiry: None,
+            shared_requests_per_second: None,
+            max_concurrent_gets: None,
+            adaptive_put_concurrency: None,
+            verify_block_length: false,
+            checksum_mismatch_policy: ChecksumMismatchPolicy::Error,
+            completion_markers: false,
+            checksum_location: ChecksumLocation::Metadata,
+            max_inflight_get_bytes: None,
+            initial_read_window: None,
         }
     }
 }
 
-/// A data cache on S3 Express One Zone that can be shared across Mountpoint instances.
-pub struct ExpressDataCache<Client: ObjectClient> {
-    client: Client,
-    prefix: String,
-    config: ExpressDataCacheConfig,
-    /// Name of the S3 Express bucket to store the blocks.
-    bucket_name: String,
-    /// Name of the mounted bucket.
-    source_bucket_name: String,
+/// Layout of the keys of blocks in the cache bucket.
+///
+/// Keys consist of a prefix identifying the cache, followed by hashes of the object key and of the
+/// object key and ETag, and the block index. The formats differ in the length of these hashes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// Full SHA-256 hashes, encoded as 64 hex characters each.
+    #[default]
+    Standard,
+    /// SHA-256 hashes truncated to 128 bits, encoded as 32 hex characters each.
+    ///
+    /// Keys are about 100 characters shorter than with [KeyFormat::Standard], in exchange for a higher,
+    /// though still negligible, risk of two objects sharing a key. Colliding blocks are detected through
+    /// their metadata and treated as misses. This format uses a different prefix, so blocks written with
+    /// the standard format are not found.
+    Short,
 }
 
-impl<S, C> From<ObjectClientError<S, C>> for DataCacheError
-where
-    S: std::error::Error + Send + Sync + 'static,
-    C: std::error::Error + Send + Sync + 'static,
-{
-    fn from(e: ObjectClientError<S, C>) -> Self {
-        DataCacheError::IoFailure(e.into())
+impl KeyFormat {
+    /// Number of bytes of SHA-256 hashes kept in keys.
+    fn hash_len(self) -> usize {
+        match self {
+            KeyFormat::Standard => 32,
+            KeyFormat::Short => 16,
+        }
+    }
+
+    fn encode_hash(self, encoding: KeyEncoding, hash: &[u8]) -> String {
+        encoding.encode(&hash[..self.hash_len().min(hash.len())])
     }
 }
 
-impl<Client> ExpressDataCache<Client>
-where
-    Client: ObjectClient + Send + Sync + 'static,
-{
-    /// Create a new instance.
-    pub fn new(client: Client, config: ExpressDataCacheConfig, source_bucket_name: &str, bucket_name: &str) -> Self {
-        Self {
-            client,
-            prefix: build_prefix(source_bucket_name, config.block_size),
-            config,
-            bucket_name: bucket_name.to_owned(),
-            source_bucket_name: source_bucket_name.to_owned(),
+/// Encoding of the hashed components of the keys of blocks in the cache bucket.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEncoding {
+    /// Lowercase hexadecimal.
+    #[default]
+    LowerHex,
+    /// Uppercase hexadecimal.
+    UpperHex,
+    /// Lowercase base32 of RFC 4648, without padding.
+    ///
+    /// Hashes are 20% shorter than in hexadecimal, 52 characters for a full SHA-256 hash.
+    Base32,
+}
+
+impl KeyEncoding {
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            KeyEncoding::LowerHex => hex::encode(bytes),
+            KeyEncoding::UpperHex => hex::encode_upper(bytes),
+            KeyEncoding::Base32 => base32_encode(bytes),
         }
     }
+}
 
-    pub async fn make_put_object_request<'a>(
-        &self,
-        mut params: PutObjectSingleParams,
-        object_key: &str,
-        data: impl AsRef<[u8]> + Send + 'a,
-    ) -> Result<(), DataCacheError> {
-        let (sse_type, key_id) = self
-            .config
-            .sse
-            .clone()
-            .into_inner()
-            .map_err(|err| DataCacheError::IoFailure(err.into()))?;
-        params = params.server_side_encryption(sse_type);
-        params = params.ssekms_key_id(key_id);
+fn base32_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
 
-        let result = self
-            .client
-            .put_object_single(&self.bucket_name, object_key, &params, data)
-            .in_current_span()
-            .await
-            .map_err(|err| DataCacheError::IoFailure(err.into()))?;
+/// Hash algorithm of the hashed components of the keys of blocks in the cache bucket.
+///
+/// Changing the algorithm of a component changes the keys of all blocks, so blocks written with another
+/// algorithm are not found.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeyHashAlgorithm {
+    /// SHA-256, truncated according to the [KeyFormat].
+    #[default]
+    Sha256,
+    /// CRC64NVME, encoded as 16 hex characters whatever the [KeyFormat].
+    ///
+    /// It is much cheaper to compute than SHA-256, but not collision resistant, so it should not be used
+    /// where the hashes must not be guessable, such as with [ExpressDataCacheConfig::key_salt]. Colliding
+    /// blocks are detected through their metadata and treated as misses.
+    Crc64nvme,
+}
 
-        // Verify that headers of the PUT response match the expected SSE
-        if let Err(err) = self
-            .config
-            .sse
-            .verify_response(result.sse_type.as_deref(), result.sse_kms_key_id.as_deref())
-        {
-            tracing::error!(object_key=?object_key, error=?err, "Unexpected SSE in PutObject response from S3. A cache block may be stored with wrong encryption settings.");
-            // Reaching this point is very unlikely and means that SSE settings were corrupted in transit or on S3 side, this may be a sign of a bug
-            // in CRT code or S3. Thus, we terminate Mountpoint to send the most noticeable signal to customer about the issue. We prefer exiting
-            // instead of returning an error because:
-            // 1. this error would only be reported to logs because the cache population is an async process
-            // 2. the reported error is severe as the object was already uploaded to S3.
-            std::process::exit(1);
+impl KeyHashAlgorithm {
+    fn digest<'a>(self, parts: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+        match self {
+            KeyHashAlgorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                for part in parts {
+                    hasher.update(part);
+                }
+                hasher.finalize().to_vec()
+            }
+            KeyHashAlgorithm::Crc64nvme => {
+                let mut hasher = Crc64nvmeHasher::new();
+                for part in parts {
+                    hasher.update(part);
+                }
+                hasher.finalize().value().to_be_bytes().to_vec()
+            }
         }
+    }
+}
 
-        Ok(())
+/// Checksum algorithm of the blocks written to the cache bucket with a single PutObject request.
+///
+/// Blocks are read back whichever algorithm they were written with, validating their content against
+/// the checksum returned by S3. Blocks written with a multipart upload always use CRC32C, stored as with
+/// [ChecksumLocation::TrailingBytes].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BlockChecksumAlgorithm {
+    /// CRC32C, which is also the checksum of the data held in memory.
+    #[default]
+    Crc32c,
+    /// CRC64NVME, the default algorithm of S3.
+    ///
+    /// The CRC32C checksum of a block is computed from its content when it is read. Since S3 only stores
+    /// one checksum per object, [ExpressDataCache::block_checksum] and [ExpressDataCache::verify_manifest]
+    /// report these blocks as having an invalid checksum.
+    Crc64nvme,
+}
+
+/// How blocks are written to the cache bucket, see [ExpressDataCacheConfig::put_mode].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PutMode {
+    /// Blocks above the threshold are written with a multipart upload.
+    #[default]
+    Multipart,
+    /// Blocks are only written with a single PutObject request, and writes of blocks above the threshold
+    /// fail with [DataCacheError::BlockTooLarge].
+    ///
+    /// This avoids the costs of multipart uploads, such as incomplete uploads left behind by failed writes.
+    /// Blocks written with [ExpressDataCacheConfig::content_hash_in_key] always use a single request.
+    SinglePartOnly,
+}
+
+/// Location of the checksum of blocks written to the cache bucket with a single PutObject request.
+///
+/// Blocks are read back wherever their checksum was stored. Blocks written with a multipart upload always
+/// use [ChecksumLocation::TrailingBytes], since their checksum is only known once all their data was written
+/// and S3 only stores a checksum of the part checksums.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumLocation {
+    /// The checksum is sent with the PutObject request and stored by S3, see [BlockChecksumAlgorithm].
+    #[default]
+    Metadata,
+    /// The CRC32C checksum of the stored data is appended to it as 4 big-endian bytes, and no checksum
+    /// is sent to S3.
+    ///
+    /// This supports S3-compatible stores which do not implement additional checksums. Since the checksum
+    /// is not stored by S3, [ExpressDataCache::block_checksum] and [ExpressDataCache::verify_manifest]
+    /// report these blocks as having an invalid checksum. [ExpressDataCache::get_block_stream] holds back
+    /// the last bytes received until the end of the block, so that the checksum is never yielded as data.
+    TrailingBytes,
+}
+
+/// Handling of cached blocks whose content does not match their checksum, see
+/// [ExpressDataCacheConfig::checksum_mismatch_policy].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumMismatchPolicy {
+    /// Reads fail with [DataCacheError::InvalidBlockChecksum] when the mismatch is detected by the cache.
+    ///
+    /// Blocks whose checksum is stored by S3 are returned with it, and only fail validation when their
+    /// content is used.
+    #[default]
+    Error,
+    /// The content of blocks is validated when they are read, and blocks failing validation are misses,
+    /// so that the caller reads them from the source bucket and writes them again.
+    Miss,
+    /// Like [ChecksumMismatchPolicy::Miss], also deleting the blocks failing validation from the cache bucket.
+    MissAndDelete,
+}
+
+/// Handling of a cache bucket in another region than the source bucket, see
+/// [ExpressDataCacheConfig::region_mismatch_policy].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RegionMismatchPolicy {
+    /// A warning is logged and the cache is used anyway.
+    #[default]
+    Warn,
+    /// [ExpressDataCache::try_new_with_source_client] fails with [DataCacheError::InvalidCacheConfig].
+    Error,
+}
+
+/// Handling of a cache bucket whose name is not a directory bucket name, see
+/// [ExpressDataCacheConfig::bucket_name_policy].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BucketNamePolicy {
+    /// The bucket name is not checked.
+    #[default]
+    Ignore,
+    /// A warning is logged and the cache is used anyway.
+    Warn,
+    /// [ExpressDataCache::try_new] fails with [DataCacheError::InvalidCacheConfig].
+    Error,
+}
+
+/// Append a chunk to the stored data of a block read so far.
+fn append_chunk(buffer: Bytes, body: Box<[u8]>) -> Bytes {
+    if buffer.is_empty() {
+        return Bytes::from(body);
     }
+    // Unlikely: we expect `get_object` to return a single chunk.
+    let mut buffer = BytesMut::from(buffer);
+    buffer.extend_from_slice(&body);
+    buffer.freeze()
+}
 
-    pub async fn verify_cache_valid(&self) -> Result<(), DataCacheError> {
-        let object_key = format!("{}/_mountpoint_cache_metadata", &self.prefix);
-        // This data is human-readable, and not expected to be read by Mountpoint.
-        // The file format used here is NOT stable.
-        // For now, let's just include the data that's guaranteed to be correct as it's what
-        // calculates the prefix.
-        let data = format!(
-            "source_bucket={}\nblock_size={}",
-            self.source_bucket_name, self.config.block_size
-        );
+/// Split the trailing checksum from the stored data of a block, validating the data against it.
+fn split_trailing_checksum(mut buffer: Bytes) -> DataCacheResult<(Bytes, Crc32c)> {
+    if buffer.len() < TRAILING_CHECKSUM_LEN {
+        return Err(DataCacheError::InvalidBlockChecksum);
+    }
+    let trailer = buffer.split_off(buffer.len() - TRAILING_CHECKSUM_LEN);
+    let stored = trailer_checksum(&trailer)?;
+    if crc32c::checksum(&buffer) != stored {
+        return Err(DataCacheError::InvalidBlockChecksum);
+    }
+    Ok((buffer, stored))
+}
 
-        // put_object is sufficient for validating cache, as S3 Directory buckets only support
-        // read-only, or read-write. Write implies read access.
-        // Validating we're in a directory bucket by using the `EXPRESS_ONEZONE` storage class.
-        let params = PutObjectSingleParams::new().storage_class("EXPRESS_ONEZONE".to_string());
-        self.make_put_object_request(params, &object_key, data).await
+/// Parse the checksum stored in the last [TRAILING_CHECKSUM_LEN] bytes of a block.
+fn trailer_checksum(trailer: &[u8]) -> DataCacheResult<Crc32c> {
+    let trailer = trailer.try_into().map_err(|_| DataCacheError::InvalidBlockChecksum)?;
+    Ok(Crc32c::new(u32::from_be_bytes(trailer)))
+}
+
+/// Add a chunk received for a block with a trailing checksum, holding back the last
+/// [TRAILING_CHECKSUM_LEN] bytes received so far in `trailer` as they may be the checksum.
+///
+/// Returns the data released by this chunk, in order, in up to two pieces to avoid copying the chunk.
+fn hold_back_trailer(trailer: &mut Bytes, mut body: Bytes) -> [Bytes; 2] {
+    if body.len() >= TRAILING_CHECKSUM_LEN {
+        let new_trailer = body.split_off(body.len() - TRAILING_CHECKSUM_LEN);
+        [std::mem::replace(trailer, new_trailer), body]
+    } else {
+        let mut joined = BytesMut::with_capacity(trailer.len() + body.len());
+        joined.extend_from_slice(trailer);
+        joined.extend_from_slice(&body);
+        let mut released = joined.freeze();
+        *trailer = released.split_off(released.len().saturating_sub(TRAILING_CHECKSUM_LEN));
+        [released, Bytes::new()]
     }
+}
 
-    // Ensure the flow-control window is large enough for reading a block of data if backpressure is enabled.
-    fn ensure_read_window(&self, backpressure_handle: Option<&mut impl ClientBackpressureHandle>) {
-        if let Some(backpressure_handle) = backpressure_handle {
-            backpressure_handle.increment_read_window(self.config.block_size as usize);
-        }
+fn has_trailing_checksum(headers: &HashMap<String, String>) -> bool {
+    headers
+        .get(CHECKSUM_LOCATION_HEADER)
+        .is_some_and(|location| location == TRAILING)
+}
+
+/// Get the CRC32C checksum of a block's stored data from the `checksum` returned with it.
+///
+/// If the block was written with [BlockChecksumAlgorithm::Crc64nvme], `compute` must return the CRC32C
+/// and CRC64NVME checksums of the data, and the latter is validated against the stored one.
+fn stored_crc32c(
+    checksum: &Checksum,
+    compute: impl FnOnce() -> Option<(Crc32c, Crc64nvme)>,
+) -> DataCacheResult<Crc32c> {
+    if let Some(crc32c_b64) = &checksum.checksum_crc32c {
+        return crc32c_from_base64(crc32c_b64).map_err(|_| DataCacheError::InvalidBlockChecksum);
     }
+    let crc64nvme_b64 = checksum
+        .checksum_crc64nvme
+        .as_ref()
+        .ok_or(DataCacheError::InvalidBlockChecksum)?;
+    let stored_crc64nvme = crc64nvme_from_base64(crc64nvme_b64).map_err(|_| DataCacheError::InvalidBlockChecksum)?;
+    let (crc32c, crc64nvme) = compute().ok_or(DataCacheError::InvalidBlockChecksum)?;
+    if crc64nvme != stored_crc64nvme {
+        return Err(DataCacheError::InvalidBlockChecksum);
+    }
+    Ok(crc32c)
+}
 
-    async fn read_block(
-        &self,
-        cache_key: &ObjectId,
-        block_idx: BlockIndex,
-        block_offset: u64,
-        object_size: usize,
-    ) -> DataCacheResult<Option<ChecksummedBytes>> {
-        if object_size > self.config.max_object_size {
-            metrics::counter!("express_data_cache.over_max_object_size", "type" => "read").increment(1);
-            return Ok(None);
-        }
+/// Secret salt for the keys of blocks, see [ExpressDataCacheConfig::key_salt].
+///
+/// Its value is not included in the [Debug] output, so that it is not logged with the configuration.
+#[derive(Clone)]
+pub struct KeySalt(String);
 
-        if block_offset != block_idx * self.config.block_size {
-            return Err(DataCacheError::InvalidBlockOffset);
-        }
+impl KeySalt {
+    /// Create a salt from a secret, which should be long and random.
+    pub fn new(salt: impl Into<String>) -> Self {
+        Self(salt.into())
+    }
+}
 
-        let object_key = get_s3_key(&self.prefix, cache_key, block_idx);
-        let mut result = match self
-            .client
-            .get_object(
-                &self.bucket_name,
-                &object_key,
-                &GetObjectParams::new().checksum_mode(Some(ChecksumMode::Enabled)),
-            )
-            .await
-        {
-            Ok(result) => result,
-            Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey)) => {
-                return Ok(None);
-            }
-            Err(e) => {
-                return Err(DataCacheError::IoFailure(e.into()));
-            }
-        };
-        let mut backpressure_handle = result.backpressure_handle().cloned();
+impl Debug for KeySalt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("KeySalt(<redacted>)")
+    }
+}
 
-        // Guarantee that the request will start even in case of `initial_read_window == 0`.
-        self.ensure_read_window(backpressure_handle.as_mut());
+/// Hashing of the object key and ETag components of the keys of blocks.
+#[derive(Clone, Copy)]
+struct KeyHasher<'a> {
+    format: KeyFormat,
+    encoding: KeyEncoding,
+    algorithm: KeyHashAlgorithm,
+    salt: Option<&'a [u8]>,
+}
 
-        let mut buffer: Bytes = Bytes::new();
-        pin_mut!(result);
-        while let Some(chunk) = result.next().await {
-            match chunk {
-                Ok((offset, body)) => {
-                    if offset != buffer.len() as u64 {
-                        return Err(DataCacheError::InvalidBlockOffset);
-                    }
+impl KeyHasher<'_> {
+    /// Hashing used by the public key functions.
+    const STANDARD: KeyHasher<'static> = KeyHasher {
+        format: KeyFormat::Standard,
+        encoding: KeyEncoding::LowerHex,
+        algorithm: KeyHashAlgorithm::Sha256,
+        salt: None,
+    };
 
-                    buffer = if buffer.is_empty() {
-                        Bytes::from(body)
-                    } else {
-                        // Unlikely: we expect `get_object` to return a single chunk.
-                        let mut buffer = BytesMut::from(buffer);
-                        buffer.extend_from_slice(&body);
-                        buffer.freeze()
-                    };
+    fn hash<'b>(self, parts: impl IntoIterator<Item = &'b [u8]>) -> String {
+        let salt_len = self.salt.map(|salt| (salt.len() as u64).to_be_bytes());
+        let salt_parts = self
+            .salt
+            .zip(salt_len.as_ref())
+            .map(|(salt, salt_len)| [b"salt".as_slice(), salt_len, salt]);
+        let digest = self.algorithm.digest(salt_parts.into_iter().flatten().chain(parts));
+        self.format.encode_hash(self.encoding, &digest)
+    }
+}
 
-                    // Ensure the flow-control window is large enough.
-                    self.ensure_read_window(backpressure_handle.as_mut());
-                }
-                Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey)) => {
-                    return Ok(None);
-                }
-                Err(e) => {
-                    return Err(DataCacheError::IoFailure(e.into()));
-                }
-            }
+/// Local directory read before the cache bucket, see [ExpressDataCacheConfig::local_mirror].
+///
+/// Each block is stored in a file at the path of its key in the cache bucket, relative to the directory,
+/// holding the CRC32C checksum of the block as 4 big-endian bytes followed by its data. Blocks found in
+/// the directory are served without a request to the cache bucket, and other blocks are read from it.
+/// Files that are corrupted or cannot be read are ignored. Not used by
+/// [ExpressDataCache::get_block_with_content_hash] with an expected hash.
+///
+/// The files of an object are removed from the directory when it is deleted from the cache bucket by
+/// [ExpressDataCache::invalidate_object], [ExpressDataCache::clear] or an eviction.
+#[derive(Clone, Debug)]
+pub struct LocalMirror {
+    /// Directory holding the blocks.
+    pub directory: PathBuf,
+    /// Also write blocks put in the cache bucket to the directory.
+    ///
+    /// Failing to write a block to the directory does not fail the put.
+    pub mirror_writes: bool,
+}
+
+/// Object Lock retention of the blocks written to the cache bucket.
+#[derive(Clone, Copy, Debug)]
+pub struct BlockRetention {
+    /// The retention mode.
+    pub mode: ObjectLockMode,
+    /// How long a block is retained after it is written.
+    pub period: Duration,
+}
+
+impl BlockRetention {
+    fn for_new_block(&self, now: OffsetDateTime) -> ObjectLockRetention {
+        ObjectLockRetention {
+            mode: self.mode,
+            retain_until_date: now + self.period,
         }
+    }
+}
 
-        let object_metadata = result.get_object_metadata();
+/// Zstd compression of the blocks written to the cache bucket.
+#[derive(Clone, Debug)]
+pub struct BlockCompression {
+    /// Dictionary used to compress new blocks and to decompress the blocks written with it.
+    pub dictionary: Option<CompressionDictionary>,
+    /// Zstd compression level of new blocks, trading CPU time for a smaller size at higher levels.
+    ///
+    /// Blocks can be decompressed regardless of the level they were compressed with.
+    pub level: i32,
+    /// Choose the compression of each block from its content instead of always using Zstd.
+    pub negotiation: Option<CompressionNegotiation>,
+}
 
-        let checksum = result
-            .get_object_checksum()
-            .map_err(|_| DataCacheError::InvalidBlockChecksum)?;
-        let crc32c_b64 = checksum
-            .checksum_crc32c
-            .ok_or_else(|| DataCacheError::InvalidBlockChecksum)?;
-        let crc32c = crc32c_from_base64(&crc32c_b64).map_err(|_| DataCacheError::InvalidBlockChecksum)?;
+impl Default for BlockCompression {
+    fn default() -> Self {
+        Self {
+            dictionary: None,
+            level: ZSTD_DEFAULT_LEVEL,
+            negotiation: None,
+        }
+    }
+}
 
-        let block_metadata = BlockMetadata::new(block_idx, block_offset, cache_key, &self.source_bucket_name, crc32c);
-        block_metadata.validate_object_metadata(&object_metadata)?;
+/// Algorithm compressing a block, recorded in its object metadata.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    Zstd,
+    Lz4,
+}
 
-        Ok(Some(ChecksummedBytes::new_from_inner_data(buffer, crc32c)))
+impl CompressionAlgorithm {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Zstd => ZSTD,
+            CompressionAlgorithm::Lz4 => LZ4,
+        }
     }
+}
 
-    async fn write_block(
-        &self,
-        cache_key: ObjectId,
-        block_idx: BlockIndex,
-        block_offset: u64,
-        bytes: ChecksummedBytes,
-        object_size: usize,
-    ) -> DataCacheResult<()> {
-        if object_size > self.config.max_object_size {
-            metrics::counter!("express_data_cache.over_max_object_size", "type" => "write").increment(1);
-            return Ok(());
+/// Selection of the compression of each block from the entropy of a sample of its bytes.
+///
+/// Blocks with a high entropy, e.g. already compressed or encrypted data, are stored uncompressed. Blocks with
+/// a low entropy compress well with LZ4, which is much faster than Zstd. Other blocks are compressed with Zstd.
+/// The algorithm is recorded in the metadata of each block, so blocks can be read back regardless of this
+/// configuration. The [BlockCompression::dictionary] only applies to Zstd.
+#[derive(Clone, Debug)]
+pub struct CompressionNegotiation {
+    /// Number of bytes sampled evenly across the block to estimate its entropy.
+    pub sample_size: usize,
+    /// Blocks with an entropy of at least this many bits per byte are stored uncompressed.
+    pub incompressible_entropy: f64,
+    /// Blocks with an entropy of at most this many bits per byte are compressed with LZ4.
+    pub lz4_entropy: f64,
+}
+
+impl Default for CompressionNegotiation {
+    fn default() -> Self {
+        Self {
+            sample_size: 4096,
+            incompressible_entropy: 7.5,
+            lz4_entropy: 3.0,
         }
+    }
+}
 
-        if block_offset != block_idx * self.config.block_size {
-            return Err(DataCacheError::InvalidBlockOffset);
+impl CompressionNegotiation {
+    /// The algorithm to compress `data` with, or [None] to store it uncompressed.
+    fn algorithm_for(&self, data: &[u8]) -> Option<CompressionAlgorithm> {
+        let entropy = sample_entropy(data, self.sample_size);
+        if entropy >= self.incompressible_entropy {
+            None
+        } else if entropy <= self.lz4_entropy {
+            Some(CompressionAlgorithm::Lz4)
+        } else {
+            Some(CompressionAlgorithm::Zstd)
         }
+    }
+}
 
-        let object_key = get_s3_key(&self.prefix, &cache_key, block_idx);
+/// Shannon entropy, in bits per byte, of up to `sample_size` bytes sampled evenly across `data`.
+fn sample_entropy(data: &[u8], sample_size: usize) -> f64 {
+    let step = (data.len() / sample_size.max(1)).max(1);
+    let mut counts = [0usize; 256];
+    let mut total = 0;
+    for &byte in data.iter().step_by(step).take(sample_size) {
+        counts[byte as usize] += 1;
+        total += 1;
+    }
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
 
-        let (data, checksum) = bytes.into_inner().map_err(|_| DataCacheError::InvalidBlockContent)?;
-        let block_metadata =
-            BlockMetadata::new(block_idx, block_offset, &cache_key, &self.source_bucket_name, checksum);
+/// A precomputed Zstd dictionary.
+///
+/// Its id is stored in the metadata of the blocks compressed with it, so it must change whenever the
+/// dictionary content does.
+#[derive(Clone)]
+pub struct CompressionDictionary {
+    id: u32,
+    data: Arc<Vec<u8>>,
+}
 
-        self.make_put_object_request(block_metadata.to_put_object_params(), &object_key, data)
-            .await
+impl CompressionDictionary {
+    pub fn new(id: u32, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            id,
+            data: Arc::new(data.into()),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
     }
 }
 
-#[async_trait]
-impl<Client> DataCache for ExpressDataCache<Client>
-where
-    Client: ObjectClient + Send + Sync + 'static,
-{
-    async fn get_block(
-        &self,
-        cache_key: &ObjectId,
-        block_idx: BlockIndex,
-        block_offset: u64,
-        object_size: usize,
-    ) -> DataCacheResult<Option<ChecksummedBytes>> {
-        let start = Instant::now();
-        let (result, result_type) = match self.read_block(cache_key, block_idx, block_offset, object_size).await {
-            Ok(Some(data)) => {
-                metrics::counter!("express_data_cache.block_hit").increment(1);
-                metrics::counter!("express_data_cache.total_bytes", "type" => "read").increment(data.len() as u64);
-                (Ok(Some(data)), "ok")
-            }
-            Ok(None) => {
-                metrics::counter!("express_data_cache.block_hit").increment(0);
-                (Ok(None), "miss")
-            }
-            Err(err) => {
-                metrics::counter!("express_data_cache.block_hit").increment(0);
-                metrics::counter!("express_data_cache.block_err", "reason" => err.reason(), "type" => "read")
-                    .increment(1);
-                (Err(err), "error")
-            }
-        };
-        metrics::histogram!("express_data_cache.read_duration_us", "type" => result_type)
-            .record(start.elapsed().as_micros() as f64);
-        result
+impl Debug for CompressionDictionary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompressionDictionary")
+            .field("id", &self.id)
+            .field("len", &self.data.len())
+            .finish()
     }
+}
 
-    async fn put_block(
-        &self,
-        cache_key: ObjectId,
+impl BlockCompression {
+    /// Compress `data`, returning the compressed bytes and the object metadata describing them.
+    fn compress(&self, data: &[u8]) -> DataCacheResult<(Bytes, HashMap<String, String>)> {
+        let mut headers = HashMap::from([(COMPRESSION_HEADER.to_string(), ZSTD.to_string())]);
+        if let Some(dictionary) = &self.dictionary {
+            headers.insert(COMPRESSION_DICTIONARY_HEADER.to_string(), dictionary.id.to_string());
+        }
+        let dictionary = self.dictionary.as_ref().map(|dictionary| dictionary.data.as_slice());
+        let compressed =
+            zstd_compress(data, self.level, dictionary).map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        Ok((compressed.into(), headers))
+    }
+
+    /// Compress `data` with the algorithm chosen by the [BlockCompression::negotiation], if any, returning
+    /// the compressed bytes and the object metadata describing them, or [None] if it should be stored
+    /// uncompressed.
+    fn compress_negotiated(&self, data: &[u8]) -> DataCacheResult<Option<(Bytes, HashMap<String, String>)>> {
+        let algorithm = match &self.negotiation {
+            Some(negotiation) => negotiation.algorithm_for(data),
+            None => Some(CompressionAlgorithm::Zstd),
+        };
+        let algorithm_label = algorithm.map_or("none", |algorithm| algorithm.as_str());
+        metrics::counter!("express_data_cache.compressed_blocks", "algorithm" => algorithm_label).increment(1);
+        match algorithm {
+            Some(CompressionAlgorithm::Zstd) => self.compress(data).map(Some),
+            Some(CompressionAlgorithm::Lz4) => {
+                let headers = HashMap::from([(COMPRESSION_HEADER.to_string(), LZ4.to_string())]);
+                let compressed = lz4_compress(data).map_err(|err| DataCacheError::IoFailure(err.into()))?;
+                Ok(Some((compressed.into(), headers)))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Decompress a block according to the compression recorded in its object metadata `headers`.
+///
+/// Returns [None] if the block is not compressed.
+fn decompress_block(
+    headers: &HashMap<String, String>,
+    data: &[u8],
+    compression: Option<&BlockCompression>,
+    capacity: usize,
+) -> DataCacheResult<Option<Bytes>> {
+    match headers.get(COMPRESSION_HEADER).map(String::as_str) {
+        None => return Ok(None),
+        Some(ZSTD) => {}
+        Some(LZ4) => return decompress_lz4(data, capacity).map(Some),
+        Some(_) => return Err(DataCacheError::InvalidBlockHeader(COMPRESSION_HEADER.to_string())),
+    }
+    let dictionary = match headers.get(COMPRESSION_DICTIONARY_HEADER) {
+        Some(id) => {
+            let dictionary = compression
+                .and_then(|compression| compression.dictionary.as_ref())
+                .filter(|dictionary| id.parse() == Ok(dictionary.id))
+                .ok_or_else(|| DataCacheError::InvalidBlockHeader(COMPRESSION_DICTIONARY_HEADER.to_string()))?;
+            Some(dictionary.data.as_slice())
+        }
+        None => None,
+    };
+    let decompressed = zstd_decompress(data, capacity, dictionary).map_err(|_| DataCacheError::InvalidBlockContent)?;
+    Ok(Some(decompressed.into()))
+}
+
+/// Decompress a block compressed with LZ4, prefixed with its decompressed size as 4 little-endian bytes.
+fn decompress_lz4(data: &[u8], capacity: usize) -> DataCacheResult<Bytes> {
+    let size = data
+        .first_chunk::<4>()
+        .map(|size| u32::from_le_bytes(*size) as usize)
+        .ok_or(DataCacheError::InvalidBlockContent)?;
+    if size > capacity {
+        return Err(DataCacheError::InvalidBlockContent);
+    }
+    let decompressed = lz4_decompress(data).map_err(|_| DataCacheError::InvalidBlockContent)?;
+    Ok(decompressed.into())
+}
+
+/// Decompress a block written without compression metadata, if it starts with a Zstd or gzip frame.
+///
+/// Returns [None] if no frame is recognized or it cannot be decompressed, in which case the block is raw data.
+fn decompress_by_magic(data: &[u8], capacity: usize) -> Option<Bytes> {
+    let (format, decompressed) = if data.starts_with(&ZSTD_MAGIC) {
+        ("zstd", zstd_decompress(data, capacity, None).ok()?)
+    } else if data.starts_with(&GZIP_MAGIC) {
+        ("gzip", gzip_decompress(data, capacity).ok()?)
+    } else {
+        return None;
+    };
+    metrics::counter!("express_data_cache.magic_decompressed", "format" => format).increment(1);
+    Some(decompressed.into())
+}
+
+/// A data cache on S3 Express One Zone that can be shared across Mountpoint instances.
+///
+/// Blocks are stored through any [ObjectClient], so the same key layout and validation can be used with
+/// other S3-compatible stores. Such a client must return the user metadata and the CRC32C checksum of
+/// single-part objects written with [ObjectClient::put_object_single], and report missing objects as
+/// `NoSuchKey`.
+pub struct ExpressDataCache<Client: ObjectClient> {
+    client: Client,
+    prefix: String,
+    config: ExpressDataCacheConfig,
+    /// Name of the S3 Express bucket to store the blocks.
+    bucket_name: String,
+    /// Name of the mounted bucket.
+    source_bucket_name: String,
+    /// Cache versions listed in [ExpressDataCacheConfig::compatible_cache_versions] with their prefixes.
+    compatible_prefixes: Vec<(String, String)>,
+    /// Outcomes of the most recent block lookups.
+    recent_lookups: Mutex<LookupWindow>,
+    /// Sequential runs of block lookups, if [ExpressDataCacheConfig::prefetch_hint_blocks] is set.
+    access_tracker: Option<Mutex<AccessTracker>>,
+    /// Whether reads are currently served from the read replica.
+    failed_over: AtomicBool,
+    /// Overrides the classification of errors from the cache bucket, see [ExpressDataCache::with_retry_classifier].
+    retry_classifier: Option<RetryClassifier<Client::ClientError>>,
+    /// Number of block reads in progress.
+    inflight_gets: AtomicUsize,
+    /// Number of block writes in progress.
+    inflight_puts: AtomicUsize,
+    /// Number of bytes buffered by block reads in progress.
+    inflight_get_bytes: AtomicUsize,
+    /// Bucket missing blocks are read from, with the runtime writing them to the cache, see
+    /// [ExpressDataCache::with_origin].
+    origin: Option<(OriginSource<Client>, BoxRuntime)>,
+    /// Limiter shared with other caches of the same bucket, see [ExpressDataCacheConfig::shared_requests_per_second].
+    rate_limiter: Option<Arc<RequestRateLimiter>>,
+    /// Limit of concurrent lookups, see [ExpressDataCacheConfig::max_concurrent_gets].
+    get_gate: Option<Arc<PriorityGate>>,
+    /// Limit of concurrent block writes, see [ExpressDataCacheConfig::adaptive_put_concurrency].
+    put_concurrency: Option<AdaptiveConcurrencyLimit>,
+    /// Selects the bucket of each block, see [ExpressDataCache::with_tier_policy].
+    tier_policy: Option<Box<dyn TierPolicy + Send + Sync>>,
+    /// Source of the current time, see [ExpressDataCache::with_clock].
+    clock: Box<dyn Clock + Send + Sync>,
+    /// Recent listings of the blocks of objects, see [ExpressDataCacheConfig::block_listing_ttl].
+    block_listings: Mutex<HashMap<ObjectId, BlockListing>>,
+    /// Objects whose blocks were recently looked up, to tell first (cold) lookups from later (warm) ones.
+    accessed_objects: Mutex<LinkedHashMap<ObjectId, ()>>,
+    /// Objects with written blocks in least-recently-used order, if
+    /// [ExpressDataCacheConfig::max_cached_objects] is set.
+    cached_objects: Option<Mutex<LinkedHashMap<ObjectId, ()>>>,
+    /// Copies of the blocks most recently read, if [ExpressDataCacheConfig::stale_on_error_blocks] is set.
+    retained_blocks: Option<Mutex<LinkedHashMap<(ObjectId, BlockIndex), DetailedBlock>>>,
+}
+
+/// Bucket and storage class a block is stored with, see [TierPolicy].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tier {
+    /// Name of the bucket holding the block.
+    pub bucket_name: String,
+    /// Storage class of the block, or the default storage class of the bucket if [None].
+    pub storage_class: Option<String>,
+}
+
+/// Source of the current time of an [ExpressDataCache], see [ExpressDataCache::with_clock].
+pub trait Clock {
+    /// The current time.
+    fn now(&self) -> OffsetDateTime;
+}
+
+/// [Clock] reading the system time, used by default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+thread_local! {
+    static CORRELATION_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Run `future` with `correlation_id` attached to the requests an [ExpressDataCache] makes while polling it,
+/// in the header set by [ExpressDataCacheConfig::correlation_id_header].
+///
+/// The ID is passed explicitly since the values recorded in [tracing] spans cannot be read back. It is held
+/// in a thread-local variable while `future` is polled, so it is not inherited by tasks that `future` spawns
+/// on a runtime: requests made by those tasks have no correlation ID unless they are wrapped in
+/// [with_correlation_id] themselves. The tasks spawned by the cache, such as the writes of blocks read by
+/// [ExpressDataCache::get_block_read_through], keep the ID.
+pub fn with_correlation_id<F: Future>(correlation_id: impl Into<String>, future: F) -> impl Future<Output = F::Output> {
+    WithCorrelationId {
+        correlation_id: Some(correlation_id.into()),
+        future: Box::pin(future),
+    }
+}
+
+/// Wrap `future` with the correlation ID of the current thread, if any, so that it keeps it once spawned.
+fn with_current_correlation_id<F: Future>(future: F) -> WithCorrelationId<F> {
+    WithCorrelationId {
+        correlation_id: current_correlation_id(),
+        future: Box::pin(future),
+    }
+}
+
+/// Future setting the correlation ID of the current thread while polling the wrapped future.
+struct WithCorrelationId<F> {
+    correlation_id: Option<String>,
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for WithCorrelationId<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let previous = CORRELATION_ID.with(|id| id.replace(this.correlation_id.clone()));
+        let result = this.future.as_mut().poll(cx);
+        CORRELATION_ID.with(|id| *id.borrow_mut() = previous);
+        result
+    }
+}
+
+/// The correlation ID set by the innermost [with_correlation_id] being polled on this thread.
+fn current_correlation_id() -> Option<String> {
+    CORRELATION_ID.with(|id| id.borrow().clone())
+}
+
+/// Routes blocks to the bucket they are stored in, see [ExpressDataCache::with_tier_policy].
+pub trait TierPolicy {
+    /// The tier of the block with index `block_idx` of the object `key`.
+    ///
+    /// Must always return the same tier for a block, otherwise the block is not found.
+    fn tier_for(&self, key: &ObjectId, block_idx: BlockIndex) -> Tier;
+}
+
+/// Bucket holding the cached objects, see [ExpressDataCache::with_origin].
+#[derive(Debug)]
+pub struct OriginSource<Client> {
+    /// Client for requests to the origin bucket.
+    pub client: Client,
+    /// Name of the origin bucket.
+    pub bucket: String,
+}
+
+/// Priority of lookups used by [DataCache::get_block], see [ExpressDataCache::get_block_with_priority].
+pub const DEFAULT_GET_PRIORITY: i32 = 0;
+
+/// Sliding window over the outcomes (hit or miss) of recent block lookups.
+#[derive(Debug)]
+struct LookupWindow {
+    outcomes: VecDeque<bool>,
+    hits: usize,
+    capacity: usize,
+}
+
+impl LookupWindow {
+    fn new(capacity: usize) -> Self {
+        Self {
+            outcomes: VecDeque::with_capacity(capacity),
+            hits: 0,
+            capacity,
+        }
+    }
+
+    fn record(&mut self, hit: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.outcomes.len() == self.capacity && self.outcomes.pop_front() == Some(true) {
+            self.hits -= 1;
+        }
+        self.outcomes.push_back(hit);
+        if hit {
+            self.hits += 1;
+        }
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        self.hits as f64 / self.outcomes.len() as f64
+    }
+}
+
+/// Outcome of [ExpressDataCache::verify_object].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Number of blocks whose content matches their stored checksum and metadata.
+    pub verified: u64,
+    /// Blocks with content, checksum or metadata that do not match.
+    pub mismatched: Vec<BlockIndex>,
+    /// Blocks missing before the last cached block of the object, or removed during verification.
+    pub missing: Vec<BlockIndex>,
+}
+
+/// S3 error codes of requests rejected because of their credentials.
+const CREDENTIALS_ERROR_CODES: &[&str] = &[
+    "InvalidAccessKeyId",
+    "SignatureDoesNotMatch",
+    "ExpiredToken",
+    "InvalidToken",
+    "TokenRefreshRequired",
+];
+
+/// Outcome of [ExpressDataCache::check_credentials].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialsCheck {
+    /// S3 authenticated a request signed with the credentials. The cache bucket may still not exist or
+    /// not be accessible.
+    Valid,
+    /// S3 rejected the credentials, with the given error code.
+    InvalidCredentials(String),
+    /// No response was received from S3, for example because no credentials were found or S3 could not
+    /// be reached.
+    Failed(String),
+}
+
+/// Outcome of [ExpressDataCache::warm].
+#[derive(Debug, Default)]
+pub struct WarmReport {
+    /// Number of blocks copied to the cache.
+    pub copied: u64,
+    /// Blocks that could not be copied, with the error of their copy.
+    pub failed: Vec<(BlockIndex, DataCacheError)>,
+}
+
+/// Fraction of their size that compressed blocks are assumed to take up by [ExpressDataCache::estimate_fill_cost].
+const ESTIMATED_COMPRESSION_RATIO: f64 = 0.5;
+
+/// Estimated cost of writing blocks to the cache, see [ExpressDataCache::estimate_fill_cost].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FillCostEstimate {
+    /// Number of requests to the cache bucket, including the requests to update manifests.
+    pub requests: u64,
+    /// Size of the block data, before compression.
+    pub uncompressed_bytes: u64,
+    /// Size of the data stored in the cache bucket, assuming that compression halves the size of blocks.
+    pub stored_bytes: u64,
+}
+
+/// A block read from the cache with [ExpressDataCache::get_block_detailed].
+#[derive(Debug, Clone)]
+pub struct DetailedBlock {
+    /// The content of the block, decompressed if it was stored compressed.
+    pub bytes: ChecksummedBytes,
+    /// Size of the block as stored in the cache bucket, i.e. after compression.
+    ///
+    /// The ratio of this size to the length of [DetailedBlock::bytes] is the realized compression ratio.
+    pub stored_size: usize,
+    /// Whether the block is a copy retained after an earlier read, served because reading it from the cache
+    /// bucket failed, see [ExpressDataCacheConfig::stale_on_error_blocks].
+    pub stale: bool,
+}
+
+/// Number of operations of an [ExpressDataCache] in progress, see [ExpressDataCache::inflight].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InflightCounts {
+    /// Block reads, including reads with [ExpressDataCache::get_block_with_content_hash].
+    pub gets: usize,
+    /// Block writes with [DataCache::put_block].
+    pub puts: usize,
+}
+
+/// Counts an operation as in progress until it is dropped.
+struct InflightGuard<'a>(&'a AtomicUsize);
+
+impl<'a> InflightGuard<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Counts the bytes buffered by a block read as in flight until it is dropped.
+struct BufferedBytesGuard<'a> {
+    counter: &'a AtomicUsize,
+    len: usize,
+}
+
+impl<'a> BufferedBytesGuard<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        Self { counter, len: 0 }
+    }
+
+    fn add(&mut self, len: usize) {
+        self.len += len;
+        let total = self.counter.fetch_add(len, Ordering::SeqCst) + len;
+        metrics::gauge!("express_data_cache.inflight_get_bytes").set(total as f64);
+    }
+}
+
+impl Drop for BufferedBytesGuard<'_> {
+    fn drop(&mut self) {
+        let total = self.counter.fetch_sub(self.len, Ordering::SeqCst) - self.len;
+        metrics::gauge!("express_data_cache.inflight_get_bytes").set(total as f64);
+    }
+}
+
+/// User metadata of an object, see [ExpressDataCache::put_object_metadata].
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedObjectMetadata {
+    /// Key and ETag of the object, checked on reads in case of a collision of the hashed key.
+    source_key: String,
+    etag: String,
+    metadata: HashMap<String, String>,
+}
+
+/// Marker of a block of an object which is all zero, see [ExpressDataCacheConfig::sparse_holes].
+#[derive(Debug, Serialize, Deserialize)]
+struct Hole {
+    /// Key and ETag of the object, checked on reads in case of a collision of the hashed key.
+    source_key: String,
+    etag: String,
+    /// Length of the block.
+    len: usize,
+}
+
+/// Location of the blocks of an object rewritten by [ExpressDataCache::compact_object].
+#[derive(Debug, Serialize, Deserialize)]
+struct PackIndex {
+    /// Key and ETag of the object, checked on reads in case of a collision of the hashed key.
+    source_key: String,
+    etag: String,
+    blocks: BTreeMap<BlockIndex, PackedBlock>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PackedBlock {
+    /// Key of the pack holding the block in the cache bucket.
+    pack_key: String,
+    /// Range of the block in the pack.
+    offset: u64,
+    len: u64,
+    /// CRC32C checksum of the block.
+    crc32c: u32,
+}
+
+/// Reference from a block of an object to its content, see [ExpressDataCacheConfig::deduplicate_blocks].
+#[derive(Debug, Serialize, Deserialize)]
+struct BlockReference {
+    /// Key and ETag of the object, checked on reads in case of a collision of the hashed key.
+    source_key: String,
+    etag: String,
+    /// Key of the content of the block in the cache bucket.
+    content_key: String,
+    len: u64,
+    /// CRC32C checksum of the content.
+    crc32c: u32,
+}
+
+/// List of the blocks written for an object, see [ExpressDataCacheConfig::object_manifest].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ObjectManifest {
+    /// Hash over all the entries, to detect a manifest whose entries were modified.
+    root: String,
+    blocks: BTreeMap<BlockIndex, ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ManifestEntry {
+    /// Key of the block in the cache bucket.
+    key: String,
+    /// CRC32C checksum of the object stored for the block.
+    crc32c: u32,
+}
+
+impl ObjectManifest {
+    fn insert(&mut self, block_idx: BlockIndex, key: String, crc32c: Crc32c) {
+        self.blocks.insert(
+            block_idx,
+            ManifestEntry {
+                key,
+                crc32c: crc32c.value(),
+            },
+        );
+        self.root = self.compute_root();
+    }
+
+    fn compute_root(&self) -> String {
+        let mut hasher = Sha256::new();
+        for (block_idx, entry) in &self.blocks {
+            hasher.update(block_idx.to_be_bytes());
+            hasher.update((entry.key.len() as u64).to_be_bytes());
+            hasher.update(entry.key.as_bytes());
+            hasher.update(entry.crc32c.to_be_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Maximum number of objects whose block listings are kept, see [ExpressDataCacheConfig::block_listing_ttl].
+const MAX_BLOCK_LISTINGS: usize = 1024;
+
+/// Maximum number of objects remembered to tell first lookups of an object from later ones.
+const MAX_ACCESSED_OBJECTS: usize = 1024;
+
+/// Blocks of an object listed by [ExpressDataCache::list_cached_blocks].
+#[derive(Debug)]
+struct BlockListing {
+    listed_at: OffsetDateTime,
+    blocks: Vec<(BlockIndex, Option<String>)>,
+}
+
+/// Blocks deleted and failed to be deleted by [ExpressDataCache::delete_under_prefix].
+#[derive(Debug, Default)]
+struct DeleteOutcome {
+    deleted: u64,
+    failed: u64,
+    first_error: Option<anyhow::Error>,
+}
+
+impl DeleteOutcome {
+    fn add(&mut self, other: DeleteOutcome) {
+        self.deleted += other.deleted;
+        self.failed += other.failed;
+        if self.first_error.is_none() {
+            self.first_error = other.first_error;
+        }
+    }
+}
+
+/// Maximum number of objects whose block lookups are tracked for prefetch hints.
+const MAX_TRACKED_OBJECTS: usize = 1024;
+
+/// The most recent run of consecutive block lookups for each object.
+#[derive(Debug, Default)]
+struct AccessTracker {
+    runs: HashMap<ObjectId, SequentialRun>,
+}
+
+#[derive(Debug)]
+struct SequentialRun {
+    last_block: BlockIndex,
+    len: usize,
+}
+
+impl AccessTracker {
+    fn record(&mut self, cache_key: &ObjectId, block_idx: BlockIndex) {
+        if let Some(run) = self.runs.get_mut(cache_key) {
+            if run.last_block.checked_add(1) == Some(block_idx) {
+                run.len += 1;
+            } else if block_idx != run.last_block {
+                run.len = 1;
+            }
+            run.last_block = block_idx;
+            return;
+        }
+        if self.runs.len() >= MAX_TRACKED_OBJECTS {
+            // Keep tracking cheap rather than precise: forget an arbitrary object.
+            if let Some(evicted) = self.runs.keys().next().cloned() {
+                self.runs.remove(&evicted);
+            }
+        }
+        let run = SequentialRun {
+            last_block: block_idx,
+            len: 1,
+        };
+        self.runs.insert(cache_key.clone(), run);
+    }
+
+    fn next_blocks(&self, cache_key: &ObjectId, count: usize) -> Vec<BlockIndex> {
+        match self.runs.get(cache_key) {
+            Some(run) if run.len >= 2 => (1..=count as u64)
+                .filter_map(|i| run.last_block.checked_add(i))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl<S, C> From<ObjectClientError<S, C>> for DataCacheError
+where
+    S: std::error::Error + Send + Sync + 'static,
+    C: std::error::Error + Send + Sync + 'static,
+{
+    fn from(e: ObjectClientError<S, C>) -> Self {
+        DataCacheError::IoFailure(e.into())
+    }
+}
+
+impl<Client> ExpressDataCache<Client>
+where
+    Client: ObjectClient + Send + Sync + 'static,
+{
+    /// Create a new instance, failing if the block size is above [DataCache::max_block_size], if compression
+    /// is configured without the `compression` feature, or if the configuration is rejected by the
+    /// [ExpressDataCacheConfig::bucket_name_policy].
+    ///
+    /// The `client` is only used for requests to the cache bucket. It does not need to be the client
+    /// used for the mounted bucket, so cache traffic can be isolated on a dedicated client.
+    ///
+    /// The source bucket is assumed to be accessed with `client`, see
+    /// [ExpressDataCache::try_new_with_source_client] to check the region of another client.
+    pub fn try_new(
+        client: Client,
+        config: ExpressDataCacheConfig,
+        source_bucket_name: &str,
+        bucket_name: &str,
+    ) -> DataCacheResult<Self> {
+        check_block_size(&config)?;
+        check_compression(&config)?;
+        check_bucket_name(&config, bucket_name)?;
+        Ok(Self::create(client, config, source_bucket_name, bucket_name))
+    }
+
+    /// Create a new instance for a source bucket accessed with `source_client`, failing like
+    /// [ExpressDataCache::try_new] or if the regions of the clients are rejected by the
+    /// [ExpressDataCacheConfig::region_mismatch_policy].
+    pub fn try_new_with_source_client(
+        client: Client,
+        source_client: &impl ObjectClient,
+        config: ExpressDataCacheConfig,
+        source_bucket_name: &str,
+        bucket_name: &str,
+    ) -> DataCacheResult<Self> {
+        check_regions(&config, client.region(), source_client.region())?;
+        Self::try_new(client, config, source_bucket_name, bucket_name)
+    }
+
+    fn create(client: Client, config: ExpressDataCacheConfig, source_bucket_name: &str, bucket_name: &str) -> Self {
+        let source_description = match config.source_description_normalizer {
+            Some(normalize) => normalize(source_bucket_name),
+            None => source_bucket_name.to_owned(),
+        };
+        let cache_region = client.region().filter(|_| config.region_in_prefix).map(str::to_owned);
+        let prefix_for_version = |cache_version: &str| {
+            build_prefix_for_version(
+                cache_version,
+                config.key_format,
+                config.key_encoding,
+                config.prefix_hash_algorithm,
+                config.tenant_id.as_deref(),
+                cache_region.as_deref(),
+                &source_description,
+                config.block_size,
+            )
+        };
+        let compatible_prefixes = config
+            .compatible_cache_versions
+            .iter()
+            .map(|version| (version.clone(), prefix_for_version(version)))
+            .collect();
+        Self {
+            client,
+            prefix: prefix_for_version(CACHE_VERSION),
+            compatible_prefixes,
+            recent_lookups: Mutex::new(LookupWindow::new(config.hit_ratio_window)),
+            access_tracker: (config.prefetch_hint_blocks > 0).then(Default::default),
+            failed_over: AtomicBool::new(false),
+            retry_classifier: None,
+            inflight_gets: AtomicUsize::new(0),
+            inflight_puts: AtomicUsize::new(0),
+            inflight_get_bytes: AtomicUsize::new(0),
+            origin: None,
+            tier_policy: None,
+            clock: Box::new(SystemClock),
+            block_listings: Default::default(),
+            accessed_objects: Default::default(),
+            cached_objects: config.max_cached_objects.map(|_| Mutex::new(LinkedHashMap::new())),
+            retained_blocks: config.stale_on_error_blocks.map(|_| Mutex::new(LinkedHashMap::new())),
+            rate_limiter: config
+                .shared_requests_per_second
+                .map(|limit| RequestRateLimiter::for_bucket(bucket_name, limit)),
+            get_gate: config
+                .max_concurrent_gets
+                .map(|permits| PriorityGate::new(permits, "express_data_cache.queued_gets")),
+            put_concurrency: config
+                .adaptive_put_concurrency
+                .clone()
+                .map(AdaptiveConcurrencyLimit::new),
+            config,
+            bucket_name: bucket_name.to_owned(),
+            source_bucket_name: source_bucket_name.to_owned(),
+        }
+    }
+
+    /// Override how GetObject errors from the cache bucket are classified.
+    ///
+    /// By default, all errors are [RetryDecision::Permanent], since the client already retries the
+    /// errors S3 considers transient. Errors classified as [RetryDecision::Transient] are retried
+    /// up to [ExpressDataCacheConfig::max_get_attempts] times in total, or
+    /// [ExpressDataCacheConfig::max_mid_stream_attempts] for errors while receiving the body of a block, and
+    /// until [ExpressDataCacheConfig::retry_deadline] if set.
+    pub fn with_retry_classifier(
+        mut self,
+        classifier: impl Fn(&ObjectClientError<GetObjectError, Client::ClientError>) -> RetryDecision
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.retry_classifier = Some(Box::new(classifier));
+        self
+    }
+
+    /// Store each block in the bucket and with the storage class selected by `policy`, instead of the
+    /// cache bucket.
+    ///
+    /// The client must have access to all buckets of the policy. Manifests are kept in the cache bucket,
+    /// and [ExpressDataCache::clear], [ExpressDataCache::invalidate_object] and
+    /// [ExpressDataCache::verify_object] only cover the blocks stored in it. The read replica is only used
+    /// for blocks stored in the cache bucket.
+    pub fn with_tier_policy(mut self, policy: impl TierPolicy + Send + Sync + 'static) -> Self {
+        self.tier_policy = Some(Box::new(policy));
+        self
+    }
+
+    /// Read the current time from `clock` rather than the system clock.
+    ///
+    /// The clock sets the expiry and retention of new blocks (see [ExpressDataCacheConfig::block_expiry]
+    /// and [ExpressDataCacheConfig::block_retention]) and decides whether read blocks have expired.
+    /// Durations, such as those of requests, are still measured with [Instant].
+    pub fn with_clock(mut self, clock: impl Clock + Send + Sync + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// The tier of a block, which is the cache bucket unless a [TierPolicy] is set.
+    fn tier_for(&self, cache_key: &ObjectId, block_idx: BlockIndex) -> Tier {
+        match &self.tier_policy {
+            Some(policy) => policy.tier_for(cache_key, block_idx),
+            None => Tier {
+                bucket_name: self.bucket_name.clone(),
+                storage_class: None,
+            },
+        }
+    }
+
+    /// Read blocks missing from the cache from `origin` in [ExpressDataCache::get_block_read_through].
+    ///
+    /// Blocks read from the origin are written to the cache in tasks spawned on `runtime`.
+    pub fn with_origin(mut self, origin: OriginSource<Client>, runtime: impl Spawn + Send + Sync + 'static) -> Self {
+        self.origin = Some((origin, BoxRuntime::new(runtime)));
+        self
+    }
+
+    /// Get a block of data, reading it from the origin bucket on a cache miss.
+    ///
+    /// A block read from the origin is returned immediately, and written to the cache in the background
+    /// so that later reads are hits. The origin object must still have the ETag of `cache_key`. Returns
+    /// [None] on a miss if no origin was set with [ExpressDataCache::with_origin], or if the block is
+    /// past the end of the object.
+    pub async fn get_block_read_through(
+        self: &Arc<Self>,
+        cache_key: &ObjectId,
         block_idx: BlockIndex,
         block_offset: u64,
-        bytes: ChecksummedBytes,
         object_size: usize,
-    ) -> DataCacheResult<()> {
-        let start = Instant::now();
-        let (result, result_type) = match self
-            .write_block(cache_key, block_idx, block_offset, bytes, object_size)
-            .await
-        {
-            Ok(()) => {
-                metrics::counter!("express_data_cache.total_bytes", "type" => "write").increment(object_size as u64);
-                (Ok(()), "ok")
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        if let Some(block) = self.get_block(cache_key, block_idx, block_offset, object_size).await? {
+            return Ok(Some(block));
+        }
+        let Some((origin, runtime)) = &self.origin else {
+            return Ok(None);
+        };
+        let Some(bytes) = self
+            .read_origin_block(origin, cache_key, block_offset, object_size)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let cache = self.clone();
+        let fill_key = cache_key.clone();
+        let fill_bytes = bytes.clone();
+        let fill = with_current_correlation_id(async move {
+            if let Err(error) = cache
+                .put_block(fill_key.clone(), block_idx, block_offset, fill_bytes, object_size)
+                .await
+            {
+                tracing::warn!(cache_key = ?fill_key, block_idx, ?error, "failed to write block read from origin");
             }
-            Err(err) => {
-                metrics::counter!("express_data_cache.block_err", "reason" => err.reason(), "type" => "write")
-                    .increment(1);
-                (Err(err), "error")
+        });
+        if let Err(error) = runtime.spawn(fill) {
+            tracing::warn!(
+                ?cache_key,
+                block_idx,
+                ?error,
+                "failed to spawn write of block read from origin"
+            );
+        }
+        Ok(Some(bytes))
+    }
+
+    /// Copy the blocks of the object `cache_key` from the origin bucket to the cache.
+    ///
+    /// At most `concurrency` blocks are copied at a time. A failure to copy a block does not stop the
+    /// others from being copied, and is reported in the returned [WarmReport]. Fails if no origin was
+    /// set with [ExpressDataCache::with_origin]. Objects larger than [ExpressDataCacheConfig::max_object_size]
+    /// are not copied.
+    pub async fn warm(
+        &self,
+        cache_key: &ObjectId,
+        object_size: usize,
+        concurrency: usize,
+    ) -> DataCacheResult<WarmReport> {
+        let Some((origin, _)) = &self.origin else {
+            return Err(DataCacheError::IoFailure(anyhow::anyhow!(
+                "no origin to warm the cache from"
+            )));
+        };
+        let mut report = WarmReport::default();
+        if object_size > self.config.max_object_size {
+            metrics::counter!("express_data_cache.over_max_object_size", "type" => "write").increment(1);
+            return Ok(report);
+        }
+
+        let block_count = (object_size as u64).div_ceil(self.config.block_size);
+        let mut copies = futures::stream::iter(0..block_count)
+            .map(|block_idx| async move {
+                let block_offset = block_idx * self.config.block_size;
+                let result = match self
+                    .read_origin_block(origin, cache_key, block_offset, object_size)
+                    .await
+                {
+                    Ok(Some(bytes)) => {
+                        self.put_block(cache_key.clone(), block_idx, block_offset, bytes, object_size)
+                            .await
+                    }
+                    Ok(None) => Ok(()),
+                    Err(err) => Err(err),
+                };
+                (block_idx, result)
+            })
+            .buffer_unordered(concurrency.max(1));
+        while let Some((block_idx, result)) = copies.next().await {
+            match result {
+                Ok(()) => report.copied += 1,
+                Err(err) => {
+                    tracing::warn!(?cache_key, block_idx, error = ?err, "failed to warm block");
+                    report.failed.push((block_idx, err));
+                }
             }
+        }
+        report.failed.sort_by_key(|(block_idx, _)| *block_idx);
+        metrics::counter!("express_data_cache.warmed_blocks").increment(report.copied);
+        Ok(report)
+    }
+
+    /// Read a block of the object `cache_key` from the origin bucket.
+    ///
+    /// Returns [None] if the block is past the end of the object.
+    async fn read_origin_block(
+        &self,
+        origin: &OriginSource<Client>,
+        cache_key: &ObjectId,
+        block_offset: u64,
+        object_size: usize,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        let block_end = block_offset
+            .saturating_add(self.config.block_size)
+            .min(object_size as u64);
+        if block_offset >= block_end {
+            return Ok(None);
+        }
+
+        let params = GetObjectParams::new()
+            .range(Some(block_offset..block_end))
+            .if_match(Some(cache_key.etag().clone()));
+        let mut result = origin
+            .client
+            .get_object(&origin.bucket, cache_key.key(), &params)
+            .await
+            .map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        let mut backpressure_handle = result.backpressure_handle().cloned();
+        self.ensure_read_window(backpressure_handle.as_mut());
+        let mut buffer = BytesMut::with_capacity((block_end - block_offset) as usize);
+        pin_mut!(result);
+        while let Some(chunk) = result.next().await {
+            let (offset, body) = chunk.map_err(|err| DataCacheError::IoFailure(err.into()))?;
+            if offset != block_offset + buffer.len() as u64 {
+                return Err(DataCacheError::InvalidBlockOffset);
+            }
+            buffer.extend_from_slice(&body);
+            self.ensure_read_window(backpressure_handle.as_mut());
+        }
+        if buffer.len() as u64 != block_end - block_offset {
+            return Err(DataCacheError::InvalidBlockContent);
+        }
+        metrics::counter!("express_data_cache.origin_reads").increment(1);
+        Ok(Some(ChecksummedBytes::new(buffer.freeze())))
+    }
+
+    fn classify_error(&self, err: &ObjectClientError<GetObjectError, Client::ClientError>) -> RetryDecision {
+        match &self.retry_classifier {
+            Some(classify) => classify(err),
+            None if self.config.max_retry_after.is_some() && err.meta().retry_after.is_some() => {
+                RetryDecision::Transient
+            }
+            None => RetryDecision::Permanent,
+        }
+    }
+
+    pub async fn make_put_object_request<'a>(
+        &self,
+        params: PutObjectSingleParams,
+        object_key: &str,
+        data: impl AsRef<[u8]> + Send + 'a,
+    ) -> Result<(), DataCacheError> {
+        self.put_object_in_bucket(&self.bucket_name, params, object_key, data)
+            .await
+    }
+
+    async fn put_object_in_bucket<'a>(
+        &self,
+        bucket_name: &str,
+        mut params: PutObjectSingleParams,
+        object_key: &str,
+        data: impl AsRef<[u8]> + Send + 'a,
+    ) -> Result<(), DataCacheError> {
+        let (sse_type, key_id) = self
+            .config
+            .sse
+            .clone()
+            .into_inner()
+            .map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        params = params.server_side_encryption(sse_type);
+        params = params.ssekms_key_id(key_id);
+        params = params.acl(self.config.acl.clone());
+        params = params.content_length(data.as_ref().len() as u64);
+
+        self.throttle().await;
+        let result = self
+            .client
+            .put_object_single(bucket_name, object_key, &params, data)
+            .in_current_span()
+            .await
+            .map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        self.verify_put_response(object_key, &result);
+
+        Ok(())
+    }
+
+    /// Start a multipart upload for a block that did not fit in a single PutObject request.
+    async fn start_multipart_upload(
+        &self,
+        block_metadata: &BlockMetadata,
+        tier: &Tier,
+        object_key: &str,
+    ) -> DataCacheResult<Client::PutObjectRequest> {
+        let (sse_type, key_id) = self
+            .config
+            .sse
+            .clone()
+            .into_inner()
+            .map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        let params = block_metadata
+            .to_multipart_put_object_params()
+            .server_side_encryption(sse_type)
+            .ssekms_key_id(key_id)
+            .acl(self.config.acl.clone())
+            .object_lock_retention(
+                self.config
+                    .block_retention
+                    .map(|retention| retention.for_new_block(self.clock.now())),
+            );
+        let params = match self.correlation_header() {
+            Some((name, value)) => params.add_custom_header(name, value),
+            None => params,
+        };
+        let params = match self.block_expiry() {
+            Some((expires, expires_at)) => {
+                let mut params = params.add_custom_header("Expires".to_string(), expires);
+                params.object_metadata.insert(EXPIRES_AT_HEADER.to_string(), expires_at);
+                params
+            }
+            None => params,
+        };
+        let params = match &tier.storage_class {
+            Some(storage_class) => params.storage_class(storage_class.clone()),
+            None => params,
+        };
+
+        self.throttle().await;
+        let request = self
+            .client
+            .put_object(&tier.bucket_name, object_key, &params)
+            .in_current_span()
+            .await?;
+        Ok(request)
+    }
+
+    /// Verify that headers of the PUT response match the expected SSE
+    fn verify_put_response(&self, object_key: &str, result: &PutObjectResult) {
+        if let Err(err) = self
+            .config
+            .sse
+            .verify_response(result.sse_type.as_deref(), result.sse_kms_key_id.as_deref())
+        {
+            tracing::error!(object_key=?object_key, error=?err, "Unexpected SSE in PutObject response from S3. A cache block may be stored with wrong encryption settings.");
+            // Reaching this point is very unlikely and means that SSE settings were corrupted in transit or on S3 side, this may be a sign of a bug
+            // in CRT code or S3. Thus, we terminate Mountpoint to send the most noticeable signal to customer about the issue. We prefer exiting
+            // instead of returning an error because:
+            // 1. this error would only be reported to logs because the cache population is an async process
+            // 2. the reported error is severe as the object was already uploaded to S3.
+            std::process::exit(1);
+        }
+    }
+
+    /// Create a writer for a block whose size is not known up front.
+    ///
+    /// The writer buffers data until it exceeds [ExpressDataCacheConfig::mpu_threshold_bytes] and then
+    /// switches to a multipart upload, so small blocks are still written with a single PutObject request.
+    pub fn block_writer(
+        &self,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+    ) -> DataCacheResult<ExpressBlockWriter<'_, Client>> {
+        self.validate_block_offset(block_idx, block_offset)?;
+        self.validate_key_length(&cache_key, block_idx)?;
+
+        Ok(ExpressBlockWriter {
+            cache: self,
+            tier: self.tier_for(&cache_key, block_idx),
+            cache_key,
+            block_idx,
+            block_offset,
+            single_put_threshold: self.single_put_threshold(),
+            checksum: Crc32c::new(0),
+            len: 0,
+            state: BlockWriterState::Buffering(Vec::new()),
+        })
+    }
+
+    /// Offset of the block with index `block_idx`, which must be passed along with it as `block_offset`.
+    ///
+    /// Returns [None] if the offset overflows.
+    pub fn expected_offset(&self, block_idx: BlockIndex) -> Option<u64> {
+        block_idx.checked_mul(self.config.block_size)
+    }
+
+    /// The `Expires` header and expiry metadata of a new block, see [ExpressDataCacheConfig::block_expiry].
+    fn block_expiry(&self) -> Option<(String, String)> {
+        let expires_at = self.clock.now() + self.config.block_expiry?;
+        let expires = expires_at.format(HTTP_DATE_FORMAT).ok()?;
+        Some((expires, expires_at.unix_timestamp().to_string()))
+    }
+
+    /// Size above which blocks are written with a multipart upload, see [ExpressDataCacheConfig::mpu_threshold_bytes].
+    fn single_put_threshold(&self) -> usize {
+        self.config
+            .mpu_threshold_bytes
+            .or_else(|| self.client.write_part_size())
+            .unwrap_or(self.config.block_size as usize)
+    }
+
+    fn key_hasher(&self) -> KeyHasher<'_> {
+        KeyHasher {
+            format: self.config.key_format,
+            encoding: self.config.key_encoding,
+            algorithm: self.config.block_key_hash_algorithm,
+            salt: self.config.key_salt.as_ref().map(|salt| salt.0.as_bytes()),
+        }
+    }
+
+    /// Check that the longest key written for the block `block_idx` is accepted by S3, so that writes fail
+    /// before sending any request.
+    fn validate_key_length(&self, cache_key: &ObjectId, block_idx: BlockIndex) -> DataCacheResult<()> {
+        let mut key_len = block_key(self.key_hasher(), &self.prefix, cache_key, block_idx).len();
+        if self.config.content_hash_in_key {
+            key_len += 1 + 2 * CONTENT_HASH_LEN;
+        }
+        if self.config.completion_markers {
+            key_len += 1 + COMPLETION_MARKER_EXTENSION.len();
+        }
+        if key_len > MAX_KEY_LEN {
+            metrics::counter!("express_data_cache.key_too_long").increment(1);
+            return Err(DataCacheError::InvalidCacheConfig(format!(
+                "key of block {block_idx} is {key_len} bytes long, above the S3 limit of {MAX_KEY_LEN} bytes"
+            )));
+        }
+        Ok(())
+    }
+
+    fn validate_block_offset(&self, block_idx: BlockIndex, block_offset: u64) -> DataCacheResult<()> {
+        if self.expected_offset(block_idx) != Some(block_offset) {
+            // The caller computed the offset incorrectly, which is a bug rather than a cache failure.
+            metrics::counter!("express_data_cache.invalid_offset").increment(1);
+            tracing::warn!(block_idx, block_offset, "block offset does not match block index");
+            return Err(DataCacheError::InvalidBlockOffset);
+        }
+        Ok(())
+    }
+
+    /// Get the checksum stored with a block, without downloading its content.
+    ///
+    /// Returns [None] if the block is not in the cache. The checksum covers the stored object, i.e. the
+    /// compressed data for compressed blocks. Blocks written with a multipart upload have no such checksum
+    /// and fail with [DataCacheError::InvalidBlockChecksum].
+    pub async fn block_checksum(&self, cache_key: &ObjectId, block_idx: BlockIndex) -> DataCacheResult<Option<Crc32c>> {
+        let object_key = block_key(self.key_hasher(), &self.prefix, cache_key, block_idx);
+        let tier = self.tier_for(cache_key, block_idx);
+        self.stored_checksum(&tier.bucket_name, &object_key).await
+    }
+
+    async fn stored_checksum(&self, bucket_name: &str, object_key: &str) -> DataCacheResult<Option<Crc32c>> {
+        let params = HeadObjectParams::new().checksum_mode(Some(ChecksumMode::Enabled));
+        self.throttle().await;
+        let result = match self
+            .client
+            .head_object(bucket_name, object_key, &params)
+            .in_current_span()
+            .await
+        {
+            Ok(result) => result,
+            Err(ObjectClientError::ServiceError(HeadObjectError::NotFound)) => return Ok(None),
+            Err(e) => return Err(DataCacheError::IoFailure(e.into())),
+        };
+        let crc32c_b64 = result
+            .checksum
+            .checksum_crc32c
+            .ok_or(DataCacheError::InvalidBlockChecksum)?;
+        let crc32c = crc32c_from_base64(&crc32c_b64).map_err(|_| DataCacheError::InvalidBlockChecksum)?;
+        Ok(Some(crc32c))
+    }
+
+    /// Estimate the cost of writing `block_count` full blocks to the cache, e.g. when warming it.
+    ///
+    /// Blocks larger than [ExpressDataCacheConfig::mpu_threshold_bytes] are written with multipart uploads,
+    /// which are never compressed. The estimate does not account for blocks skipped or retried.
+    pub fn estimate_fill_cost(&self, block_count: u64) -> FillCostEstimate {
+        let block_size = self.config.block_size;
+        let multipart = block_size > self.single_put_threshold() as u64
+            && !self.config.content_hash_in_key
+            && self.config.put_mode == PutMode::Multipart;
+        // Blocks written with their content hash in the key are also written under the standard key.
+        let copies = if self.config.content_hash_in_key { 2 } else { 1 };
+        let requests_per_block = if multipart {
+            let part_size = self.client.write_part_size().unwrap_or(block_size as usize) as u64;
+            // CreateMultipartUpload, one UploadPart per part, and CompleteMultipartUpload.
+            block_size.div_ceil(part_size) + 2
+        } else if self.config.object_manifest {
+            // The manifest is read and written back for every block.
+            copies + 2
+        } else {
+            copies
+        };
+        let uncompressed_bytes = block_count * block_size;
+        let stored_bytes = if self.config.compression.is_some() && !multipart {
+            (uncompressed_bytes as f64 * ESTIMATED_COMPRESSION_RATIO) as u64 * copies
+        } else {
+            uncompressed_bytes * copies
+        };
+        FillCostEstimate {
+            requests: block_count * requests_per_block,
+            uncompressed_bytes,
+            stored_bytes,
+        }
+    }
+
+    /// The keys in the cache bucket of the blocks in `block_range` of the object `cache_key`,
+    /// for looking them up during troubleshooting.
+    ///
+    /// Blocks written with [ExpressDataCacheConfig::content_hash_in_key] have an additional suffix.
+    pub fn debug_keys(&self, cache_key: &ObjectId, block_range: Range<BlockIndex>) -> Vec<String> {
+        block_range
+            .map(|block_idx| block_key(self.key_hasher(), &self.prefix, cache_key, block_idx))
+            .collect()
+    }
+
+    /// List the blocks of the object `cache_key` in the cache bucket, with the content hash in their key if any,
+    /// sorted by index. Blocks only written for compatible cache versions are not listed.
+    ///
+    /// Listings are reused for [ExpressDataCacheConfig::block_listing_ttl].
+    async fn list_cached_blocks(&self, cache_key: &ObjectId) -> DataCacheResult<Vec<(BlockIndex, Option<String>)>> {
+        let Some(ttl) = self.config.block_listing_ttl else {
+            return self.list_cached_blocks_uncached(cache_key).await;
+        };
+        if let Some(listing) = self.block_listings.lock().unwrap().get(cache_key) {
+            if self.clock.now() < listing.listed_at + ttl {
+                metrics::counter!("express_data_cache.block_listing_hits").increment(1);
+                return Ok(listing.blocks.clone());
+            }
+        }
+        let listed_at = self.clock.now();
+        let blocks = self.list_cached_blocks_uncached(cache_key).await?;
+        let mut block_listings = self.block_listings.lock().unwrap();
+        if block_listings.len() >= MAX_BLOCK_LISTINGS && !block_listings.contains_key(cache_key) {
+            // Forget an arbitrary object, as for prefetch hints.
+            if let Some(evicted) = block_listings.keys().next().cloned() {
+                block_listings.remove(&evicted);
+            }
+        }
+        let listing = BlockListing {
+            listed_at,
+            blocks: blocks.clone(),
+        };
+        block_listings.insert(cache_key.clone(), listing);
+        Ok(blocks)
+    }
+
+    /// Drop the listing of the blocks of the object `cache_key` after they changed.
+    fn invalidate_block_listing(&self, cache_key: &ObjectId) {
+        if self.config.block_listing_ttl.is_some() {
+            self.block_listings.lock().unwrap().remove(cache_key);
+        }
+    }
+
+    async fn list_cached_blocks_uncached(
+        &self,
+        cache_key: &ObjectId,
+    ) -> DataCacheResult<Vec<(BlockIndex, Option<String>)>> {
+        let version_prefix = key_prefix_for_version(self.key_hasher(), &self.prefix, cache_key);
+        let mut blocks = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            self.throttle().await;
+            let result = self
+                .client
+                .list_objects(
+                    &self.bucket_name,
+                    continuation_token.as_deref(),
+                    "",
+                    1000,
+                    &version_prefix,
+                )
+                .in_current_span()
+                .await?;
+            for object in result.objects {
+                let Some(suffix) = object.key.strip_prefix(&version_prefix) else {
+                    continue;
+                };
+                // Keys written with `content_hash_in_key` have the content hash after the block index.
+                let (block_idx, content_hash) = match suffix.split_once('.') {
+                    Some((_, COMPLETION_MARKER_EXTENSION)) => continue,
+                    Some((block_idx, content_hash)) => (block_idx, Some(content_hash.to_owned())),
+                    None => (suffix, None),
+                };
+                if let Ok(block_idx) = block_idx.parse::<BlockIndex>() {
+                    blocks.push((block_idx, content_hash));
+                }
+            }
+            continuation_token = result.next_continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        blocks.sort();
+        Ok(blocks)
+    }
+
+    /// Which blocks of the object `cache_key` in `range` are in the cache bucket, indexed from the start of
+    /// the range.
+    ///
+    /// The blocks are found by listing the keys of the object, which is much cheaper than a request for each
+    /// block of a dense range. Only blocks that [DataCache::get_block] looks up are reported, and they are not
+    /// validated, so a present block can still be read as a miss.
+    ///
+    /// Objects larger than [ExpressDataCacheConfig::max_object_size] are not cached, so the range is
+    /// clamped to the blocks of an object of that size, and shorter than requested past them.
+    pub async fn present_blocks(&self, cache_key: &ObjectId, range: Range<BlockIndex>) -> DataCacheResult<Vec<bool>> {
+        let max_block_count = (self.config.max_object_size as u64).div_ceil(self.config.block_size);
+        let range = range.start..range.end.min(max_block_count);
+        let mut present = vec![false; range.end.saturating_sub(range.start) as usize];
+        for (block_idx, content_hash) in self.list_cached_blocks(cache_key).await? {
+            if content_hash.is_none() && range.contains(&block_idx) {
+                present[(block_idx - range.start) as usize] = true;
+            }
+        }
+        Ok(present)
+    }
+
+    /// Whether [DataCache::get_block] would find the block `block_idx` of the object `cache_key`, without
+    /// side effects on the cache: no metrics are recorded, the [ExpressDataCache::hit_ratio] and the lookups
+    /// tracked for [ExpressDataCache::suggested_prefetch] are left unchanged, and no block listing is cached.
+    ///
+    /// The block is found with HeadObject requests and not downloaded, so it is not validated and can still
+    /// be read as a miss. Holes of sparse objects and blocks packed by [ExpressDataCache::compact_object]
+    /// are not reported.
+    pub async fn would_hit(&self, cache_key: &ObjectId, block_idx: BlockIndex) -> DataCacheResult<bool> {
+        if self
+            .local_mirror_path(cache_key, block_idx)
+            .is_some_and(|path| path.is_file())
+        {
+            return Ok(true);
+        }
+        let tier = self.tier_for(cache_key, block_idx);
+        let compatible_prefixes = self.compatible_prefixes.iter().map(|(_, prefix)| prefix.as_str());
+        for prefix in std::iter::once(self.prefix.as_str()).chain(compatible_prefixes) {
+            let object_key = block_key(self.key_hasher(), prefix, cache_key, block_idx);
+            if self.object_exists(&tier.bucket_name, &object_key).await?
+                && (!self.config.completion_markers || self.is_block_complete(&tier, &object_key).await?)
+            {
+                return Ok(true);
+            }
+        }
+        if self.config.deduplicate_blocks {
+            let object_key = block_reference_key(self.key_hasher(), &self.prefix, cache_key, block_idx);
+            return self.object_exists(&self.bucket_name, &object_key).await;
+        }
+        Ok(false)
+    }
+
+    async fn object_exists(&self, bucket_name: &str, object_key: &str) -> DataCacheResult<bool> {
+        self.throttle().await;
+        match self
+            .client
+            .head_object(bucket_name, object_key, &HeadObjectParams::new())
+            .in_current_span()
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(ObjectClientError::ServiceError(HeadObjectError::NotFound)) => Ok(false),
+            Err(e) => Err(DataCacheError::IoFailure(e.into())),
+        }
+    }
+
+    /// Write the cached blocks of the object `cache_key` to `writer`, in order of their index, returning the
+    /// number of bytes written.
+    ///
+    /// The output starts with [EXPORT_MAGIC], followed by a record for each block: its index and the length
+    /// of its data as 8 big-endian bytes each, its CRC32C checksum as 4 big-endian bytes, and its data.
+    /// It can be loaded into a cache with [ExpressDataCache::import_object]. Blocks written with
+    /// [ExpressDataCacheConfig::content_hash_in_key] or only for compatible cache versions are not exported.
+    pub async fn export_object(
+        &self,
+        cache_key: &ObjectId,
+        mut writer: impl AsyncWrite + Unpin,
+    ) -> DataCacheResult<u64> {
+        writer.write_all(EXPORT_MAGIC).await?;
+        let mut written = EXPORT_MAGIC.len() as u64;
+        for (block_idx, content_hash) in self.list_cached_blocks(cache_key).await? {
+            let Some(block_offset) = self.expected_offset(block_idx).filter(|_| content_hash.is_none()) else {
+                continue;
+            };
+            let Some(block) = self
+                .read_block_for_version(CACHE_VERSION, &self.prefix, cache_key, block_idx, block_offset, None)
+                .await?
+            else {
+                // Removed since it was listed.
+                continue;
+            };
+            let (data, checksum) = block
+                .bytes
+                .into_inner()
+                .map_err(|_| DataCacheError::InvalidBlockContent)?;
+            writer.write_all(&block_idx.to_be_bytes()).await?;
+            writer.write_all(&(data.len() as u64).to_be_bytes()).await?;
+            writer.write_all(&checksum.value().to_be_bytes()).await?;
+            writer.write_all(&data).await?;
+            written += (EXPORT_RECORD_HEADER_LEN + data.len()) as u64;
+        }
+        writer.flush().await?;
+        metrics::counter!("express_data_cache.exported_bytes").increment(written);
+        Ok(written)
+    }
+
+    /// Write the blocks exported by [ExpressDataCache::export_object] from `reader` to the cache, as blocks of
+    /// the object `cache_key` of size `object_size`. Returns the number of blocks imported.
+    ///
+    /// Fails on the first block that is corrupted or does not fit the block size of the cache. Blocks before it
+    /// are still imported.
+    pub async fn import_object(
+        &self,
+        cache_key: &ObjectId,
+        object_size: usize,
+        mut reader: impl AsyncRead + Unpin,
+    ) -> DataCacheResult<u64> {
+        let mut magic = [0u8; EXPORT_MAGIC.len()];
+        reader.read_exact(&mut magic).await?;
+        if &magic != EXPORT_MAGIC {
+            return Err(DataCacheError::InvalidBlockHeader("not an exported object".to_owned()));
+        }
+        let mut imported = 0;
+        loop {
+            let mut header = [0u8; EXPORT_RECORD_HEADER_LEN];
+            let mut filled = 0;
+            while filled < header.len() {
+                match reader.read(&mut header[filled..]).await? {
+                    0 => break,
+                    read => filled += read,
+                }
+            }
+            if filled == 0 {
+                break;
+            }
+            if filled < header.len() {
+                return Err(DataCacheError::InvalidBlockHeader("truncated block record".to_owned()));
+            }
+            let block_idx = u64::from_be_bytes(header[0..8].try_into().unwrap());
+            let len = u64::from_be_bytes(header[8..16].try_into().unwrap());
+            let checksum = Crc32c::new(u32::from_be_bytes(header[16..20].try_into().unwrap()));
+            if len > self.config.block_size {
+                return Err(DataCacheError::BlockTooLarge(self.config.block_size));
+            }
+            let block_offset = self
+                .expected_offset(block_idx)
+                .ok_or(DataCacheError::InvalidBlockOffset)?;
+
+            let mut data = vec![0u8; len as usize];
+            reader.read_exact(&mut data).await?;
+            let bytes = ChecksummedBytes::new_from_inner_data(data.into(), checksum);
+            bytes.validate().map_err(|_| DataCacheError::InvalidBlockChecksum)?;
+            self.put_block(cache_key.clone(), block_idx, block_offset, bytes, object_size)
+                .await?;
+            imported += 1;
+        }
+        Ok(imported)
+    }
+
+    /// Check the integrity of every cached block of the object `cache_key`.
+    ///
+    /// Each block is downloaded and its content compared with the checksum and metadata stored with it,
+    /// without keeping the data. Blocks only written for compatible cache versions are not checked.
+    pub async fn verify_object(&self, cache_key: &ObjectId) -> DataCacheResult<VerifyReport> {
+        let mut report = VerifyReport::default();
+        let blocks = self.list_cached_blocks(cache_key).await?;
+
+        let mut next_block_idx = 0;
+        for (block_idx, content_hash) in blocks {
+            report.missing.extend(next_block_idx..block_idx);
+            next_block_idx = next_block_idx.max(block_idx.saturating_add(1));
+
+            let Some(block_offset) = self.expected_offset(block_idx) else {
+                report.mismatched.push(block_idx);
+                continue;
+            };
+            match self
+                .read_block_for_version(
+                    CACHE_VERSION,
+                    &self.prefix,
+                    cache_key,
+                    block_idx,
+                    block_offset,
+                    content_hash.as_deref(),
+                )
+                .await
+            {
+                Ok(Some(block)) if block.bytes.validate().is_ok() => report.verified += 1,
+                Ok(Some(_))
+                | Err(
+                    DataCacheError::InvalidBlockHeader(_)
+                    | DataCacheError::InvalidBlockChecksum
+                    | DataCacheError::InvalidBlockContent
+                    | DataCacheError::InvalidBlockOffset
+                    | DataCacheError::BlockTooLarge(_),
+                ) => report.mismatched.push(block_idx),
+                Ok(None) => report.missing.push(block_idx),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Check the cached blocks of the object `cache_key` against its manifest, using only their checksums.
+    ///
+    /// Returns [None] if the object has no manifest, see [ExpressDataCacheConfig::object_manifest].
+    /// Only blocks listed in the manifest are checked.
+    pub async fn verify_manifest(&self, cache_key: &ObjectId) -> DataCacheResult<Option<VerifyReport>> {
+        let Some(manifest) = self.read_manifest(cache_key).await? else {
+            return Ok(None);
+        };
+        let mut report = VerifyReport::default();
+        for (block_idx, entry) in manifest.blocks {
+            match self.stored_checksum(&self.bucket_name, &entry.key).await {
+                Ok(Some(checksum)) if checksum.value() == entry.crc32c => report.verified += 1,
+                Ok(Some(_)) | Err(DataCacheError::InvalidBlockChecksum) => report.mismatched.push(block_idx),
+                Ok(None) => report.missing.push(block_idx),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Some(report))
+    }
+
+    async fn read_manifest(&self, cache_key: &ObjectId) -> DataCacheResult<Option<ObjectManifest>> {
+        let object_key = manifest_key(self.key_hasher(), &self.prefix, cache_key);
+        let Some(buffer) = self.read_small_object(&object_key).await? else {
+            return Ok(None);
+        };
+        let manifest: ObjectManifest = serde_json::from_slice(&buffer).map_err(|err| {
+            tracing::warn!(key = object_key, error = ?err, "manifest could not be deserialized");
+            DataCacheError::InvalidBlockContent
+        })?;
+        if manifest.root != manifest.compute_root() {
+            tracing::warn!(key = object_key, "manifest entries do not match its root hash");
+            return Err(DataCacheError::InvalidBlockContent);
+        }
+        Ok(Some(manifest))
+    }
+
+    /// Download an object of the cache bucket other than a block, such as a manifest, as a whole.
+    async fn read_small_object(&self, object_key: &str) -> DataCacheResult<Option<Vec<u8>>> {
+        self.read_object_range(object_key, None, u64::MAX).await
+    }
+
+    /// Download the `range` of an object of the cache bucket, or the whole object if [None].
+    ///
+    /// Fails with [DataCacheError::BlockTooLarge] once more than `max_size` bytes were received.
+    async fn read_object_range(
+        &self,
+        object_key: &str,
+        range: Option<Range<u64>>,
+        max_size: u64,
+    ) -> DataCacheResult<Option<Vec<u8>>> {
+        let mut result = match self.get_object_range(&self.bucket_name, object_key, range).await {
+            Ok(result) => result,
+            Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey)) => return Ok(None),
+            Err(e) => return Err(DataCacheError::IoFailure(e.into())),
+        };
+        let mut backpressure_handle = result.backpressure_handle().cloned();
+        self.ensure_read_window(backpressure_handle.as_mut());
+
+        let mut buffer = Vec::new();
+        pin_mut!(result);
+        while let Some(chunk) = result.next().await {
+            match chunk {
+                Ok((_offset, body)) => {
+                    if (buffer.len() + body.len()) as u64 > max_size {
+                        return Err(DataCacheError::BlockTooLarge(max_size));
+                    }
+                    buffer.extend_from_slice(&body);
+                    self.ensure_read_window(backpressure_handle.as_mut());
+                }
+                Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey)) => return Ok(None),
+                Err(e) => return Err(DataCacheError::IoFailure(e.into())),
+            }
+        }
+        Ok(Some(buffer))
+    }
+
+    /// Rewrite runs of consecutive cached blocks of the object `cache_key` into fewer, larger packs, then
+    /// delete the original blocks.
+    ///
+    /// Each pack holds up to the size above which blocks are written with a multipart upload (see
+    /// [ExpressDataCacheConfig::mpu_threshold_bytes]), and the offset and checksum of its blocks are listed
+    /// in a pack index stored next to them. Packed blocks are only read by caches with
+    /// [ExpressDataCacheConfig::compacted_blocks] set. The packs and the index are written before the
+    /// original blocks are deleted, so concurrent reads find each block in one place or the other. Blocks
+    /// written with [ExpressDataCacheConfig::content_hash_in_key] are not compacted.
+    pub async fn compact_object(&self, cache_key: &ObjectId) -> DataCacheResult<()> {
+        let block_size = self.config.block_size;
+        let blocks_per_pack = (self.single_put_threshold() as u64 / block_size.max(1)).max(1) as usize;
+        let mut runs: Vec<Vec<BlockIndex>> = Vec::new();
+        for (block_idx, content_hash) in self.list_cached_blocks(cache_key).await? {
+            if content_hash.is_some() {
+                continue;
+            }
+            match runs.last_mut() {
+                Some(run) if run.last().unwrap() + 1 == block_idx && run.len() < blocks_per_pack => run.push(block_idx),
+                _ => runs.push(vec![block_idx]),
+            }
+        }
+
+        let mut index = match self.read_pack_index(cache_key).await {
+            Ok(Some(index)) => index,
+            // Replace an invalid index, whose blocks cannot be read anyway.
+            Ok(None) | Err(DataCacheError::InvalidBlockContent) => PackIndex {
+                source_key: cache_key.key().to_owned(),
+                etag: cache_key.etag().as_str().to_owned(),
+                blocks: BTreeMap::new(),
+            },
+            Err(e) => return Err(e),
+        };
+        let mut packed = Vec::new();
+        'runs: for run in runs.into_iter().filter(|run| run.len() > 1) {
+            let pack_key = pack_key(self.key_hasher(), &self.prefix, cache_key, run[0]);
+            let mut data = Vec::new();
+            let mut entries = Vec::with_capacity(run.len());
+            for &block_idx in &run {
+                let block = self
+                    .read_block_for_version(
+                        CACHE_VERSION,
+                        &self.prefix,
+                        cache_key,
+                        block_idx,
+                        block_idx * block_size,
+                        None,
+                    )
+                    .await?;
+                let Some(block) = block else {
+                    // The block was deleted since it was listed.
+                    continue 'runs;
+                };
+                let (bytes, crc32c) = block
+                    .bytes
+                    .into_inner()
+                    .map_err(|_| DataCacheError::InvalidBlockChecksum)?;
+                let entry = PackedBlock {
+                    pack_key: pack_key.clone(),
+                    offset: data.len() as u64,
+                    len: bytes.len() as u64,
+                    crc32c: crc32c.value(),
+                };
+                entries.push((block_idx, entry));
+                data.extend_from_slice(&bytes);
+            }
+            let params = PutObjectSingleParams::new().checksum(Some(UploadChecksum::Crc32c(crc32c::checksum(&data))));
+            self.make_put_object_request(params, &pack_key, data).await?;
+            index.blocks.extend(entries);
+            packed.extend(run);
+        }
+        if packed.is_empty() {
+            return Ok(());
+        }
+
+        let data = serde_json::to_vec(&index).map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        let params = PutObjectSingleParams::new().checksum(Some(UploadChecksum::Crc32c(crc32c::checksum(&data))));
+        let index_key = pack_index_key(self.key_hasher(), &self.prefix, cache_key);
+        self.make_put_object_request(params, &index_key, data).await?;
+        self.invalidate_block_listing(cache_key);
+        for &block_idx in &packed {
+            let tier = self.tier_for(cache_key, block_idx);
+            let object_key = block_key(self.key_hasher(), &self.prefix, cache_key, block_idx);
+            self.throttle().await;
+            self.client.delete_object(&tier.bucket_name, &object_key).await?;
+        }
+        metrics::counter!("express_data_cache.compacted_blocks").increment(packed.len() as u64);
+        Ok(())
+    }
+
+    async fn read_pack_index(&self, cache_key: &ObjectId) -> DataCacheResult<Option<PackIndex>> {
+        let object_key = pack_index_key(self.key_hasher(), &self.prefix, cache_key);
+        let Some(buffer) = self.read_small_object(&object_key).await? else {
+            return Ok(None);
+        };
+        let index: PackIndex = serde_json::from_slice(&buffer).map_err(|err| {
+            tracing::warn!(key = object_key, error = ?err, "pack index could not be deserialized");
+            DataCacheError::InvalidBlockContent
+        })?;
+        if index.source_key != cache_key.key() || index.etag != cache_key.etag().as_str() {
+            tracing::warn!(key = object_key, "pack index was stored for another object");
+            return Err(DataCacheError::InvalidBlockContent);
+        }
+        Ok(Some(index))
+    }
+
+    /// Read a block rewritten by [ExpressDataCache::compact_object] from its pack.
+    async fn read_packed_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+    ) -> DataCacheResult<Option<DetailedBlock>> {
+        let Some(mut index) = self.read_pack_index(cache_key).await? else {
+            return Ok(None);
+        };
+        let Some(entry) = index.blocks.remove(&block_idx) else {
+            return Ok(None);
+        };
+        let range = entry.offset..entry.offset + entry.len;
+        let Some(data) = self.read_object_range(&entry.pack_key, Some(range), entry.len).await? else {
+            return Ok(None);
+        };
+        let crc32c = Crc32c::new(entry.crc32c);
+        if data.len() as u64 != entry.len || crc32c::checksum(&data) != crc32c {
+            return Err(DataCacheError::InvalidBlockChecksum);
+        }
+        metrics::counter!("express_data_cache.packed_block_hits").increment(1);
+        let stored_size = data.len();
+        let bytes = ChecksummedBytes::new_from_inner_data(data.into(), crc32c);
+        Ok(Some(DetailedBlock {
+            bytes,
+            stored_size,
+            stale: false,
+        }))
+    }
+
+    /// Store the user metadata of the object `cache_key` next to its blocks, replacing any stored before.
+    ///
+    /// Together with its blocks, this lets a fully cached object be served without a request to the source
+    /// bucket. Like blocks, the metadata is stored per version (ETag) of the object.
+    pub async fn put_object_metadata(
+        &self,
+        cache_key: &ObjectId,
+        metadata: &HashMap<String, String>,
+    ) -> DataCacheResult<()> {
+        let cached = CachedObjectMetadata {
+            source_key: cache_key.key().to_owned(),
+            etag: cache_key.etag().as_str().to_owned(),
+            metadata: metadata.clone(),
+        };
+        let data = serde_json::to_vec(&cached).map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        let params = PutObjectSingleParams::new().checksum(Some(UploadChecksum::Crc32c(crc32c::checksum(&data))));
+        let object_key = object_metadata_key(self.key_hasher(), &self.prefix, cache_key);
+        self.make_put_object_request(params, &object_key, data).await
+    }
+
+    /// Get the user metadata of the object `cache_key` stored with [ExpressDataCache::put_object_metadata].
+    ///
+    /// Returns [None] if no metadata was stored for this version of the object.
+    pub async fn get_object_metadata(&self, cache_key: &ObjectId) -> DataCacheResult<Option<HashMap<String, String>>> {
+        let object_key = object_metadata_key(self.key_hasher(), &self.prefix, cache_key);
+        let Some(buffer) = self.read_small_object(&object_key).await? else {
+            return Ok(None);
+        };
+        let cached: CachedObjectMetadata = serde_json::from_slice(&buffer).map_err(|err| {
+            tracing::warn!(key = object_key, error = ?err, "object metadata could not be deserialized");
+            DataCacheError::InvalidBlockContent
+        })?;
+        if cached.source_key != cache_key.key() || cached.etag != cache_key.etag().as_str() {
+            tracing::warn!(key = object_key, "object metadata was stored for another object");
+            return Err(DataCacheError::InvalidBlockContent);
+        }
+        Ok(Some(cached.metadata))
+    }
+
+    /// Whether the block `block_idx` is recorded as a hole, failing if the hole is not `expected_len` bytes long.
+    async fn is_hole(&self, cache_key: &ObjectId, block_idx: BlockIndex, expected_len: usize) -> DataCacheResult<bool> {
+        let object_key = hole_key(self.key_hasher(), &self.prefix, cache_key, block_idx);
+        let Some(buffer) = self.read_small_object(&object_key).await? else {
+            return Ok(false);
+        };
+        let hole: Hole = serde_json::from_slice(&buffer).map_err(|err| {
+            tracing::warn!(key = object_key, error = ?err, "hole marker could not be deserialized");
+            DataCacheError::InvalidBlockContent
+        })?;
+        if hole.source_key != cache_key.key() || hole.etag != cache_key.etag().as_str() {
+            tracing::warn!(key = object_key, "hole marker was stored for another object");
+            return Err(DataCacheError::InvalidBlockContent);
+        }
+        if hole.len != expected_len {
+            tracing::warn!(
+                key = object_key,
+                len = hole.len,
+                expected_len,
+                "hole has an unexpected length"
+            );
+            return Err(DataCacheError::InvalidBlockContent);
+        }
+        Ok(true)
+    }
+
+    /// Record the block `block_idx` of `len` zero bytes as a hole of the object `cache_key`.
+    ///
+    /// Each hole has its own marker, so recording it is a single request and concurrent writers of other
+    /// holes do not overwrite it.
+    async fn record_hole(&self, cache_key: &ObjectId, block_idx: BlockIndex, len: usize) -> DataCacheResult<()> {
+        let hole = Hole {
+            source_key: cache_key.key().to_owned(),
+            etag: cache_key.etag().as_str().to_owned(),
+            len,
+        };
+        let data = serde_json::to_vec(&hole).map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        let params = PutObjectSingleParams::new().checksum(Some(UploadChecksum::Crc32c(crc32c::checksum(&data))));
+        let object_key = hole_key(self.key_hasher(), &self.prefix, cache_key, block_idx);
+        self.make_put_object_request(params, &object_key, data).await
+    }
+
+    /// Record a block in the manifest of the object `cache_key`.
+    ///
+    /// The manifest is read, updated, and written back as a whole. A concurrent update by another
+    /// writer may be overwritten, in which case the blocks it recorded are no longer listed.
+    async fn update_manifest(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_key: String,
+        checksum: Crc32c,
+    ) -> DataCacheResult<()> {
+        let mut manifest = match self.read_manifest(cache_key).await {
+            Ok(manifest) => manifest.unwrap_or_default(),
+            // Replace an invalid manifest rather than failing every write for the object.
+            Err(DataCacheError::InvalidBlockContent) => Default::default(),
+            Err(e) => return Err(e),
+        };
+        manifest.insert(block_idx, block_key, checksum);
+        let data = serde_json::to_vec(&manifest).map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        let params = PutObjectSingleParams::new().checksum(Some(UploadChecksum::Crc32c(crc32c::checksum(&data))));
+        self.make_put_object_request(params, &manifest_key(self.key_hasher(), &self.prefix, cache_key), data)
+            .await
+    }
+
+    /// Delete the cached blocks of every version (ETag) of the object `key`.
+    ///
+    /// Blocks are deleted in batches, see [ExpressDataCache::clear]. Failed deletions do not stop the
+    /// invalidation, but are reported in the returned error. Returns the number of deleted blocks.
+    /// Content shared through [ExpressDataCacheConfig::deduplicate_blocks] is kept.
+    pub async fn invalidate_object(&self, key: &str) -> DataCacheResult<u64> {
+        let object_prefix = key_prefix_for_object(self.key_hasher(), &self.prefix, key);
+        self.remove_local_mirror(&object_prefix);
+        self.block_listings
+            .lock()
+            .unwrap()
+            .retain(|cache_key, _| cache_key.key() != key);
+        let deleted = if self.config.delete_by_subtree {
+            self.delete_subtrees(&object_prefix).await?
+        } else {
+            self.delete_under_prefix(&object_prefix, |_| true).await?
+        };
+        metrics::counter!("express_data_cache.invalidated_blocks").increment(deleted);
+        Ok(deleted)
+    }
+
+    /// Delete all the blocks in the cache, i.e. every object under the cache prefix apart from
+    /// the metadata written by [ExpressDataCache::verify_cache_valid].
+    ///
+    /// Listed objects are deleted with DeleteObjects requests of up to 1000 keys, with up to
+    /// [ExpressDataCacheConfig::delete_concurrency] concurrent requests. Failed deletions do not stop the
+    /// cleanup, but are reported in the returned error. Returns the number of deleted blocks.
+    pub async fn clear(&self) -> DataCacheResult<u64> {
+        let cache_prefix = format!("{}/", self.prefix);
+        let metadata_key = format!("{}/_mountpoint_cache_metadata", self.prefix);
+        self.remove_local_mirror(&cache_prefix);
+        self.block_listings.lock().unwrap().clear();
+        // The metadata is directly under the cache prefix, so it is not part of any subtree.
+        let deleted = if self.config.delete_by_subtree {
+            self.delete_subtrees(&cache_prefix).await?
+        } else {
+            self.delete_under_prefix(&cache_prefix, |object_key| object_key != metadata_key)
+                .await?
+        };
+        metrics::counter!("express_data_cache.cleared_blocks").increment(deleted);
+        Ok(deleted)
+    }
+
+    /// Delete the subtrees of `prefix`, i.e. the objects under the common prefixes found by listing it
+    /// with a `/` delimiter, one subtree at a time. Objects directly under `prefix` are kept.
+    ///
+    /// Errors of all subtrees are aggregated into the returned error, see [ExpressDataCache::delete_under_prefix].
+    async fn delete_subtrees(&self, prefix: &str) -> DataCacheResult<u64> {
+        let mut deleted = 0;
+        let mut subtrees = 0;
+        let mut failed_subtrees = 0;
+        let mut first_error = None;
+        let mut continuation_token = None;
+        loop {
+            self.throttle().await;
+            let result = self
+                .client
+                .list_objects(
+                    &self.bucket_name,
+                    continuation_token.as_deref(),
+                    "/",
+                    DELETE_BATCH_SIZE,
+                    prefix,
+                )
+                .in_current_span()
+                .await?;
+            for subtree in &result.common_prefixes {
+                subtrees += 1;
+                match self.delete_under_prefix(subtree, |_| true).await {
+                    Ok(subtree_deleted) => deleted += subtree_deleted,
+                    Err(e) => {
+                        failed_subtrees += 1;
+                        first_error.get_or_insert(e);
+                    }
+                }
+            }
+            continuation_token = result.next_continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        metrics::counter!("express_data_cache.deleted_subtrees").increment(subtrees - failed_subtrees);
+        match first_error {
+            None => Ok(deleted),
+            Some(DataCacheError::IoFailure(e)) => Err(DataCacheError::IoFailure(e.context(format!(
+                "failed to delete {failed_subtrees} of {subtrees} subtrees, deleted {deleted} blocks in the others"
+            )))),
+            Some(e) => Err(e),
+        }
+    }
+
+    /// Delete the objects under `prefix` for which `filter` returns true, with one DeleteObjects request
+    /// per listed page.
+    ///
+    /// Pages are listed one after another, with the DeleteObjects requests of up to
+    /// [ExpressDataCacheConfig::delete_concurrency] pages in flight. Objects failing to be deleted, on their
+    /// own or along with their whole batch, do not stop the deletion and are aggregated into the returned error.
+    async fn delete_under_prefix(&self, prefix: &str, filter: impl Fn(&str) -> bool) -> DataCacheResult<u64> {
+        let concurrency = self.config.delete_concurrency.max(1);
+        let mut outcome = DeleteOutcome::default();
+        let mut batches = 0;
+        let mut in_flight = FuturesUnordered::new();
+        let mut continuation_token = None;
+        loop {
+            self.throttle().await;
+            let result = self
+                .client
+                .list_objects(
+                    &self.bucket_name,
+                    continuation_token.as_deref(),
+                    "",
+                    DELETE_BATCH_SIZE,
+                    prefix,
+                )
+                .in_current_span()
+                .await?;
+            let keys: Vec<_> = result
+                .objects
+                .into_iter()
+                .map(|object| object.key)
+                .filter(|key| filter(key))
+                .collect();
+            if !keys.is_empty() {
+                batches += 1;
+                in_flight.push(self.delete_batch(keys));
+                if in_flight.len() >= concurrency {
+                    if let Some(batch) = in_flight.next().await {
+                        outcome.add(batch);
+                    }
+                }
+            }
+            continuation_token = result.next_continuation_token;
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        while let Some(batch) = in_flight.next().await {
+            outcome.add(batch);
+        }
+        metrics::counter!("express_data_cache.delete_batches").increment(batches);
+        match outcome.first_error {
+            None => Ok(outcome.deleted),
+            Some(e) => Err(DataCacheError::IoFailure(e.context(format!(
+                "failed to delete {} blocks, deleted {}",
+                outcome.failed, outcome.deleted
+            )))),
+        }
+    }
+
+    /// Delete `keys` with a single DeleteObjects request.
+    async fn delete_batch(&self, keys: Vec<String>) -> DeleteOutcome {
+        self.throttle().await;
+        let result = self
+            .client
+            .delete_objects(&self.bucket_name, &keys)
+            .in_current_span()
+            .await;
+        match result {
+            Ok(result) => {
+                let failed = result.errors.len() as u64;
+                DeleteOutcome {
+                    deleted: keys.len() as u64 - failed,
+                    failed,
+                    first_error: result.errors.first().map(|error| {
+                        anyhow::anyhow!("failed to delete {}: {} ({})", error.key, error.message, error.code)
+                    }),
+                }
+            }
+            Err(e) => DeleteOutcome {
+                deleted: 0,
+                failed: keys.len() as u64,
+                first_error: Some(anyhow::Error::new(e)),
+            },
+        }
+    }
+
+    /// Check that S3 accepts the credentials of the client, without requiring access to the cache bucket.
+    ///
+    /// Unlike [ExpressDataCache::verify_cache_valid], this succeeds if the cache bucket does not exist or
+    /// the credentials do not grant access to it, so that credential and bucket issues can be told apart.
+    pub async fn check_credentials(&self) -> CredentialsCheck {
+        self.throttle().await;
+        let result = self
+            .client
+            .list_objects(&self.bucket_name, None, "", 1, &self.prefix)
+            .in_current_span()
+            .await;
+        credentials_check(result.map(|_| ()))
+    }
+
+    pub async fn verify_cache_valid(&self) -> Result<(), DataCacheError> {
+        let object_key = format!("{}/_mountpoint_cache_metadata", &self.prefix);
+        // This data is human-readable, and not expected to be read by Mountpoint.
+        // The file format used here is NOT stable.
+        // For now, let's just include the data that's guaranteed to be correct as it's what
+        // calculates the prefix.
+        let data = format!(
+            "source_bucket={}\nblock_size={}",
+            self.source_bucket_name, self.config.block_size
+        );
+
+        // put_object is sufficient for validating cache, as S3 Directory buckets only support
+        // read-only, or read-write. Write implies read access.
+        // Validating we're in a directory bucket by using the `EXPRESS_ONEZONE` storage class.
+        let params = PutObjectSingleParams::new().storage_class("EXPRESS_ONEZONE".to_string());
+        self.make_put_object_request(params, &object_key, data).await
+    }
+
+    // Ensure the flow-control window is large enough for reading a block of data if backpressure is enabled.
+    /// Returns whether the read window was incremented.
+    fn ensure_read_window(&self, backpressure_handle: Option<&mut impl ClientBackpressureHandle>) -> bool {
+        if let Some(backpressure_handle) = backpressure_handle {
+            backpressure_handle.increment_read_window(self.config.block_size as usize);
+            return true;
+        }
+        false
+    }
+
+    async fn read_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+        content_hash: Option<&str>,
+    ) -> DataCacheResult<Option<DetailedBlock>> {
+        if object_size > self.config.max_object_size {
+            metrics::counter!("express_data_cache.over_max_object_size", "type" => "read").increment(1);
+            return Ok(None);
+        }
+
+        self.validate_block_offset(block_idx, block_offset)?;
+
+        if content_hash.is_none() {
+            if let Some(bytes) = self
+                .local_mirror_path(cache_key, block_idx)
+                .and_then(|path| read_local_mirror(&path))
+            {
+                metrics::counter!("express_data_cache.local_mirror_hits").increment(1);
+                let stored_size = bytes.len();
+                return Ok(Some(DetailedBlock {
+                    bytes,
+                    stored_size,
+                    stale: false,
+                }));
+            }
+        }
+
+        let compatible_prefixes = self
+            .compatible_prefixes
+            .iter()
+            .map(|(version, prefix)| (version.as_str(), prefix.as_str()));
+        for (cache_version, prefix) in std::iter::once((CACHE_VERSION, self.prefix.as_str())).chain(compatible_prefixes)
+        {
+            let block = self
+                .read_block_for_version(cache_version, prefix, cache_key, block_idx, block_offset, content_hash)
+                .await?;
+            if block.is_some() {
+                return Ok(block);
+            }
+        }
+
+        if self.config.sparse_holes && content_hash.is_none() {
+            // Holes span a whole block, or the rest of the object for its last block.
+            let len = (self.config.block_size as usize).min(object_size.saturating_sub(block_offset as usize));
+            if self.is_hole(cache_key, block_idx, len).await? {
+                metrics::counter!("express_data_cache.hole_hits").increment(1);
+                let bytes = ChecksummedBytes::new(Bytes::from(vec![0; len]));
+                return Ok(Some(DetailedBlock {
+                    bytes,
+                    stored_size: 0,
+                    stale: false,
+                }));
+            }
+        }
+
+        if self.config.deduplicate_blocks && content_hash.is_none() {
+            let block = self.read_deduplicated_block(cache_key, block_idx).await?;
+            if block.is_some() {
+                return Ok(block);
+            }
+        }
+
+        if self.config.compacted_blocks && content_hash.is_none() {
+            return self.read_packed_block(cache_key, block_idx).await;
+        }
+        Ok(None)
+    }
+
+    /// Read a block written by the cache version `cache_version` under its `prefix`.
+    async fn read_block_for_version(
+        &self,
+        cache_version: &str,
+        prefix: &str,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        content_hash: Option<&str>,
+    ) -> DataCacheResult<Option<DetailedBlock>> {
+        let object_key = match content_hash {
+            Some(content_hash) => {
+                block_key_with_content_hash(self.key_hasher(), prefix, cache_key, block_idx, content_hash)
+            }
+            None => block_key(self.key_hasher(), prefix, cache_key, block_idx),
+        };
+        let tier = self.tier_for(cache_key, block_idx);
+        if self.config.completion_markers && !self.is_block_complete(&tier, &object_key).await? {
+            metrics::counter!("express_data_cache.incomplete_blocks").increment(1);
+            return Ok(None);
+        }
+        let max_block_download_size = self.config.max_block_download_size.unwrap_or(self.config.block_size);
+        let mut buffered;
+        let mut attempt = 1;
+        let (mut buffer, object_metadata, object_checksum, trailing_checksum) = loop {
+            let mut result = match self.get_block_object(&tier.bucket_name, &object_key).await {
+                Ok(result) => result,
+                Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey)) => {
+                    return Ok(None);
+                }
+                Err(e) => {
+                    return Err(DataCacheError::IoFailure(e.into()));
+                }
+            };
+            let mut backpressure_handle = result.backpressure_handle().cloned();
+
+            // Guarantee that the request will start even in case of `initial_read_window == 0`.
+            let mut window_increments = u64::from(self.ensure_read_window(backpressure_handle.as_mut()));
+
+            let trailing_checksum = has_trailing_checksum(&result.get_object_metadata());
+            let max_stored_size = if trailing_checksum {
+                max_block_download_size + TRAILING_CHECKSUM_LEN as u64
+            } else {
+                max_block_download_size
+            };
+            let mut buffer: Bytes = Bytes::new();
+            // Chunks received ahead of the data before them, if `reorder_chunks` is set.
+            let mut early_chunks: BTreeMap<u64, Box<[u8]>> = BTreeMap::new();
+            let mut received = 0;
+            buffered = BufferedBytesGuard::new(&self.inflight_get_bytes);
+            let mut failure = None;
+            pin_mut!(result);
+            while let Some(chunk) = result.next().await {
+                match chunk {
+                    Ok((offset, body)) => {
+                        let in_order = offset == buffer.len() as u64;
+                        if !in_order && (!self.config.reorder_chunks || offset < buffer.len() as u64) {
+                            return Err(DataCacheError::InvalidBlockOffset);
+                        }
+                        received += body.len();
+                        if received as u64 > max_stored_size {
+                            return Err(DataCacheError::BlockTooLarge(max_block_download_size));
+                        }
+                        buffered.add(body.len());
+
+                        if in_order {
+                            buffer = append_chunk(buffer, body);
+                            while let Some(body) = early_chunks.remove(&(buffer.len() as u64)) {
+                                buffer = append_chunk(buffer, body);
+                            }
+                        } else if early_chunks.insert(offset, body).is_some() {
+                            return Err(DataCacheError::InvalidBlockOffset);
+                        }
+
+                        // Ensure the flow-control window is large enough.
+                        window_increments += u64::from(self.ensure_read_window(backpressure_handle.as_mut()));
+                    }
+                    Err(e @ ObjectClientError::ServiceError(GetObjectError::NoSuchKey)) => {
+                        if self.config.error_on_deleted_during_read {
+                            tracing::warn!(key = object_key, "block was deleted while being read");
+                            return Err(DataCacheError::IoFailure(e.into()));
+                        }
+                        return Ok(None);
+                    }
+                    Err(e) => {
+                        failure = Some(e);
+                        break;
+                    }
+                }
+            }
+            if let Some(e) = failure {
+                if attempt < self.config.max_mid_stream_attempts && self.classify_error(&e) == RetryDecision::Transient
+                {
+                    tracing::debug!(key = object_key, attempt, error = ?e, "retrying block request failed mid-stream");
+                    metrics::counter!("express_data_cache.mid_stream_retries").increment(1);
+                    attempt += 1;
+                    continue;
+                }
+                return Err(DataCacheError::IoFailure(e.into()));
+            }
+            if !early_chunks.is_empty() {
+                // Some data before the chunks received early is missing, or overlaps them.
+                return Err(DataCacheError::InvalidBlockOffset);
+            }
+            // Many increments for a block suggest that the chunks are small compared to the block size.
+            metrics::histogram!("express_cache.get.window_increments").record(window_increments as f64);
+
+            break (
+                buffer,
+                result.get_object_metadata(),
+                result.get_object_checksum(),
+                trailing_checksum,
+            );
+        };
+
+        let multipart = BlockMetadata::is_multipart(&object_metadata);
+        let (metadata_checksum, crc32c) = if trailing_checksum {
+            let (data, crc32c) = split_trailing_checksum(buffer)?;
+            buffer = data;
+            // The checksum of a block written with a multipart upload was not known when its metadata was written.
+            let metadata_checksum = if multipart { Crc32c::new(0) } else { crc32c };
+            (metadata_checksum, crc32c)
+        } else if multipart {
+            // Blocks written with a multipart upload always end with their checksum.
+            return Err(DataCacheError::InvalidBlockChecksum);
+        } else {
+            let checksum = object_checksum.map_err(|_| DataCacheError::InvalidBlockChecksum)?;
+            let crc32c = stored_crc32c(&checksum, || {
+                Some((crc32c::checksum(&buffer), crc64nvme::checksum(&buffer)))
+            })?;
+            (crc32c, crc32c)
+        };
+        let block_metadata = BlockMetadata::new_inner(
+            cache_version,
+            block_idx,
+            block_offset,
+            cache_key,
+            &self.source_bucket_name,
+            metadata_checksum,
+            multipart,
+        );
+        block_metadata.validate_object_metadata(&object_metadata)?;
+        if is_expired(&object_metadata, self.clock.now()) {
+            metrics::counter!("express_data_cache.expired_blocks").increment(1);
+            return Ok(None);
+        }
+
+        // S3 (or the trailing checksum) verified the stored bytes, and Zstd verifies the decompressed content.
+        let decompressed = match decompress_block(
+            &object_metadata,
+            &buffer,
+            self.config.compression.as_ref(),
+            self.config.block_size as usize,
+        )? {
+            None if self.config.detect_compression_magic => {
+                decompress_by_magic(&buffer, self.config.block_size as usize)
+            }
+            decompressed => decompressed,
+        };
+        let stored_size = buffer.len();
+        let bytes = match decompressed {
+            Some(decompressed) => ChecksummedBytes::new(decompressed),
+            None => ChecksummedBytes::new_from_inner_data(buffer, crc32c),
+        };
+
+        if let Some(content_hash) = content_hash {
+            // The object was found under the expected hash, so any difference means it was substituted.
+            let data = bytes
+                .clone()
+                .into_bytes()
+                .map_err(|_| DataCacheError::InvalidBlockContent)?;
+            if block_content_hash(&data) != content_hash {
+                return Err(DataCacheError::InvalidBlockContent);
+            }
+        }
+
+        Ok(Some(DetailedBlock {
+            bytes,
+            stored_size,
+            stale: false,
+        }))
+    }
+
+    /// Whether the completion marker of the block at `object_key` exists.
+    async fn is_block_complete(&self, tier: &Tier, object_key: &str) -> DataCacheResult<bool> {
+        self.object_exists(&tier.bucket_name, &completion_marker_key(object_key))
+            .await
+    }
+
+    /// Write the completion marker of the block at `object_key`, once the block was fully written.
+    async fn write_completion_marker(&self, tier: &Tier, object_key: &str) -> DataCacheResult<()> {
+        self.put_object_in_bucket(
+            &tier.bucket_name,
+            PutObjectSingleParams::new(),
+            &completion_marker_key(object_key),
+            Bytes::new(),
+        )
+        .await
+    }
+
+    /// Start a GetObject request for a block, failing over to the read replica if the cache bucket is unavailable.
+    async fn get_block_object(
+        &self,
+        bucket_name: &str,
+        object_key: &str,
+    ) -> ObjectClientResult<Client::GetObjectResponse, GetObjectError, Client::ClientError> {
+        self.get_object_range(bucket_name, object_key, None).await
+    }
+
+    async fn get_object_range(
+        &self,
+        bucket_name: &str,
+        object_key: &str,
+        range: Option<Range<u64>>,
+    ) -> ObjectClientResult<Client::GetObjectResponse, GetObjectError, Client::ClientError> {
+        retry_get(
+            object_key,
+            self.config.max_get_attempts,
+            self.config.retry_deadline,
+            self.config.max_retry_after,
+            |err| self.classify_error(err),
+            || self.get_block_object_once(bucket_name, object_key, range.clone()),
+        )
+        .await
+    }
+
+    /// Wait for the shared rate limiter of the cache bucket, if any, before a request to it.
+    async fn throttle(&self) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+    }
+
+    /// The header carrying the correlation ID of the current request, see [with_correlation_id].
+    fn correlation_header(&self) -> Option<(String, String)> {
+        let name = self.config.correlation_id_header.as_ref()?;
+        Some((name.clone(), current_correlation_id()?))
+    }
+
+    async fn get_block_object_once(
+        &self,
+        bucket_name: &str,
+        object_key: &str,
+        range: Option<Range<u64>>,
+    ) -> ObjectClientResult<Client::GetObjectResponse, GetObjectError, Client::ClientError> {
+        let mut params = GetObjectParams::new()
+            .range(range)
+            .checksum_mode(Some(ChecksumMode::Enabled))
+            .initial_read_window(self.config.initial_read_window);
+        if let Some((name, value)) = self.correlation_header() {
+            params = params.add_custom_header(name, value);
+        }
+        self.throttle().await;
+        let result = self.client.get_object(bucket_name, object_key, &params).await;
+        let Some(replica_bucket_name) = self
+            .config
+            .read_replica_bucket_name
+            .as_ref()
+            .filter(|_| bucket_name == self.bucket_name)
+        else {
+            return result;
+        };
+        match result {
+            Err(err) if is_unavailable(&err) => {
+                if !self.failed_over.swap(true, Ordering::SeqCst) {
+                    tracing::warn!(bucket=?self.bucket_name, error=?err, "cache bucket unavailable, failing over to read replica");
+                }
+                metrics::counter!("express_data_cache.replica_reads").increment(1);
+                self.client.get_object(replica_bucket_name, object_key, &params).await
+            }
+            result => {
+                if self.failed_over.swap(false, Ordering::SeqCst) {
+                    tracing::info!(bucket=?self.bucket_name, "cache bucket available again");
+                }
+                result
+            }
+        }
+    }
+
+    async fn write_block(
+        &self,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        bytes: ChecksummedBytes,
+        object_size: usize,
+    ) -> DataCacheResult<()> {
+        if self.config.read_only {
+            metrics::counter!("express_data_cache.writes_skipped", "reason" => "read_only").increment(1);
+            return Ok(());
+        }
+
+        if self.failed_over.load(Ordering::SeqCst) {
+            metrics::counter!("express_data_cache.writes_skipped", "reason" => "failed_over").increment(1);
+            return Ok(());
+        }
+
+        if object_size > self.config.max_object_size {
+            metrics::counter!("express_data_cache.over_max_object_size", "type" => "write").increment(1);
+            return Ok(());
+        }
+
+        if bytes.is_empty() && self.config.skip_empty_blocks {
+            metrics::counter!("express_data_cache.writes_skipped", "reason" => "empty").increment(1);
+            return Ok(());
+        }
+
+        if (bytes.len() as u64) < self.config.min_cacheable_block_size {
+            metrics::counter!("express_data_cache.writes_skipped", "reason" => "below_min_size").increment(1);
+            return Ok(());
+        }
+
+        if self.config.skip_unstable_etags && !is_md5_etag(cache_key.etag()) {
+            metrics::counter!("express_data_cache.writes_skipped", "reason" => "unstable_etag").increment(1);
+            return Ok(());
+        }
+
+        if let Some(evicted) = self.track_cached_object(&cache_key) {
+            self.evict_object(&evicted).await;
+        }
+
+        let is_hole = self.config.sparse_holes
+            && !bytes.is_empty()
+            && bytes
+                .clone()
+                .into_bytes()
+                .is_ok_and(|data| data.iter().all(|byte| *byte == 0));
+        if is_hole {
+            metrics::counter!("express_data_cache.holes_recorded").increment(1);
+            return self.record_hole(&cache_key, block_idx, bytes.len()).await;
+        }
+
+        let mirror = match &self.config.local_mirror {
+            Some(mirror) if mirror.mirror_writes => self
+                .local_mirror_path(&cache_key, block_idx)
+                .map(|path| (path, bytes.clone())),
+            _ => None,
+        };
+        if self.config.deduplicate_blocks {
+            self.write_deduplicated_block(&cache_key, block_idx, bytes).await?;
+        } else {
+            let mut writer = self.block_writer(cache_key, block_idx, block_offset)?;
+            writer.write(bytes).await?;
+            writer.complete().await?;
+        }
+        if let Some((path, bytes)) = mirror {
+            if let Err(error) = write_local_mirror(&path, bytes) {
+                tracing::warn!(?path, ?error, "failed to write block to local mirror");
+                metrics::counter!("express_data_cache.local_mirror_write_err").increment(1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Store the content of a block under its content hash unless it is already stored, then reference it
+    /// from the block `block_idx` of the object `cache_key`, see [ExpressDataCacheConfig::deduplicate_blocks].
+    async fn write_deduplicated_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        bytes: ChecksummedBytes,
+    ) -> DataCacheResult<()> {
+        let (data, checksum) = bytes.into_inner().map_err(|_| DataCacheError::InvalidBlockContent)?;
+        let content_key = content_key(self.key_hasher(), &self.prefix, &data);
+        self.throttle().await;
+        match self
+            .client
+            .head_object(&self.bucket_name, &content_key, &HeadObjectParams::new())
+            .in_current_span()
+            .await
+        {
+            Ok(_) => metrics::counter!("express_data_cache.deduplicated_blocks").increment(1),
+            Err(ObjectClientError::ServiceError(HeadObjectError::NotFound)) => {
+                let params = PutObjectSingleParams::new().checksum(Some(UploadChecksum::Crc32c(checksum)));
+                self.make_put_object_request(params, &content_key, &data).await?;
+            }
+            Err(e) => return Err(DataCacheError::IoFailure(e.into())),
+        }
+
+        let reference = BlockReference {
+            source_key: cache_key.key().to_owned(),
+            etag: cache_key.etag().as_str().to_owned(),
+            content_key,
+            len: data.len() as u64,
+            crc32c: checksum.value(),
+        };
+        let data = serde_json::to_vec(&reference).map_err(|err| DataCacheError::IoFailure(err.into()))?;
+        let params = PutObjectSingleParams::new().checksum(Some(UploadChecksum::Crc32c(crc32c::checksum(&data))));
+        let object_key = block_reference_key(self.key_hasher(), &self.prefix, cache_key, block_idx);
+        self.make_put_object_request(params, &object_key, data).await
+    }
+
+    /// Read a block written with [ExpressDataCacheConfig::deduplicate_blocks] through its reference.
+    async fn read_deduplicated_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+    ) -> DataCacheResult<Option<DetailedBlock>> {
+        let object_key = block_reference_key(self.key_hasher(), &self.prefix, cache_key, block_idx);
+        let Some(buffer) = self.read_small_object(&object_key).await? else {
+            return Ok(None);
+        };
+        let reference: BlockReference = serde_json::from_slice(&buffer).map_err(|err| {
+            tracing::warn!(key = object_key, error = ?err, "block reference could not be deserialized");
+            DataCacheError::InvalidBlockContent
+        })?;
+        if reference.source_key != cache_key.key() || reference.etag != cache_key.etag().as_str() {
+            tracing::warn!(key = object_key, "block reference was stored for another object");
+            return Err(DataCacheError::InvalidBlockContent);
+        }
+        let max_block_download_size = self.config.max_block_download_size.unwrap_or(self.config.block_size);
+        if reference.len > max_block_download_size {
+            return Err(DataCacheError::BlockTooLarge(max_block_download_size));
+        }
+        let Some(data) = self
+            .read_object_range(&reference.content_key, None, max_block_download_size)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let crc32c = Crc32c::new(reference.crc32c);
+        if data.len() as u64 != reference.len || crc32c::checksum(&data) != crc32c {
+            return Err(DataCacheError::InvalidBlockChecksum);
+        }
+        metrics::counter!("express_data_cache.deduplicated_block_hits").increment(1);
+        let stored_size = data.len();
+        let bytes = ChecksummedBytes::new_from_inner_data(data.into(), crc32c);
+        Ok(Some(DetailedBlock {
+            bytes,
+            stored_size,
+            stale: false,
+        }))
+    }
+
+    /// Apply the [ExpressDataCacheConfig::checksum_mismatch_policy] to the result of reading a block.
+    async fn handle_checksum_mismatch(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        content_hash: Option<&str>,
+        read_result: DataCacheResult<Option<DetailedBlock>>,
+    ) -> DataCacheResult<Option<DetailedBlock>> {
+        let policy = self.config.checksum_mismatch_policy;
+        if policy == ChecksumMismatchPolicy::Error {
+            return read_result;
+        }
+        match read_result {
+            Ok(Some(block)) if block.bytes.validate().is_ok() => return Ok(Some(block)),
+            Ok(Some(_)) | Err(DataCacheError::InvalidBlockChecksum) => {}
+            result => return result,
+        }
+        tracing::warn!(?cache_key, block_idx, "cached block does not match its checksum");
+        metrics::counter!("express_data_cache.checksum_mismatches").increment(1);
+        if policy == ChecksumMismatchPolicy::MissAndDelete {
+            let object_key = match content_hash {
+                Some(content_hash) => {
+                    block_key_with_content_hash(self.key_hasher(), &self.prefix, cache_key, block_idx, content_hash)
+                }
+                None => block_key(self.key_hasher(), &self.prefix, cache_key, block_idx),
+            };
+            let tier = self.tier_for(cache_key, block_idx);
+            self.invalidate_block_listing(cache_key);
+            self.throttle().await;
+            if let Err(error) = self.client.delete_object(&tier.bucket_name, &object_key).await {
+                tracing::warn!(key = object_key, ?error, "failed to delete corrupted block");
+                metrics::counter!("express_data_cache.corrupted_block_delete_err").increment(1);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Record a lookup of the object `cache_key`, returning whether it is the first one by this instance.
+    ///
+    /// Only the [MAX_ACCESSED_OBJECTS] most recently looked up objects are remembered, so that the next
+    /// lookup of an object forgotten since is reported as a first one again.
+    fn record_access(&self, cache_key: &ObjectId) -> bool {
+        let mut accessed_objects = self.accessed_objects.lock().unwrap();
+        if accessed_objects.get_refresh(cache_key).is_some() {
+            return false;
+        }
+        if accessed_objects.len() >= MAX_ACCESSED_OBJECTS {
+            accessed_objects.pop_front();
+        }
+        accessed_objects.insert(cache_key.clone(), ());
+        true
+    }
+
+    /// Mark the object `cache_key` as the most recently used, returning the object to evict if this
+    /// exceeds [ExpressDataCacheConfig::max_cached_objects].
+    fn track_cached_object(&self, cache_key: &ObjectId) -> Option<ObjectId> {
+        let max_cached_objects = self.config.max_cached_objects?.max(1);
+        let mut cached_objects = self.cached_objects.as_ref()?.lock().unwrap();
+        if cached_objects.get_refresh(cache_key).is_some() {
+            return None;
+        }
+        cached_objects.insert(cache_key.clone(), ());
+        if cached_objects.len() > max_cached_objects {
+            return cached_objects.pop_front().map(|(evicted, ())| evicted);
+        }
+        None
+    }
+
+    /// Retain a copy of a block read from the cache bucket, see [ExpressDataCacheConfig::stale_on_error_blocks].
+    fn retain_block(&self, cache_key: &ObjectId, block_idx: BlockIndex, block: &DetailedBlock) {
+        let (Some(max_blocks), Some(retained_blocks)) = (self.config.stale_on_error_blocks, &self.retained_blocks)
+        else {
+            return;
+        };
+        let mut retained_blocks = retained_blocks.lock().unwrap();
+        let block = DetailedBlock {
+            stale: false,
+            ..block.clone()
+        };
+        retained_blocks.insert((cache_key.clone(), block_idx), block);
+        while retained_blocks.len() > max_blocks {
+            retained_blocks.pop_front();
+        }
+    }
+
+    /// The retained copy of a block, to serve when reading it from the cache bucket failed.
+    fn stale_block(&self, cache_key: &ObjectId, block_idx: BlockIndex) -> Option<DetailedBlock> {
+        let mut retained_blocks = self.retained_blocks.as_ref()?.lock().unwrap();
+        let block = retained_blocks.get_refresh(&(cache_key.clone(), block_idx))?;
+        Some(DetailedBlock {
+            stale: true,
+            ..block.clone()
+        })
+    }
+
+    /// Delete every object stored for the object version `cache_key` in the cache bucket.
+    ///
+    /// Failures are logged rather than failing the write which caused the eviction.
+    async fn evict_object(&self, cache_key: &ObjectId) {
+        self.invalidate_block_listing(cache_key);
+        let version_prefix = key_prefix_for_version(self.key_hasher(), &self.prefix, cache_key);
+        self.remove_local_mirror(&version_prefix);
+        match self.delete_under_prefix(&version_prefix, |_| true).await {
+            Ok(deleted) => {
+                metrics::counter!("express_data_cache.evicted_objects").increment(1);
+                metrics::counter!("express_data_cache.evicted_blocks").increment(deleted);
+            }
+            Err(error) => {
+                tracing::warn!(?cache_key, ?error, "failed to evict object");
+                metrics::counter!("express_data_cache.eviction_err").increment(1);
+            }
+        }
+    }
+
+    /// Path of the block in the [ExpressDataCacheConfig::local_mirror] directory, if configured.
+    fn local_mirror_path(&self, cache_key: &ObjectId, block_idx: BlockIndex) -> Option<PathBuf> {
+        let mirror = self.config.local_mirror.as_ref()?;
+        Some(
+            mirror
+                .directory
+                .join(block_key(self.key_hasher(), &self.prefix, cache_key, block_idx)),
+        )
+    }
+
+    /// Remove the files of the blocks under `key_prefix` from the [ExpressDataCacheConfig::local_mirror]
+    /// directory, if configured.
+    ///
+    /// Failures are logged, so that the blocks are still deleted from the cache bucket.
+    fn remove_local_mirror(&self, key_prefix: &str) {
+        let Some(mirror) = &self.config.local_mirror else {
+            return;
+        };
+        let path = mirror.directory.join(key_prefix);
+        match fs::remove_dir_all(&path) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(error) => {
+                tracing::warn!(?path, ?error, "failed to remove blocks from local mirror");
+                metrics::counter!("express_data_cache.local_mirror_remove_err").increment(1);
+            }
+        }
+    }
+
+    /// Get a block of data from the cache, optionally requiring that its content matches `expected_content_hash`.
+    ///
+    /// With an expected hash, the block is looked up under the key written when
+    /// [ExpressDataCacheConfig::content_hash_in_key] is enabled (see [block_content_hash]), so a block
+    /// with different content is reported as a miss. Without it, the standard key is used.
+    pub async fn get_block_with_content_hash(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+        expected_content_hash: Option<&str>,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        let block = self
+            .lookup_block(
+                cache_key,
+                block_idx,
+                block_offset,
+                object_size,
+                expected_content_hash,
+                DEFAULT_GET_PRIORITY,
+            )
+            .await?;
+        Ok(block.map(|block| block.bytes))
+    }
+
+    /// Get a block of data from the cache, with the given priority over other lookups waiting for
+    /// [ExpressDataCacheConfig::max_concurrent_gets].
+    ///
+    /// Lookups with a higher priority are served first. [DataCache::get_block] uses
+    /// [DEFAULT_GET_PRIORITY], so e.g. prefetching can use a lower priority than reads blocking a caller.
+    pub async fn get_block_with_priority(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+        priority: i32,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        let block = self
+            .lookup_block(cache_key, block_idx, block_offset, object_size, None, priority)
+            .await?;
+        Ok(block.map(|block| block.bytes))
+    }
+
+    /// Get a block of data from the cache along with the size it is stored with, see [DetailedBlock].
+    pub async fn get_block_detailed(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+    ) -> DataCacheResult<Option<DetailedBlock>> {
+        self.lookup_block(
+            cache_key,
+            block_idx,
+            block_offset,
+            object_size,
+            None,
+            DEFAULT_GET_PRIORITY,
+        )
+        .await
+    }
+
+    /// Whether `block` is of `block_size`, unless it is the last block of the object, if
+    /// [ExpressDataCacheConfig::verify_block_length] is set.
+    fn has_expected_length(&self, block: &DetailedBlock, block_offset: u64, object_size: usize) -> bool {
+        let is_last_block = block_offset.saturating_add(self.config.block_size) >= object_size as u64;
+        !self.config.verify_block_length || is_last_block || block.bytes.len() as u64 == self.config.block_size
+    }
+
+    fn is_over_memory_limit(&self) -> bool {
+        self.config
+            .max_inflight_get_bytes
+            .is_some_and(|max| self.inflight_get_bytes() >= max)
+    }
+
+    async fn lookup_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+        expected_content_hash: Option<&str>,
+        priority: i32,
+    ) -> DataCacheResult<Option<DetailedBlock>> {
+        let _inflight = InflightGuard::new(&self.inflight_gets);
+        let start = Instant::now();
+        let read_result = if self.is_over_memory_limit() {
+            metrics::counter!("express_data_cache.memory_limited_gets").increment(1);
+            Ok(None)
+        } else {
+            let _permit = match &self.get_gate {
+                Some(gate) => Some(gate.acquire(priority).await),
+                None => None,
+            };
+            let read_result = self
+                .read_block(cache_key, block_idx, block_offset, object_size, expected_content_hash)
+                .await;
+            self.handle_checksum_mismatch(cache_key, block_idx, expected_content_hash, read_result)
+                .await
+        };
+        let read_result = match read_result {
+            Err(DataCacheError::IoFailure(err)) => match self.stale_block(cache_key, block_idx) {
+                Some(block) => {
+                    tracing::warn!(?cache_key, block_idx, error = ?err, "serving stale copy of block after read failure");
+                    metrics::counter!("express_data_cache.stale_blocks_served").increment(1);
+                    Ok(Some(block))
+                }
+                None => Err(DataCacheError::IoFailure(err)),
+            },
+            read_result => read_result,
+        };
+        let (result, result_type) = match read_result {
+            Ok(Some(block)) if !self.has_expected_length(&block, block_offset, object_size) => {
+                tracing::warn!(
+                    ?cache_key,
+                    block_idx,
+                    len = block.bytes.len(),
+                    "cached block is truncated"
+                );
+                metrics::counter!("express_data_cache.block_hit").increment(0);
+                metrics::counter!("express_data_cache.truncated_blocks").increment(1);
+                (Ok(None), "miss")
+            }
+            Ok(Some(block)) => {
+                metrics::counter!("express_data_cache.block_hit").increment(1);
+                metrics::counter!("express_data_cache.total_bytes", "type" => "read")
+                    .increment(block.bytes.len() as u64);
+                if let Some(cached_objects) = &self.cached_objects {
+                    cached_objects.lock().unwrap().get_refresh(cache_key);
+                }
+                if !block.stale {
+                    self.retain_block(cache_key, block_idx, &block);
+                }
+                (Ok(Some(block)), "ok")
+            }
+            Ok(None) => {
+                metrics::counter!("express_data_cache.block_hit").increment(0);
+                (Ok(None), "miss")
+            }
+            Err(err) => {
+                metrics::counter!("express_data_cache.block_hit").increment(0);
+                metrics::counter!("express_data_cache.block_err", "reason" => err.code(), "type" => "read")
+                    .increment(1);
+                (Err(err), "error")
+            }
+        };
+        let duration_us = start.elapsed().as_micros() as f64;
+        metrics::histogram!("express_data_cache.read_duration_us", "type" => result_type).record(duration_us);
+        if self.record_access(cache_key) {
+            metrics::histogram!("express_cache.cold_get.duration_us", "type" => result_type).record(duration_us);
+        } else {
+            metrics::histogram!("express_cache.warm_get.duration_us", "type" => result_type).record(duration_us);
+        }
+        self.recent_lookups
+            .lock()
+            .unwrap()
+            .record(matches!(result, Ok(Some(_))));
+        if let Some(access_tracker) = &self.access_tracker {
+            access_tracker.lock().unwrap().record(cache_key, block_idx);
+        }
+        result
+    }
+
+    /// Stream the content of a block as it is downloaded from the cache bucket, without buffering it.
+    ///
+    /// The stream is empty if the block is not in the cache. The checksum and metadata stored with the block
+    /// are only validated once it was fully downloaded, so a block that fails validation ends with an error
+    /// after its chunks were yielded, and the caller must discard them. Compressed blocks can only be
+    /// decompressed as a whole and are yielded as a single chunk. Blocks only written for compatible
+    /// cache versions are not found.
+    pub fn get_block_stream<'a>(
+        &'a self,
+        cache_key: &'a ObjectId,
+        block_idx: BlockIndex,
+    ) -> impl Stream<Item = DataCacheResult<Bytes>> + 'a {
+        try_stream! {
+            let block_offset = self.expected_offset(block_idx).ok_or(DataCacheError::InvalidBlockOffset)?;
+            let object_key = block_key(self.key_hasher(), &self.prefix, cache_key, block_idx);
+            let tier = self.tier_for(cache_key, block_idx);
+            let response = match self.get_block_object(&tier.bucket_name, &object_key).await {
+                Ok(response) => Some(response),
+                Err(ObjectClientError::ServiceError(GetObjectError::NoSuchKey)) => None,
+                Err(e) => Err(DataCacheError::IoFailure(e.into()))?,
+            };
+            if let Some(mut result) = response {
+                let mut backpressure_handle = result.backpressure_handle().cloned();
+                self.ensure_read_window(backpressure_handle.as_mut());
+
+                let max_block_download_size = self.config.max_block_download_size.unwrap_or(self.config.block_size);
+                let mut len = 0;
+                let mut hasher = crc32c::Hasher::new();
+                let mut crc64nvme_hasher = None;
+                let mut compressed = None;
+                let mut trailing_checksum = None;
+                let mut trailer = Bytes::new();
+                pin_mut!(result);
+                while let Some(chunk) = result.next().await {
+                    let (offset, body) = chunk.map_err(|e| DataCacheError::IoFailure(e.into()))?;
+                    if offset != len {
+                        Err(DataCacheError::InvalidBlockOffset)?;
+                    }
+                    len += body.len() as u64;
+                    let trailing_checksum = *trailing_checksum
+                        .get_or_insert_with(|| has_trailing_checksum(&result.get_object_metadata()));
+                    let max_stored_size = if trailing_checksum {
+                        max_block_download_size + TRAILING_CHECKSUM_LEN as u64
+                    } else {
+                        max_block_download_size
+                    };
+                    if len > max_stored_size {
+                        Err(DataCacheError::BlockTooLarge(max_block_download_size))?;
+                    }
+                    let chunks = if trailing_checksum {
+                        hold_back_trailer(&mut trailer, Bytes::from(body))
+                    } else {
+                        [Bytes::from(body), Bytes::new()]
+                    };
+
+                    // Whether the block is compressed is known from the headers received with the first chunk.
+                    // Compressed data is buffered until it can be decompressed.
+                    let buffer = compressed.get_or_insert_with(|| {
+                        result.get_object_metadata().contains_key(COMPRESSION_HEADER).then(BytesMut::new)
+                    });
+                    for data in chunks.into_iter().filter(|data| !data.is_empty()) {
+                        hasher.update(&data);
+                        // Blocks without a CRC32C checksum are validated against their CRC64NVME checksum.
+                        let crc64nvme_hasher = crc64nvme_hasher.get_or_insert_with(|| {
+                            (!trailing_checksum
+                                && result
+                                    .get_object_checksum()
+                                    .is_ok_and(|checksum| checksum.checksum_crc32c.is_none()))
+                            .then(Crc64nvmeHasher::new)
+                        });
+                        if let Some(crc64nvme_hasher) = crc64nvme_hasher {
+                            crc64nvme_hasher.update(&data);
+                        }
+                        match buffer {
+                            Some(buffer) => buffer.extend_from_slice(&data),
+                            None => yield data,
+                        }
+                    }
+                    self.ensure_read_window(backpressure_handle.as_mut());
+                }
+
+                let object_metadata = result.get_object_metadata();
+                let multipart = BlockMetadata::is_multipart(&object_metadata);
+                let crc32c = hasher.finalize();
+                let metadata_checksum = if has_trailing_checksum(&object_metadata) {
+                    let stored = trailer_checksum(&trailer)?;
+                    if stored != crc32c {
+                        Err(DataCacheError::InvalidBlockChecksum)?;
+                    }
+                    if multipart {
+                        Crc32c::new(0)
+                    } else {
+                        stored
+                    }
+                } else if multipart {
+                    Err(DataCacheError::InvalidBlockChecksum)?
+                } else {
+                    let checksum = result
+                        .get_object_checksum()
+                        .map_err(|_| DataCacheError::InvalidBlockChecksum)?;
+                    let stored_crc32c = stored_crc32c(&checksum, || {
+                        crc64nvme_hasher.flatten().map(|crc64nvme_hasher| (crc32c, crc64nvme_hasher.finalize()))
+                    })?;
+                    if stored_crc32c != crc32c {
+                        Err(DataCacheError::InvalidBlockChecksum)?;
+                    }
+                    stored_crc32c
+                };
+                let block_metadata = BlockMetadata::new_inner(
+                    CACHE_VERSION,
+                    block_idx,
+                    block_offset,
+                    cache_key,
+                    &self.source_bucket_name,
+                    metadata_checksum,
+                    multipart,
+                );
+                block_metadata.validate_object_metadata(&object_metadata)?;
+
+                if let Some(Some(buffer)) = compressed {
+                    let decompressed = decompress_block(
+                        &object_metadata,
+                        &buffer,
+                        self.config.compression.as_ref(),
+                        self.config.block_size as usize,
+                    )?;
+                    if let Some(decompressed) = decompressed {
+                        yield decompressed;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Blocks of the object `cache_key` likely to be read next, based on the lookups made so far.
+    ///
+    /// Once consecutive blocks were looked up, returns the [ExpressDataCacheConfig::prefetch_hint_blocks]
+    /// blocks following the last one. Returns no blocks otherwise, or if tracking is disabled.
+    pub fn suggested_prefetch(&self, cache_key: &ObjectId) -> Vec<BlockIndex> {
+        match &self.access_tracker {
+            Some(access_tracker) => access_tracker
+                .lock()
+                .unwrap()
+                .next_blocks(cache_key, self.config.prefetch_hint_blocks),
+            None => Vec::new(),
+        }
+    }
+
+    /// Fraction of the most recent block lookups that were hits, over a window of
+    /// [ExpressDataCacheConfig::hit_ratio_window] lookups. Failed lookups count as misses.
+    ///
+    /// Returns 0 if no lookups were made yet.
+    pub fn hit_ratio(&self) -> f64 {
+        self.recent_lookups.lock().unwrap().ratio()
+    }
+
+    /// Number of block reads and writes currently in progress.
+    ///
+    /// Operations are counted until they complete or their future is dropped, so a count that stays
+    /// above 0 without progress points to requests stuck on the cache bucket.
+    pub fn inflight(&self) -> InflightCounts {
+        InflightCounts {
+            gets: self.inflight_gets.load(Ordering::SeqCst),
+            puts: self.inflight_puts.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Number of bytes buffered by the block reads in progress, also reported by the
+    /// `express_data_cache.inflight_get_bytes` gauge.
+    ///
+    /// Blocks streamed with [ExpressDataCache::get_block_stream] are not counted.
+    pub fn inflight_get_bytes(&self) -> usize {
+        self.inflight_get_bytes.load(Ordering::SeqCst)
+    }
+
+    /// Current limit of the blocks written at a time, also reported by the
+    /// `express_data_cache.put_concurrency_limit` gauge, or [None] if
+    /// [ExpressDataCacheConfig::adaptive_put_concurrency] is not set.
+    pub fn put_concurrency_limit(&self) -> Option<usize> {
+        self.put_concurrency.as_ref().map(AdaptiveConcurrencyLimit::limit)
+    }
+}
+
+#[async_trait]
+impl<Client> DataCache for ExpressDataCache<Client>
+where
+    Client: ObjectClient + Send + Sync + 'static,
+{
+    async fn get_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        self.get_block_with_content_hash(cache_key, block_idx, block_offset, object_size, None)
+            .await
+    }
+
+    async fn put_block(
+        &self,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        bytes: ChecksummedBytes,
+        object_size: usize,
+    ) -> DataCacheResult<()> {
+        let _inflight = InflightGuard::new(&self.inflight_puts);
+        let _permit = match &self.put_concurrency {
+            Some(put_concurrency) => Some(put_concurrency.acquire().await),
+            None => None,
+        };
+        let start = Instant::now();
+        let write_result = self
+            .write_block(cache_key, block_idx, block_offset, bytes, object_size)
+            .await;
+        if let Some(put_concurrency) = &self.put_concurrency {
+            // Only failed requests to the cache bucket signal that it is overloaded.
+            put_concurrency.record(!matches!(write_result, Err(DataCacheError::IoFailure(_))));
+        }
+        let (result, result_type) = match write_result {
+            Ok(()) => {
+                metrics::counter!("express_data_cache.total_bytes", "type" => "write").increment(object_size as u64);
+                (Ok(()), "ok")
+            }
+            Err(err) => {
+                metrics::counter!("express_data_cache.block_err", "reason" => err.code(), "type" => "write")
+                    .increment(1);
+                (Err(err), "error")
+            }
+        };
+        metrics::histogram!("express_data_cache.write_duration_us", "type" => result_type)
+            .record(start.elapsed().as_micros() as f64);
+        result
+    }
+
+    fn block_size(&self) -> u64 {
+        self.config.block_size
+    }
+
+    fn max_block_size(&self) -> u64 {
+        MAX_OBJECT_SIZE
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "express"
+    }
+
+    fn supports_ranged_reads(&self) -> bool {
+        // Blocks are validated against the checksum of the whole block, and there is no API to read
+        // a range of a block yet.
+        false
+    }
+}
+
+/// Writer for a single block of an [ExpressDataCache], created by [ExpressDataCache::block_writer].
+///
+/// Blocks that fit within [ExpressDataCacheConfig::mpu_threshold_bytes] are uploaded with a single PutObject
+/// request on [ExpressBlockWriter::complete]. Larger blocks are written with a multipart upload, started as
+/// soon as the buffered data exceeds the threshold. When [ExpressDataCacheConfig::content_hash_in_key]
+/// is enabled, the object key depends on the complete block content, so data is always buffered.
+/// [ExpressDataCacheConfig::compression] only applies to blocks written with a single PutObject request.
+pub struct ExpressBlockWriter<'a, Client: ObjectClient> {
+    cache: &'a ExpressDataCache<Client>,
+    cache_key: ObjectId,
+    block_idx: BlockIndex,
+    block_offset: u64,
+    tier: Tier,
+    single_put_threshold: usize,
+    checksum: Crc32c,
+    len: usize,
+    state: BlockWriterState<Client::PutObjectRequest>,
+}
+
+enum BlockWriterState<Request> {
+    /// Data is held in memory until it is known whether it fits in a single PutObject request.
+    Buffering(Vec<Bytes>),
+    /// Data exceeded the single PutObject threshold and is written with a multipart upload.
+    Multipart(Request),
+}
+
+impl<Client> ExpressBlockWriter<'_, Client>
+where
+    Client: ObjectClient + Send + Sync + 'static,
+{
+    /// Append data to the block.
+    pub async fn write(&mut self, bytes: ChecksummedBytes) -> DataCacheResult<()> {
+        let (data, checksum) = bytes.into_inner().map_err(|_| DataCacheError::InvalidBlockContent)?;
+        self.checksum = combine_checksums(self.checksum, checksum, data.len());
+        self.len += data.len();
+        match &mut self.state {
+            BlockWriterState::Buffering(chunks) => {
+                chunks.push(data);
+                if self.len > self.single_put_threshold && !self.cache.config.content_hash_in_key {
+                    if self.cache.config.put_mode == PutMode::SinglePartOnly {
+                        return Err(DataCacheError::BlockTooLarge(self.single_put_threshold as u64));
+                    }
+                    let chunks = std::mem::take(chunks);
+                    let block_metadata = BlockMetadata::new_multipart(
+                        self.block_idx,
+                        self.block_offset,
+                        &self.cache_key,
+                        &self.cache.source_bucket_name,
+                    );
+                    let object_key = block_key(
+                        self.cache.key_hasher(),
+                        &self.cache.prefix,
+                        &self.cache_key,
+                        self.block_idx,
+                    );
+                    let mut request = self
+                        .cache
+                        .start_multipart_upload(&block_metadata, &self.tier, &object_key)
+                        .await?;
+                    for chunk in chunks {
+                        request.write(&chunk).await?;
+                    }
+                    self.state = BlockWriterState::Multipart(request);
+                }
+            }
+            BlockWriterState::Multipart(request) => request.write(&data).await?,
+        }
+        Ok(())
+    }
+
+    /// Finish writing the block.
+    pub async fn complete(self) -> DataCacheResult<()> {
+        let cache = self.cache;
+        let cache_key = self.cache_key.clone();
+        let block_idx = self.block_idx;
+        let stored_block = self.complete_upload().await?;
+        cache.invalidate_block_listing(&cache_key);
+        if let (true, Some((object_key, checksum))) = (cache.config.object_manifest, stored_block) {
+            cache
+                .update_manifest(&cache_key, block_idx, object_key, checksum)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Complete the upload, returning the key and checksum of blocks written with a single PutObject request.
+    async fn complete_upload(self) -> DataCacheResult<Option<(String, Crc32c)>> {
+        match self.state {
+            BlockWriterState::Buffering(mut chunks) => {
+                let data = if chunks.len() == 1 {
+                    chunks.pop().unwrap()
+                } else {
+                    let mut buffer = BytesMut::with_capacity(self.len);
+                    for chunk in chunks {
+                        buffer.extend_from_slice(&chunk);
+                    }
+                    buffer.freeze()
+                };
+                let standard_key = block_key(
+                    self.cache.key_hasher(),
+                    &self.cache.prefix,
+                    &self.cache_key,
+                    self.block_idx,
+                );
+                let object_key = if self.cache.config.content_hash_in_key {
+                    block_key_with_content_hash(
+                        self.cache.key_hasher(),
+                        &self.cache.prefix,
+                        &self.cache_key,
+                        self.block_idx,
+                        &block_content_hash(&data),
+                    )
+                } else {
+                    standard_key.clone()
+                };
+                let compressed = match &self.cache.config.compression {
+                    Some(compression) => compression.compress_negotiated(&data)?,
+                    None => None,
+                };
+                let (data, checksum, compression_headers) = match compressed {
+                    Some((compressed, headers)) => {
+                        let checksum = crc32c::checksum(&compressed);
+                        (compressed, checksum, headers)
+                    }
+                    None => (data, self.checksum, HashMap::new()),
+                };
+                let block_metadata = BlockMetadata::new(
+                    self.block_idx,
+                    self.block_offset,
+                    &self.cache_key,
+                    &self.cache.source_bucket_name,
+                    checksum,
+                );
+                let mut params = block_metadata.to_put_object_params().object_lock_retention(
+                    self.cache
+                        .config
+                        .block_retention
+                        .map(|retention| retention.for_new_block(self.cache.clock.now())),
+                );
+                params.object_metadata.extend(compression_headers);
+                if let Some((name, value)) = self.cache.correlation_header() {
+                    params = params.add_custom_header(name, value);
+                }
+                if let Some((expires, expires_at)) = self.cache.block_expiry() {
+                    params = params.add_custom_header("Expires".to_string(), expires);
+                    params.object_metadata.insert(EXPIRES_AT_HEADER.to_string(), expires_at);
+                }
+                let data = if self.cache.config.checksum_location == ChecksumLocation::TrailingBytes {
+                    params = params.checksum(None);
+                    params
+                        .object_metadata
+                        .insert(CHECKSUM_LOCATION_HEADER.to_string(), TRAILING.to_string());
+                    let mut buffer = BytesMut::with_capacity(data.len() + TRAILING_CHECKSUM_LEN);
+                    buffer.extend_from_slice(&data);
+                    buffer.extend_from_slice(&checksum.value().to_be_bytes());
+                    buffer.freeze()
+                } else {
+                    if self.cache.config.checksum_algorithm == BlockChecksumAlgorithm::Crc64nvme {
+                        params = params.checksum(Some(UploadChecksum::Crc64nvme(crc64nvme::checksum(&data))));
+                    }
+                    data
+                };
+                if let Some(storage_class) = &self.tier.storage_class {
+                    params = params.storage_class(storage_class.clone());
+                }
+                if object_key != standard_key {
+                    // Also write the block under the standard key, for reads without an expected content hash.
+                    self.cache
+                        .put_object_in_bucket(&self.tier.bucket_name, params.clone(), &standard_key, data.clone())
+                        .await?;
+                    if self.cache.config.completion_markers {
+                        self.cache.write_completion_marker(&self.tier, &standard_key).await?;
+                    }
+                }
+                self.cache
+                    .put_object_in_bucket(&self.tier.bucket_name, params, &object_key, data)
+                    .await?;
+                if self.cache.config.completion_markers {
+                    self.cache.write_completion_marker(&self.tier, &object_key).await?;
+                }
+                Ok(Some((object_key, checksum)))
+            }
+            BlockWriterState::Multipart(request) => {
+                let object_key = block_key(
+                    self.cache.key_hasher(),
+                    &self.cache.prefix,
+                    &self.cache_key,
+                    self.block_idx,
+                );
+                request.write(&self.checksum.value().to_be_bytes()).await?;
+                let result = request.complete().in_current_span().await?;
+                self.cache.verify_put_response(&object_key, &result);
+                if self.cache.config.completion_markers {
+                    self.cache.write_completion_marker(&self.tier, &object_key).await?;
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Metadata about the cached object to ensure that the object we've retrieved is the one we were
+/// wanting to get (and avoid collisions with the key).
+/// On miss, bypass the cache and go to the main data source.
+#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct BlockMetadata {
+    cache_version: String,
+    block_idx: BlockIndex,
+    block_offset: u64,
+    etag: String,
+    source_key: String,
+    source_bucket_name: String,
+    data_checksum: u32,
+    header_checksum: u32,
+    /// Whether the block was written with a multipart upload, in which case its data checksum is only
+    /// stored in its trailing bytes.
+    multipart: bool,
+}
+
+impl BlockMetadata {
+    pub fn new(
+        block_idx: BlockIndex,
+        block_offset: u64,
+        cache_key: &ObjectId,
+        source_bucket_name: &str,
+        data_checksum: Crc32c,
+    ) -> Self {
+        Self::new_inner(
+            CACHE_VERSION,
+            block_idx,
+            block_offset,
+            cache_key,
+            source_bucket_name,
+            data_checksum,
+            false,
+        )
+    }
+
+    /// Metadata for a block written with a multipart upload, whose checksum is not known up front and is
+    /// appended to its data instead.
+    pub fn new_multipart(
+        block_idx: BlockIndex,
+        block_offset: u64,
+        cache_key: &ObjectId,
+        source_bucket_name: &str,
+    ) -> Self {
+        Self::new_inner(
+            CACHE_VERSION,
+            block_idx,
+            block_offset,
+            cache_key,
+            source_bucket_name,
+            Crc32c::new(0),
+            true,
+        )
+    }
+
+    fn new_inner(
+        cache_version: &str,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        cache_key: &ObjectId,
+        source_bucket_name: &str,
+        data_checksum: Crc32c,
+        multipart: bool,
+    ) -> Self {
+        let header_checksum = Self::get_header_checksum(
+            cache_version,
+            block_idx,
+            block_offset,
+            cache_key,
+            source_bucket_name,
+            data_checksum,
+            multipart,
+        )
+        .value();
+        Self {
+            cache_version: cache_version.to_string(),
+            block_idx,
+            block_offset,
+            etag: cache_key.etag().as_str().to_string(),
+            source_key: cache_key.key().to_string(),
+            source_bucket_name: source_bucket_name.to_string(),
+            data_checksum: data_checksum.value(),
+            header_checksum,
+            multipart,
+        }
+    }
+
+    /// Whether the object metadata headers describe a block written with a multipart upload.
+    pub fn is_multipart(headers: &HashMap<String, String>) -> bool {
+        headers.get("multipart").is_some_and(|multipart| multipart == "true")
+    }
+
+    /// Build parameters to be used when running a PutObject for this block
+    pub fn to_put_object_params(&self) -> PutObjectSingleParams {
+        PutObjectSingleParams::new()
+            .object_metadata(self.to_object_metadata())
+            .checksum(Some(UploadChecksum::Crc32c(Crc32c::new(self.data_checksum))))
+    }
+
+    /// Build parameters to be used when writing this block with a multipart upload
+    pub fn to_multipart_put_object_params(&self) -> PutObjectParams {
+        PutObjectParams::new()
+            .object_metadata(self.to_object_metadata())
+            .trailing_checksums(PutObjectTrailingChecksums::Enabled)
+    }
+
+    fn to_object_metadata(&self) -> HashMap<String, String> {
+        // Convert to object metadata that is HTTP header safe (ASCII only)
+        let source_key_encoded = Base64::encode_string(self.source_key.as_bytes());
+        let mut object_metadata = HashMap::from([
+            ("cache-version".to_string(), self.cache_version.clone()),
+            ("block-idx".to_string(), format!("{}", self.block_idx)),
+            ("block-offset".to_string(), format!("{}", self.block_offset)),
+            ("etag".to_string(), self.etag.clone()),
+            ("source-key".to_string(), source_key_encoded),
+            ("source-bucket-name".to_string(), self.source_bucket_name.clone()),
+            ("header-checksum".to_string(), format!("{}", self.header_checksum)),
+            (MP_VERSION_HEADER.to_string(), env!("CARGO_PKG_VERSION").to_string()),
+        ]);
+        if self.multipart {
+            object_metadata.insert("multipart".to_string(), "true".to_string());
+            object_metadata.insert(CHECKSUM_LOCATION_HEADER.to_string(), TRAILING.to_string());
+        }
+        object_metadata
+    }
+
+    /// Validate the object metadata headers received match this BlockMetadata object.
+    pub fn validate_object_metadata(&self, headers: &HashMap<String, String>) -> DataCacheResult<()> {
+        self.validate_header(headers, "cache-version", |version| version == self.cache_version)?;
+        self.validate_header(headers, "block-idx", |block_idx| {
+            block_idx.parse() == Ok(self.block_idx)
+        })?;
+        self.validate_header(headers, "block-offset", |block_offset| {
+            block_offset.parse() == Ok(self.block_offset)
+        })?;
+        self.validate_header(headers, "etag", |etag| etag == self.etag)?;
+        self.validate_header(headers, "source-key", |source_key| {
+            source_key == Base64::encode_string(self.source_key.as_bytes())
+        })?;
+        self.validate_header(headers, "source-bucket-name", |source_bucket_name| {
+            source_bucket_name == self.source_bucket_name
+        })?;
+        self.validate_header(headers, "header-checksum", |header_checksum| {
+            header_checksum.parse() == Ok(self.header_checksum)
+        })?;
+        if Self::is_multipart(headers) != self.multipart {
+            return Err(DataCacheError::InvalidBlockHeader("multipart".to_string()));
+        }
+
+        Ok(())
+    }
+
+    fn validate_header<F: Fn(&str) -> bool>(
+        &self,
+        headers: &HashMap<String, String>,
+        header: &str,
+        is_valid: F,
+    ) -> DataCacheResult<()> {
+        let value = headers
+            .get(header)
+            .ok_or(DataCacheError::InvalidBlockHeader(header.to_string()))?;
+        is_valid(value)
+            .then_some(())
+            .ok_or(DataCacheError::InvalidBlockHeader(header.to_string()))
+    }
+
+    fn get_header_checksum(
+        cache_version: &str,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        cache_key: &ObjectId,
+        source_bucket_name: &str,
+        data_checksum: Crc32c,
+        multipart: bool,
+    ) -> Crc32c {
+        let mut hasher = crc32c::Hasher::new();
+        hasher.update(cache_version.as_bytes());
+        hasher.update(&block_idx.to_be_bytes());
+        hasher.update(&block_offset.to_be_bytes());
+        hasher.update(cache_key.etag().as_str().as_bytes());
+        hasher.update(cache_key.key().as_bytes());
+        hasher.update(source_bucket_name.as_bytes());
+        hasher.update(&data_checksum.value().to_be_bytes());
+        if multipart {
+            hasher.update(b"multipart");
+        }
+        hasher.finalize()
+    }
+}
+
+/// Check that blocks of the configured size fit in a single S3 object.
+fn check_block_size(config: &ExpressDataCacheConfig) -> DataCacheResult<()> {
+    if config.block_size > MAX_OBJECT_SIZE {
+        return Err(DataCacheError::InvalidCacheConfig(format!(
+            "block size {} is above the maximum S3 object size of {MAX_OBJECT_SIZE} bytes",
+            config.block_size
+        )));
+    }
+    Ok(())
+}
+
+/// Check that compression is only configured when the codecs of the `compression` feature are built.
+fn check_compression(config: &ExpressDataCacheConfig) -> DataCacheResult<()> {
+    if (config.compression.is_some() || config.detect_compression_magic) && !COMPRESSION_ENABLED {
+        return Err(DataCacheError::InvalidCacheConfig(
+            "block compression requires the compression feature".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Compare the regions of the cache and source clients, warning if they differ and failing if the
+/// [ExpressDataCacheConfig::region_mismatch_policy] rejects it.
+fn check_regions(
+    config: &ExpressDataCacheConfig,
+    cache_region: Option<&str>,
+    source_region: Option<&str>,
+) -> DataCacheResult<()> {
+    let (Some(cache_region), Some(source_region)) = (cache_region, source_region) else {
+        return Ok(());
+    };
+    if cache_region == source_region {
+        return Ok(());
+    }
+    tracing::warn!(
+        cache_region,
+        source_region,
+        "cache bucket is in another region than the source bucket, requests for blocks will cross regions"
+    );
+    metrics::counter!("express_data_cache.region_mismatch").increment(1);
+    match config.region_mismatch_policy {
+        RegionMismatchPolicy::Warn => Ok(()),
+        RegionMismatchPolicy::Error => Err(DataCacheError::InvalidCacheConfig(format!(
+            "cache bucket region {cache_region} does not match source bucket region {source_region}"
+        ))),
+    }
+}
+
+/// Check that the name of the cache bucket is a directory bucket name, warning if not and failing if the
+/// [ExpressDataCacheConfig::bucket_name_policy] rejects it.
+fn check_bucket_name(config: &ExpressDataCacheConfig, bucket_name: &str) -> DataCacheResult<()> {
+    if config.bucket_name_policy == BucketNamePolicy::Ignore || is_directory_bucket_name(bucket_name) {
+        return Ok(());
+    }
+    tracing::warn!(
+        bucket_name,
+        "cache bucket name is not an S3 Express One Zone directory bucket name"
+    );
+    metrics::counter!("express_data_cache.invalid_bucket_name").increment(1);
+    match config.bucket_name_policy {
+        BucketNamePolicy::Ignore | BucketNamePolicy::Warn => Ok(()),
+        BucketNamePolicy::Error => Err(DataCacheError::InvalidCacheConfig(format!(
+            "cache bucket name {bucket_name} does not match the directory bucket name pattern base-name--zone-id--x-s3"
+        ))),
+    }
+}
+
+/// Whether `bucket_name` follows the naming of directory buckets, `base-name--zone-id--x-s3`, where the zone
+/// id is an Availability Zone or Local Zone id such as `usw2-az1` or `usw2-lax1-az1`.
+fn is_directory_bucket_name(bucket_name: &str) -> bool {
+    let Some((base_name, zone_id)) = bucket_name
+        .strip_suffix("--x-s3")
+        .and_then(|name| name.rsplit_once("--"))
+    else {
+        return false;
+    };
+    let Some((location, zone)) = zone_id.rsplit_once('-') else {
+        return false;
+    };
+    let is_name_char = |b: u8| b.is_ascii_lowercase() || b.is_ascii_digit();
+    let is_name_part = |part: &str| !part.is_empty() && part.bytes().all(is_name_char);
+    let valid_base_name = base_name.as_bytes().first().is_some_and(|&b| is_name_char(b))
+        && base_name.as_bytes().last().is_some_and(|&b| is_name_char(b))
+        && base_name.bytes().all(|b| is_name_char(b) || b == b'-');
+    let valid_zone = zone
+        .strip_prefix("az")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+    valid_base_name && location.split('-').all(is_name_part) && valid_zone
+}
+
+/// Whether a GetObject error indicates that the bucket is unavailable, rather than a problem with the block.
+fn is_unavailable<C>(err: &ObjectClientError<GetObjectError, C>) -> bool {
+    matches!(
+        err,
+        ObjectClientError::ClientError(_) | ObjectClientError::ServiceError(GetObjectError::NoSuchBucket)
+    )
+}
+
+/// Interpret the outcome of the request sent by [ExpressDataCache::check_credentials].
+fn credentials_check<C: ProvideErrorMetadata + std::error::Error>(
+    result: ObjectClientResult<(), ListObjectsError, C>,
+) -> CredentialsCheck {
+    match result {
+        // S3 authenticated the request, whatever the outcome for the bucket.
+        Ok(()) | Err(ObjectClientError::ServiceError(_)) => CredentialsCheck::Valid,
+        Err(ObjectClientError::ClientError(err)) => match err.meta().error_code {
+            Some(code) if CREDENTIALS_ERROR_CODES.contains(&code.as_str()) => {
+                CredentialsCheck::InvalidCredentials(code)
+            }
+            Some(code) if code == "AccessDenied" => CredentialsCheck::Valid,
+            _ => CredentialsCheck::Failed(err.to_string()),
+        },
+    }
+}
+
+/// Whether the expiry recorded in the metadata of a block has passed, see [ExpressDataCacheConfig::block_expiry].
+fn is_expired(object_metadata: &HashMap<String, String>, now: OffsetDateTime) -> bool {
+    object_metadata
+        .get(EXPIRES_AT_HEADER)
+        .and_then(|expires_at| expires_at.parse::<i64>().ok())
+        .is_some_and(|expires_at| now.unix_timestamp() >= expires_at)
+}
+
+/// Whether the ETag is a plain MD5 digest, i.e. 32 hex characters, as for objects uploaded with a single request.
+fn is_md5_etag(etag: &ETag) -> bool {
+    let etag = etag.as_str().trim_matches('"');
+    etag.len() == 32 && etag.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Read a block from a [LocalMirror] file, returning [None] if it is missing or invalid.
+fn read_local_mirror(path: &Path) -> Option<ChecksummedBytes> {
+    let contents = match fs::read(path) {
+        Ok(contents) => Bytes::from(contents),
+        Err(err) if err.kind() == ErrorKind::NotFound => return None,
+        Err(error) => {
+            tracing::warn!(?path, ?error, "failed to read block from local mirror");
+            return None;
+        }
+    };
+    if contents.len() < 4 {
+        tracing::warn!(?path, "truncated block in local mirror");
+        return None;
+    }
+    let checksum = Crc32c::new(u32::from_be_bytes(contents[..4].try_into().unwrap()));
+    let block = ChecksummedBytes::new_from_inner_data(contents.slice(4..), checksum);
+    if block.validate().is_err() {
+        tracing::warn!(?path, "corrupted block in local mirror");
+        metrics::counter!("express_data_cache.local_mirror_corrupted").increment(1);
+        return None;
+    }
+    Some(block)
+}
+
+/// Write a block to a [LocalMirror] file, replacing the file atomically.
+///
+/// The block is first written to a temporary file with a random name next to it, so concurrent writes of
+/// the same block do not interfere.
+fn write_local_mirror(path: &Path, bytes: ChecksummedBytes) -> DataCacheResult<()> {
+    let (data, checksum) = bytes.into_inner().map_err(|_| DataCacheError::InvalidBlockContent)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+    temp_name.push(format!(".{:016x}.tmp", rand::random::<u64>()));
+    let temp_path = path.with_file_name(temp_name);
+    let result = fs::File::create(&temp_path).and_then(|mut file| {
+        file.write_all(&checksum.value().to_be_bytes())?;
+        file.write_all(&data)?;
+        fs::rename(&temp_path, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    Ok(result?)
+}
+
+/// Get the prefix for objects we'll be creating in S3
+///
+/// This is the prefix for [KeyFormat::Standard], which the other public key functions also use.
+pub fn build_prefix(source_bucket_name: &str, block_size: u64) -> String {
+    build_prefix_for_version(
+        CACHE_VERSION,
+        KeyFormat::Standard,
+        KeyEncoding::LowerHex,
+        KeyHashAlgorithm::Sha256,
+        None,
+        None,
+        source_bucket_name,
+        block_size,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_prefix_for_version(
+    cache_version: &str,
+    key_format: KeyFormat,
+    encoding: KeyEncoding,
+    algorithm: KeyHashAlgorithm,
+    tenant_id: Option<&str>,
+    region: Option<&str>,
+    source_bucket_name: &str,
+    block_size: u64,
+) -> String {
+    let block_size = block_size.to_be_bytes();
+    let mut parts = vec![cache_version.as_bytes(), &block_size, source_bucket_name.as_bytes()];
+    let tenant_id_len = tenant_id.map(|tenant_id| (tenant_id.len() as u64).to_be_bytes());
+    if let Some((tenant_id, tenant_id_len)) = tenant_id.zip(tenant_id_len.as_ref()) {
+        parts.extend([b"tenant".as_slice(), tenant_id_len, tenant_id.as_bytes()]);
+    }
+    let region_len = region.map(|region| (region.len() as u64).to_be_bytes());
+    if let Some((region, region_len)) = region.zip(region_len.as_ref()) {
+        parts.extend([b"region".as_slice(), region_len, region.as_bytes()]);
+    }
+    if key_format == KeyFormat::Short {
+        parts.push(b"short-keys");
+    }
+    key_format.encode_hash(encoding, &algorithm.digest(parts))
+}
+
+/// Get the S3 key prefix shared by the blocks of every version (ETag) of the object `key`.
+pub fn get_s3_key_prefix_for_object(prefix: &str, key: &str) -> String {
+    key_prefix_for_object(KeyHasher::STANDARD, prefix, key)
+}
+
+fn key_prefix_for_object(hasher: KeyHasher, prefix: &str, key: &str) -> String {
+    format!("{}/{}/", prefix, hasher.hash([key.as_bytes()]))
+}
+
+/// Get the S3 key prefix shared by the blocks of the object version `cache_key`.
+fn key_prefix_for_version(hasher: KeyHasher, prefix: &str, cache_key: &ObjectId) -> String {
+    let hashed_cache_key = hasher.hash([cache_key.key().as_bytes(), cache_key.etag().as_str().as_bytes()]);
+    format!(
+        "{}{}/",
+        key_prefix_for_object(hasher, prefix, cache_key.key()),
+        hashed_cache_key
+    )
+}
+
+/// Get the S3 key of the manifest of the object version `cache_key`.
+fn manifest_key(hasher: KeyHasher, prefix: &str, cache_key: &ObjectId) -> String {
+    format!("{}manifest", key_prefix_for_version(hasher, prefix, cache_key))
+}
+
+/// Get the S3 key of the user metadata of the object version `cache_key`.
+fn object_metadata_key(hasher: KeyHasher, prefix: &str, cache_key: &ObjectId) -> String {
+    format!("{}metadata", key_prefix_for_version(hasher, prefix, cache_key))
+}
+
+/// Get the S3 key of the marker of the hole `block_idx` of the object version `cache_key`.
+fn hole_key(hasher: KeyHasher, prefix: &str, cache_key: &ObjectId, block_idx: BlockIndex) -> String {
+    format!(
+        "{}holes/{:010}",
+        key_prefix_for_version(hasher, prefix, cache_key),
+        block_idx
+    )
+}
+
+/// Get the S3 key of the pack index of the object version `cache_key`, see [ExpressDataCache::compact_object].
+fn pack_index_key(hasher: KeyHasher, prefix: &str, cache_key: &ObjectId) -> String {
+    format!("{}packs", key_prefix_for_version(hasher, prefix, cache_key))
+}
+
+/// Get the S3 key of the pack starting with the block `first_block_idx` of the object version `cache_key`.
+fn pack_key(hasher: KeyHasher, prefix: &str, cache_key: &ObjectId, first_block_idx: BlockIndex) -> String {
+    format!(
+        "{}pack-{:010}",
+        key_prefix_for_version(hasher, prefix, cache_key),
+        first_block_idx
+    )
+}
+
+/// Get the S3 key of the reference to the content of a block, see [ExpressDataCacheConfig::deduplicate_blocks].
+fn block_reference_key(hasher: KeyHasher, prefix: &str, cache_key: &ObjectId, block_idx: BlockIndex) -> String {
+    format!(
+        "{}ref-{:010}",
+        key_prefix_for_version(hasher, prefix, cache_key),
+        block_idx
+    )
+}
+
+/// Get the S3 key of the block content `data`, shared by all objects, see [ExpressDataCacheConfig::deduplicate_blocks].
+fn content_key(hasher: KeyHasher, prefix: &str, data: &[u8]) -> String {
+    format!("{prefix}/content/{}", hasher.hash([data]))
+}
+
+/// Get the S3 key this block should be written to or read from.
+pub fn get_s3_key(prefix: &str, cache_key: &ObjectId, block_idx: BlockIndex) -> String {
+    block_key(KeyHasher::STANDARD, prefix, cache_key, block_idx)
+}
+
+fn block_key(hasher: KeyHasher, prefix: &str, cache_key: &ObjectId, block_idx: BlockIndex) -> String {
+    format!("{}{:010}", key_prefix_for_version(hasher, prefix, cache_key), block_idx)
+}
+
+/// Get the S3 key for a block written with its content hash appended, see [block_content_hash].
+pub fn get_s3_key_with_content_hash(
+    prefix: &str,
+    cache_key: &ObjectId,
+    block_idx: BlockIndex,
+    content_hash: &str,
+) -> String {
+    block_key_with_content_hash(KeyHasher::STANDARD, prefix, cache_key, block_idx, content_hash)
+}
+
+fn completion_marker_key(object_key: &str) -> String {
+    format!("{object_key}.{COMPLETION_MARKER_EXTENSION}")
+}
+
+fn block_key_with_content_hash(
+    hasher: KeyHasher,
+    prefix: &str,
+    cache_key: &ObjectId,
+    block_idx: BlockIndex,
+    content_hash: &str,
+) -> String {
+    format!("{}.{}", block_key(hasher, prefix, cache_key, block_idx), content_hash)
+}
+
+/// Compute the truncated SHA-256 hash of block content used in object keys.
+pub fn block_content_hash(data: &[u8]) -> String {
+    hex::encode(&Sha256::digest(data)[..CONTENT_HASH_LEN])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksums::ChecksummedBytes;
+    use crate::data_cache::cancellation::{run_with_cancellation, YieldBeforeRequest};
+    use crate::data_cache::conformance::run_data_cache_conformance;
+    use crate::metrics::with_captured_metrics;
+    use crate::sync::Arc;
+    use proptest::{prop_assert, proptest};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::str::FromStr;
+
+    use crate::data_cache::hook_client::{HookClient, HookedRequest, RequestHook};
+    use futures::executor::ThreadPool;
+    use mountpoint_s3_client::checksums::{crc32c_to_base64, crc64nvme_to_base64};
+    use mountpoint_s3_client::error_metadata::ClientErrorMetadata;
+    use mountpoint_s3_client::failure_client::{countdown_failure_client, CountdownFailureConfig};
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockClientError, MockObject, Operation};
+    use mountpoint_s3_client::types::{ETag, ObjectLockMode, ObjectLockRetention};
+    use test_case::test_case;
+
+    fn new_mock_client(bucket: &str) -> Arc<MockClient> {
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * 1024 * 1024,
+            enable_backpressure: true,
+            initial_read_window_size: 8 * 1024 * 1024,
+            ..Default::default()
+        };
+        Arc::new(MockClient::new(config))
+    }
+
+    /// [Clock] whose time only changes when it is advanced, for tests of time-based behavior.
+    ///
+    /// Clones share the same time, so a clone kept by a test can advance the clock of a cache.
+    #[derive(Debug, Clone)]
+    struct MockClock {
+        now: Arc<Mutex<OffsetDateTime>>,
+    }
+
+    impl MockClock {
+        /// Create a clock starting at `now`.
+        fn new(now: OffsetDateTime) -> Self {
+            Self {
+                now: Arc::new(Mutex::new(now)),
+            }
+        }
+
+        /// Move the time of the clock forward by `duration`.
+        fn advance(&self, duration: Duration) {
+            *self.now.lock().unwrap() += duration;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> OffsetDateTime {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    fn new_mock_client_in_region(bucket: &str, region: &str) -> Arc<MockClient> {
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * 1024 * 1024,
+            region: Some(region.to_owned()),
+            ..Default::default()
+        };
+        Arc::new(MockClient::new(config))
+    }
+
+    #[test_case(1024, 512 * 1024; "block_size smaller than part_size")]
+    #[test_case(8 * 1024 * 1024, 512 * 1024; "block_size larger than part_size")]
+    #[tokio::test]
+    async fn test_put_get(part_size: usize, block_size: u64) {
+        let bucket = "test-bucket";
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size,
+            enable_backpressure: true,
+            initial_read_window_size: part_size,
+            ..Default::default()
+        };
+        let client = Arc::new(MockClient::new(config));
+
+        let config = ExpressDataCacheConfig {
+            block_size,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data_1 = ChecksummedBytes::new("Foo".into());
+        let data_2 = ChecksummedBytes::new("Bar".into());
+        let data_3 = ChecksummedBytes::new("a".repeat(block_size as usize).into());
+
+        let object_1_size = data_1.len() + data_3.len();
+        let object_2_size = data_2.len();
+
+        let cache_key_1 = ObjectId::new("a".into(), ETag::for_tests());
+        let cache_key_2 = ObjectId::new(
+            "longkey_".repeat(128), // 1024 bytes, max length for S3 keys
+            ETag::for_tests(),
+        );
+
+        let block = cache
+            .get_block(&cache_key_1, 0, 0, object_1_size)
+            .await
+            .expect("cache should be accessible");
+        assert!(
+            block.is_none(),
+            "no entry should be available to return but got {:?}",
+            block,
+        );
+
+        // PUT and GET, OK?
+        cache
+            .put_block(cache_key_1.clone(), 0, 0, data_1.clone(), object_1_size)
+            .await
+            .expect("cache should be accessible");
+        let entry = cache
+            .get_block(&cache_key_1, 0, 0, object_1_size)
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert!(entry.validate().is_ok(), "CRC32C should match");
+        assert_eq!(
+            data_1, entry,
+            "cache entry returned should match original bytes after put"
+        );
+
+        // PUT AND GET block for a second key, OK?
+        cache
+            .put_block(cache_key_2.clone(), 0, 0, data_2.clone(), object_2_size)
+            .await
+            .expect("cache should be accessible");
+        let entry = cache
+            .get_block(&cache_key_2, 0, 0, object_2_size)
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert!(entry.validate().is_ok(), "CRC32C should match");
+        assert_eq!(
+            data_2, entry,
+            "cache entry returned should match original bytes after put"
+        );
+
+        // PUT AND GET a second block in a cache entry, OK?
+        cache
+            .put_block(cache_key_1.clone(), 1, block_size, data_3.clone(), object_1_size)
+            .await
+            .expect("cache should be accessible");
+        let entry = cache
+            .get_block(&cache_key_1, 1, block_size, object_1_size)
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert!(entry.validate().is_ok(), "CRC32C should match");
+        assert_eq!(
+            data_3, entry,
+            "cache entry returned should match original bytes after put"
+        );
+
+        // Entry 1's first block still intact
+        let entry = cache
+            .get_block(&cache_key_1, 0, 0, object_1_size)
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert!(entry.validate().is_ok(), "CRC32C should match");
+        assert_eq!(
+            data_1, entry,
+            "cache entry returned should match original bytes after put"
+        );
+    }
+
+    #[tokio::test]
+    async fn large_object_bypassed() {
+        let bucket = "test-bucket";
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * 1024 * 1024,
+            enable_backpressure: true,
+            initial_read_window_size: 8 * 1024 * 1024,
+            ..Default::default()
+        };
+        let client = Arc::new(MockClient::new(config));
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        let data_1 = vec![0u8; 1024 * 1024 + 1];
+        let data_1 = ChecksummedBytes::new(data_1.into());
+        let cache_key_1 = ObjectId::new("a".into(), ETag::for_tests());
+        // PUT and GET for a large object should be no-op
+        cache
+            .put_block(cache_key_1.clone(), 0, 0, data_1.clone(), data_1.len())
+            .await
+            .expect("cache should be accessible");
+        let get_result = cache
+            .get_block(&cache_key_1, 0, 0, data_1.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(get_result.is_none());
+        assert_eq!(client.object_count(), 0, "cache must be empty");
+    }
+
+    #[tokio::test]
+    async fn test_get_validate_failure() {
+        let source_bucket = "source-bucket";
+        let bucket = "test-bucket";
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * 1024 * 1024,
+            enable_backpressure: true,
+            initial_read_window_size: 8 * 1024 * 1024,
+            ..Default::default()
+        };
+        let client = Arc::new(MockClient::new(config));
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let data_2 = ChecksummedBytes::new("Bar".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let cache_key_non_existent = ObjectId::new("does-not-exist".into(), ETag::for_tests());
+
+        // Setup cache
+        let object_key = get_s3_key(
+            &build_prefix(source_bucket, ExpressDataCacheConfig::default().block_size),
+            &cache_key,
+            0,
+        );
+
+        let (data, checksum) = data.into_inner().unwrap();
+        let block_metadata = BlockMetadata::new(0, 0, &cache_key, source_bucket, checksum);
+        let put_params = block_metadata.to_put_object_params();
+
+        let (data_2, checksum_2) = data_2.into_inner().unwrap();
+        let block_metadata_2 = BlockMetadata::new(0, 0, &cache_key, source_bucket, checksum_2);
+        let put_params_2 = block_metadata_2.to_put_object_params();
+
+        // Store with correct metadata and expect a successful get_block
+        client
+            .put_object_single(bucket, &object_key, &put_params, data.clone())
+            .in_current_span()
+            .await
+            .unwrap();
+        let (received_data, _) = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed with intact metadata")
+            .expect("block should be non-empty")
+            .into_inner()
+            .expect("block should be valid");
+        assert_eq!(received_data, data);
+
+        // Remove the checksum when writing.
+        client
+            .put_object_single(bucket, &object_key, &put_params.clone().checksum(None), data.clone())
+            .in_current_span()
+            .await
+            .unwrap();
+        let err = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect_err("cache should return error if checksum isn't present");
+        assert!(matches!(err, DataCacheError::InvalidBlockChecksum));
+
+        // Remove the object metadata when writing.
+        client
+            .put_object_single(
+                bucket,
+                &object_key,
+                &put_params.clone().object_metadata(HashMap::new()),
+                data.clone(),
+            )
+            .in_current_span()
+            .await
+            .unwrap();
+        let err = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect_err("cache should return error if object metadata isn't present");
+        assert!(matches!(err, DataCacheError::InvalidBlockHeader(_)));
+
+        // Emulate corrupt data by writing 'incorrect' data.
+        client
+            .put_object_single(bucket, &object_key, &put_params.clone(), data_2.clone())
+            .in_current_span()
+            .await
+            .expect_err("should fail to write as checksum incorrect");
+
+        // Write data with object metadata header for different object
+        client
+            .put_object_single(
+                bucket,
+                &object_key,
+                &put_params.clone().checksum(put_params_2.checksum.clone()),
+                data_2.clone(),
+            )
+            .in_current_span()
+            .await
+            .unwrap();
+        let err = cache
+            .get_block(&cache_key, 0, 0, data_2.len())
+            .await
+            .expect_err("cache should return error if object metadata doesn't match data");
+        assert!(matches!(err, DataCacheError::InvalidBlockHeader(_)));
+
+        // Write data with object metadata header for object from a different bucket
+        let mut corrupted_metadata = block_metadata.clone();
+        corrupted_metadata.source_bucket_name = bucket.to_owned();
+        client
+            .put_object_single(
+                bucket,
+                &object_key,
+                &corrupted_metadata.to_put_object_params(),
+                data.clone(),
+            )
+            .in_current_span()
+            .await
+            .unwrap();
+        let err = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect_err("cache should return error if source bucket does not match");
+        assert!(matches!(err, DataCacheError::InvalidBlockHeader(_)));
+
+        // Get data that's not been written yet
+        let result = cache
+            .get_block(&cache_key_non_existent, 0, 0, data.len())
+            .await
+            .expect("cache should return None if data is not present");
+        assert_eq!(result, None);
+    }
+
+    #[test_case(true; "with content hash in key")]
+    #[test_case(false; "without content hash in key")]
+    #[tokio::test]
+    async fn test_put_get_content_hash(content_hash_in_key: bool) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let put_single_counter = client.new_counter(Operation::PutObjectSingle);
+        let config = ExpressDataCacheConfig {
+            content_hash_in_key,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let content_hash = block_content_hash(b"Foo");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("cache should be accessible");
+        let expected_puts = if content_hash_in_key { 2 } else { 1 };
+        assert_eq!(put_single_counter.count(), expected_puts);
+
+        // Reads with the expected hash only find blocks written with the hash in the key.
+        let entry = cache
+            .get_block_with_content_hash(&cache_key, 0, 0, data.len(), Some(&content_hash))
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(entry.is_some(), content_hash_in_key);
+
+        // Reads without an expected hash use the standard key, which is always written.
+        let entry = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert_eq!(
+            data, entry,
+            "cache entry returned should match original bytes after put"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_content_hash_mismatch() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            content_hash_in_key: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("cache should be accessible");
+
+        let other_content_hash = block_content_hash(b"Bar");
+        let entry = cache
+            .get_block_with_content_hash(&cache_key, 0, 0, data.len(), Some(&other_content_hash))
+            .await
+            .expect("cache should be accessible");
+        assert!(entry.is_none(), "block with different content should be a miss");
+    }
+
+    #[test]
+    fn test_supports_ranged_reads() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        assert!(!cache.supports_ranged_reads());
+    }
+
+    #[test_case(999, false; "below threshold")]
+    #[test_case(1000, false; "at threshold")]
+    #[test_case(1001, true; "above threshold")]
+    #[tokio::test]
+    async fn test_mpu_threshold(len: usize, expect_multipart: bool) {
+        let bucket = "test-bucket";
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 256,
+            enable_backpressure: true,
+            initial_read_window_size: 2048,
+            ..Default::default()
+        };
+        let client = Arc::new(MockClient::new(config));
+        let put_single_counter = client.new_counter(Operation::PutObjectSingle);
+        let put_mpu_counter = client.new_counter(Operation::PutObject);
+        let config = ExpressDataCacheConfig {
+            block_size: 2048,
+            mpu_threshold_bytes: Some(1000),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new(vec![7u8; len].into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(put_single_counter.count(), if expect_multipart { 0 } else { 1 });
+        assert_eq!(put_mpu_counter.count(), if expect_multipart { 1 } else { 0 });
+
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+    }
+
+    #[tokio::test]
+    async fn test_mpu_block_checksum() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 256,
+            enable_backpressure: true,
+            initial_read_window_size: 2048,
+            ..Default::default()
+        }));
+        let put_mpu_counter = client.new_counter(Operation::PutObject);
+        let config = ExpressDataCacheConfig {
+            block_size: 2048,
+            mpu_threshold_bytes: Some(1000),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let data: Vec<u8> = (0..1500).map(|i| (i % 251) as u8).collect();
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(
+                cache_key.clone(),
+                0,
+                0,
+                ChecksummedBytes::new(data.clone().into()),
+                data.len(),
+            )
+            .await
+            .expect("put should succeed");
+        assert_eq!(
+            put_mpu_counter.count(),
+            1,
+            "block should be written with a multipart upload"
+        );
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible")
+            .expect("block should be found");
+        assert_eq!(block.into_bytes().unwrap(), Bytes::from(data.clone()));
+        let mut streamed = Vec::new();
+        let stream = cache.get_block_stream(&cache_key, 0);
+        pin_mut!(stream);
+        while let Some(chunk) = stream.next().await {
+            streamed.extend_from_slice(&chunk.expect("block should be valid"));
+        }
+        assert_eq!(streamed, data);
+
+        // Corrupt the data of the block, keeping its metadata and trailing checksum.
+        let object_metadata = BlockMetadata::new_multipart(0, 0, &cache_key, source_bucket).to_object_metadata();
+        let mut corrupted = data.clone();
+        corrupted[0] ^= 1;
+        corrupted.extend_from_slice(&crc32c::checksum(&data).value().to_be_bytes());
+        let mut tampered = MockObject::from_bytes(&corrupted, ETag::for_tests());
+        tampered.set_object_metadata(object_metadata);
+        client.add_object(&get_s3_key(&cache.prefix, &cache_key, 0), tampered);
+
+        let err = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect_err("corrupted block should fail validation");
+        assert!(matches!(err, DataCacheError::InvalidBlockChecksum), "{err:?}");
+        let mut items: Vec<_> = cache.get_block_stream(&cache_key, 0).collect().await;
+        assert!(
+            matches!(items.pop(), Some(Err(DataCacheError::InvalidBlockChecksum))),
+            "stream should end with the validation error"
+        );
+    }
+
+    #[test_case(4096, false; "part size above block")]
+    #[test_case(1024, true; "part size below block")]
+    #[tokio::test]
+    async fn test_mpu_threshold_from_client_part_size(part_size: usize, expect_multipart: bool) {
+        let bucket = "test-bucket";
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size,
+            enable_backpressure: true,
+            initial_read_window_size: 4096,
+            ..Default::default()
+        };
+        let client = Arc::new(MockClient::new(config));
+        let put_single_counter = client.new_counter(Operation::PutObjectSingle);
+        let put_mpu_counter = client.new_counter(Operation::PutObject);
+        let config = ExpressDataCacheConfig {
+            block_size: 2048,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        assert_eq!(cache.single_put_threshold(), part_size);
+
+        let data = ChecksummedBytes::new(vec![7u8; 2048].into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(put_single_counter.count(), if expect_multipart { 0 } else { 1 });
+        assert_eq!(put_mpu_counter.count(), if expect_multipart { 1 } else { 0 });
+
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+    }
+
+    #[test_case(1000; "under limit")]
+    #[test_case(1001; "over limit")]
+    #[tokio::test]
+    async fn test_single_part_only(len: usize) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let put_single_counter = client.new_counter(Operation::PutObjectSingle);
+        let put_mpu_counter = client.new_counter(Operation::PutObject);
+        let config = ExpressDataCacheConfig {
+            block_size: 2048,
+            mpu_threshold_bytes: Some(1000),
+            put_mode: PutMode::SinglePartOnly,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new(vec![7u8; len].into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let result = cache.put_block(cache_key.clone(), 0, 0, data.clone(), data.len()).await;
+        assert_eq!(put_mpu_counter.count(), 0, "multipart uploads should never be used");
+        if len > 1000 {
+            let err = result.expect_err("put over the limit should fail");
+            assert!(matches!(err, DataCacheError::BlockTooLarge(1000)), "{err:?}");
+            assert_eq!(put_single_counter.count(), 0);
+            assert_eq!(client.object_count(), 0);
+        } else {
+            result.expect("put under the limit should succeed");
+            assert_eq!(put_single_counter.count(), 1);
+            let block = cache
+                .get_block(&cache_key, 0, 0, data.len())
+                .await
+                .expect("cache should be accessible");
+            assert_eq!(block, Some(data));
+        }
+    }
+
+    #[test_case(3, 200, false; "stream under part size")]
+    #[test_case(3, 500, true; "stream over part size")]
+    #[tokio::test]
+    async fn test_block_writer(chunk_count: usize, chunk_size: usize, expect_multipart: bool) {
+        let bucket = "test-bucket";
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 1024,
+            enable_backpressure: true,
+            initial_read_window_size: 1024,
+            ..Default::default()
+        };
+        let client = Arc::new(MockClient::new(config));
+        let put_single_counter = client.new_counter(Operation::PutObjectSingle);
+        let put_mpu_counter = client.new_counter(Operation::PutObject);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let mut expected = Vec::new();
+        let mut writer = cache
+            .block_writer(cache_key.clone(), 0, 0)
+            .expect("block offset should be valid");
+        for i in 0..chunk_count {
+            let chunk = vec![i as u8; chunk_size];
+            expected.extend_from_slice(&chunk);
+            writer
+                .write(ChecksummedBytes::new(chunk.into()))
+                .await
+                .expect("write should succeed");
+        }
+        writer.complete().await.expect("upload should succeed");
+
+        assert_eq!(put_single_counter.count(), if expect_multipart { 0 } else { 1 });
+        assert_eq!(put_mpu_counter.count(), if expect_multipart { 1 } else { 0 });
+
+        let entry = cache
+            .get_block(&cache_key, 0, 0, expected.len())
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert!(entry.validate().is_ok(), "CRC32C should match");
+        assert_eq!(entry.into_bytes().unwrap(), Bytes::from(expected));
+    }
+
+    #[test]
+    fn test_expected_offset() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let block_size = ExpressDataCacheConfig::default().block_size;
+        let cache = ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let max_block_idx = u64::MAX / block_size;
+        for block_idx in [0, 1, 7, max_block_idx, max_block_idx + 1, u64::MAX] {
+            let expected_offset = cache.expected_offset(block_idx);
+            assert_eq!(expected_offset, block_idx.checked_mul(block_size));
+
+            // The same check is applied to requests, without making any for invalid offsets.
+            let result = cache.block_writer(ObjectId::new("a".into(), ETag::for_tests()), block_idx, 0);
+            assert_eq!(result.is_ok(), expected_offset == Some(0));
+            if let Some(expected_offset) = expected_offset {
+                cache
+                    .block_writer(ObjectId::new("a".into(), ETag::for_tests()), block_idx, expected_offset)
+                    .expect("block offset should be valid");
+            }
+        }
+        assert_eq!(cache.expected_offset(max_block_idx + 1), None);
+    }
+
+    #[test_case(1024; "full block")]
+    #[test_case(100; "partial block")]
+    #[tokio::test]
+    async fn test_put_content_length(len: usize) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        // The mock client rejects uploads whose content length differs from the data.
+        let data = ChecksummedBytes::new(vec![7u8; len].into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("cache should be accessible");
+
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let head = client
+            .head_object(bucket, &object_key, &Default::default())
+            .await
+            .expect("block should be stored");
+        assert_eq!(head.size, len as u64);
+    }
+
+    #[tokio::test]
+    async fn test_mp_version_metadata() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("cache should be accessible");
+
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let result = client
+            .get_object(bucket, &object_key, &Default::default())
+            .await
+            .expect("block should be stored");
+        let object_metadata = result.get_object_metadata();
+        assert_eq!(
+            object_metadata.get(MP_VERSION_HEADER).map(String::as_str),
+            Some(env!("CARGO_PKG_VERSION"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_mirror() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let get_counter = client.new_counter(Operation::GetObject);
+        let mirror_dir = tempfile::tempdir().unwrap();
+        let config = ExpressDataCacheConfig {
+            local_mirror: Some(LocalMirror {
+                directory: mirror_dir.path().to_owned(),
+                mirror_writes: true,
+            }),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        assert!(
+            client.contains_key(&object_key),
+            "block should be written to the cache bucket"
+        );
+        let mirror_path = mirror_dir.path().join(&object_key);
+        let contents = fs::read(&mirror_path).expect("block should be mirrored");
+        assert_eq!(&contents[4..], b"Foo");
+
+        // Served from the local mirror, even once removed from the cache bucket.
+        client.remove_object(&object_key);
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()));
+        assert_eq!(get_counter.count(), 0);
+
+        // A corrupted file is ignored.
+        fs::write(&mirror_path, b"\0\0\0\0Bar").unwrap();
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none());
+        assert_eq!(get_counter.count(), 1);
+
+        // Removed from the local mirror with the cache bucket.
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert!(mirror_path.exists(), "block should be mirrored");
+        let entries = fs::read_dir(mirror_path.parent().unwrap()).unwrap().count();
+        assert_eq!(entries, 1, "no temporary file should be left");
+        cache.invalidate_object("a").await.expect("invalidation should succeed");
+        assert!(!mirror_path.exists(), "block should be removed from the local mirror");
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_local_mirror_fallback() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let get_counter = client.new_counter(Operation::GetObject);
+        let mirror_dir = tempfile::tempdir().unwrap();
+        let config = ExpressDataCacheConfig {
+            local_mirror: Some(LocalMirror {
+                directory: mirror_dir.path().to_owned(),
+                mirror_writes: false,
+            }),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        assert!(
+            !mirror_dir.path().join(&object_key).exists(),
+            "writes should not be mirrored"
+        );
+
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+        assert_eq!(get_counter.count(), 1, "block should be read from the cache bucket");
+    }
+
+    #[tokio::test]
+    async fn test_get_block_read_through() {
+        let bucket = "test-bucket";
+        let origin_bucket = "origin-bucket";
+        let client = new_mock_client(bucket);
+        let put_counter = client.new_counter(Operation::PutObjectSingle);
+        let origin_client = new_mock_client(origin_bucket);
+        let origin_get_counter = origin_client.new_counter(Operation::GetObject);
+        let object_data: Vec<u8> = (0..1500u32).map(|i| i as u8).collect();
+        let etag = ETag::for_tests();
+        origin_client.add_object("a", MockObject::from_bytes(&object_data, etag.clone()));
+
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            ..Default::default()
+        };
+        let origin = OriginSource {
+            client: origin_client.clone(),
+            bucket: origin_bucket.to_owned(),
+        };
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let cache = Arc::new(
+            ExpressDataCache::try_new(client.clone(), config, origin_bucket, bucket)
+                .expect("cache config should be valid")
+                .with_origin(origin, runtime),
+        );
+
+        let cache_key = ObjectId::new("a".into(), etag);
+        let block = cache
+            .get_block_read_through(&cache_key, 1, 1024, object_data.len())
+            .await
+            .expect("block should be read from origin")
+            .expect("block should be returned");
+        assert_eq!(
+            block.into_bytes().unwrap(),
+            Bytes::copy_from_slice(&object_data[1024..])
+        );
+        assert_eq!(origin_get_counter.count(), 1);
+
+        let mut retries = 50;
+        while put_counter.count() == 0 && retries > 0 {
+            async_io::Timer::after(Duration::from_millis(20)).await;
+            retries -= 1;
+        }
+        let block = cache
+            .get_block(&cache_key, 1, 1024, object_data.len())
+            .await
+            .expect("cache should be accessible")
+            .expect("block should be written to the cache");
+        assert_eq!(
+            block.into_bytes().unwrap(),
+            Bytes::copy_from_slice(&object_data[1024..])
+        );
+        let block = cache
+            .get_block_read_through(&cache_key, 1, 1024, object_data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_some());
+        assert_eq!(origin_get_counter.count(), 1, "block should be served from the cache");
+    }
+
+    #[test_case("\"d41d8cd98f00b204e9800998ecf8427e\"", true; "simple etag")]
+    #[test_case("\"d41d8cd98f00b204e9800998ecf8427e-3\"", false; "multipart etag")]
+    #[tokio::test]
+    async fn test_skip_unstable_etags(etag: &str, expect_cached: bool) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            skip_unstable_etags: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::from_str(etag).unwrap());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(client.object_count(), usize::from(expect_cached));
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block.is_some(), expect_cached);
+    }
+
+    #[test_case(Duration::from_secs(3600), true; "unexpired")]
+    #[test_case(Duration::ZERO, false; "expired")]
+    #[tokio::test]
+    async fn test_block_expiry(block_expiry: Duration, expect_hit: bool) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_expiry: Some(block_expiry),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let result = client
+            .get_object(bucket, &object_key, &Default::default())
+            .await
+            .expect("block should be stored");
+        assert!(result.get_object_metadata().contains_key(EXPIRES_AT_HEADER));
+
+        // Reads honor the recorded expiry whatever their configuration.
+        let reader = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        let block = reader
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block.is_some(), expect_hit);
+    }
+
+    #[tokio::test]
+    async fn test_block_expiry_with_mock_clock() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_expiry: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let clock = MockClock::new(OffsetDateTime::now_utc());
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid")
+            .with_clock(clock.clone());
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        clock.advance(Duration::from_secs(59));
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()), "block should not have expired yet");
+
+        clock.advance(Duration::from_secs(1));
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none(), "block should have expired");
+    }
+
+    #[test]
+    fn test_http_date_format() {
+        let date = time::macros::datetime!(1994-11-06 08:49:37 UTC);
+        assert_eq!(date.format(HTTP_DATE_FORMAT).unwrap(), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+
+    #[test_case(false, 0, true; "not verified")]
+    #[test_case(true, 0, false; "truncated block")]
+    #[test_case(true, 1, true; "last block")]
+    #[tokio::test]
+    async fn test_verify_block_length(verify_block_length: bool, block_idx: BlockIndex, expect_hit: bool) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let block_size = 1024;
+        let config = ExpressDataCacheConfig {
+            block_size,
+            verify_block_length,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        // A block of half the block size, for an object of two blocks.
+        let data = ChecksummedBytes::new(vec![1u8; block_size as usize / 2].into());
+        let object_size = 2 * block_size as usize;
+        let block_offset = block_idx * block_size;
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), block_idx, block_offset, data.clone(), object_size)
+            .await
+            .expect("put should succeed");
+        let block = cache
+            .get_block(&cache_key, block_idx, block_offset, object_size)
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block.is_some(), expect_hit);
+    }
+
+    #[tokio::test]
+    async fn test_warm() {
+        let bucket = "test-bucket";
+        let origin_bucket = "origin-bucket";
+        let block_size = 1024;
+        let block_count = 50;
+        let client = new_mock_client(bucket);
+        let origin_client = new_mock_client(origin_bucket);
+        let object_data: Vec<u8> = (0..block_count * block_size - 100).map(|i| i as u8).collect();
+        let etag = ETag::for_tests();
+        origin_client.add_object("a", MockObject::from_bytes(&object_data, etag.clone()));
+
+        let config = ExpressDataCacheConfig {
+            block_size: block_size as u64,
+            max_object_size: object_data.len(),
+            ..Default::default()
+        };
+        let origin = OriginSource {
+            client: HookClient::new(origin_client.clone(), PutTracking::default()),
+            bucket: origin_bucket.to_owned(),
+        };
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let cache = ExpressDataCache::try_new(
+            HookClient::new(client.clone(), PutTracking::default()),
+            config,
+            origin_bucket,
+            bucket,
+        )
+        .expect("cache config should be valid")
+        .with_origin(origin, runtime);
+
+        let cache_key = ObjectId::new("a".into(), etag);
+        let report = cache
+            .warm(&cache_key, object_data.len(), 4)
+            .await
+            .expect("warm should succeed");
+        assert_eq!(report.copied, block_count as u64);
+        assert!(
+            report.failed.is_empty(),
+            "no block should fail, got {:?}",
+            report.failed
+        );
+        assert_eq!(client.object_count(), block_count);
+        let max_in_flight = cache.client.hook.max_in_flight.load(Ordering::SeqCst);
+        assert!(
+            (2..=4).contains(&max_in_flight),
+            "copies should be concurrent and bounded, got {max_in_flight}"
+        );
+
+        let block = cache
+            .get_block(&cache_key, 49, 49 * block_size as u64, object_data.len())
+            .await
+            .expect("cache should be accessible")
+            .expect("block should be warmed");
+        assert_eq!(
+            block.into_bytes().unwrap(),
+            Bytes::copy_from_slice(&object_data[49 * block_size..])
+        );
+
+        // Blocks of an outdated ETag cannot be read from the origin, and are all reported.
+        let outdated_key = ObjectId::new("a".into(), ETag::from_str("\"outdated\"").unwrap());
+        let report = cache
+            .warm(&outdated_key, object_data.len(), 4)
+            .await
+            .expect("warm should complete");
+        assert_eq!(report.copied, 0);
+        let failed: Vec<_> = report.failed.iter().map(|(block_idx, _)| *block_idx).collect();
+        assert_eq!(failed, (0..block_count as u64).collect::<Vec<_>>());
+    }
+
+    /// Stores even blocks in the cache bucket and odd blocks in a standard bucket.
+    struct EvenOddTiers;
+
+    impl TierPolicy for EvenOddTiers {
+        fn tier_for(&self, _key: &ObjectId, block_idx: BlockIndex) -> Tier {
+            if block_idx % 2 == 0 {
+                Tier {
+                    bucket_name: "hot-bucket".to_owned(),
+                    storage_class: None,
+                }
+            } else {
+                Tier {
+                    bucket_name: "cold-bucket".to_owned(),
+                    storage_class: Some("STANDARD_IA".to_owned()),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tier_policy() {
+        let hot_client = new_mock_client("hot-bucket");
+        let cold_client = new_mock_client("cold-bucket");
+        let client = HookClient::new(hot_client.clone(), ()).with_bucket_client("cold-bucket", cold_client.clone());
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            max_object_size: 4096,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", "hot-bucket")
+            .expect("cache config should be valid")
+            .with_tier_policy(EvenOddTiers);
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let object_size = 4096;
+        for block_idx in 0..4 {
+            let data = ChecksummedBytes::new(vec![block_idx as u8; 1024].into());
+            cache
+                .put_block(cache_key.clone(), block_idx, block_idx * 1024, data, object_size)
+                .await
+                .expect("put should succeed");
+        }
+        assert_eq!(hot_client.object_count(), 2);
+        assert_eq!(cold_client.object_count(), 2);
+        let cold_key = get_s3_key(&cache.prefix, &cache_key, 1);
+        assert_eq!(
+            cold_client.get_object_storage_class(&cold_key).unwrap().as_deref(),
+            Some("STANDARD_IA")
+        );
+
+        for block_idx in 0..4 {
+            let block = cache
+                .get_block(&cache_key, block_idx, block_idx * 1024, object_size)
+                .await
+                .expect("cache should be accessible")
+                .expect("block should be found in its tier");
+            assert_eq!(block.into_bytes().unwrap(), Bytes::from(vec![block_idx as u8; 1024]));
+        }
+    }
+
+    /// Client error carrying the error code of an S3 response.
+    #[derive(Debug, thiserror::Error)]
+    #[error("request failed with {0}")]
+    struct ErrorCodeClientError(&'static str);
+
+    impl ProvideErrorMetadata for ErrorCodeClientError {
+        fn meta(&self) -> ClientErrorMetadata {
+            ClientErrorMetadata {
+                http_code: Some(403),
+                error_code: Some(self.0.to_owned()),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[test_case(Ok(()), CredentialsCheck::Valid; "success")]
+    #[test_case(Err(ObjectClientError::ServiceError(ListObjectsError::NoSuchBucket)), CredentialsCheck::Valid; "no such bucket")]
+    #[test_case(Err(ObjectClientError::ClientError(ErrorCodeClientError("AccessDenied"))), CredentialsCheck::Valid; "access denied")]
+    #[test_case(
+        Err(ObjectClientError::ClientError(ErrorCodeClientError("InvalidAccessKeyId"))),
+        CredentialsCheck::InvalidCredentials("InvalidAccessKeyId".to_owned());
+        "invalid access key"
+    )]
+    #[test_case(
+        Err(ObjectClientError::ClientError(ErrorCodeClientError("ExpiredToken"))),
+        CredentialsCheck::InvalidCredentials("ExpiredToken".to_owned());
+        "expired token"
+    )]
+    fn test_credentials_check(
+        result: ObjectClientResult<(), ListObjectsError, ErrorCodeClientError>,
+        expected: CredentialsCheck,
+    ) {
+        assert_eq!(credentials_check(result), expected);
+    }
+
+    #[tokio::test]
+    async fn test_check_credentials_missing_bucket() {
+        // The mock client only serves another bucket, so the cache bucket does not exist.
+        let client = new_mock_client("other-bucket");
+        let cache = ExpressDataCache::try_new(
+            client,
+            Default::default(),
+            "unique source description",
+            "missing-bucket",
+        )
+        .expect("cache config should be valid");
+        assert_eq!(cache.check_credentials().await, CredentialsCheck::Valid);
+        assert!(cache.verify_cache_valid().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_completion_markers() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            completion_markers: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let marker_key = completion_marker_key(&object_key);
+        assert!(client.contains_key(&marker_key));
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()), "completed block should be a hit");
+
+        // Simulate an upload in progress, where the data is written but not the marker.
+        client.remove_object(&marker_key);
+        assert!(client.contains_key(&object_key));
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none(), "block without marker should be a miss");
+    }
+
+    #[test_case(ChecksumLocation::Metadata; "metadata")]
+    #[test_case(ChecksumLocation::TrailingBytes; "trailing bytes")]
+    #[tokio::test]
+    async fn test_checksum_location(checksum_location: ChecksumLocation) {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            checksum_location,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let params = HeadObjectParams::new().checksum_mode(Some(ChecksumMode::Enabled));
+        let head = client
+            .head_object(bucket, &object_key, &params)
+            .await
+            .expect("block should be stored");
+        let trailer = crc32c::checksum(b"Foo").value().to_be_bytes();
+        match checksum_location {
+            ChecksumLocation::Metadata => {
+                assert_eq!(head.size, 3);
+                assert!(head.checksum.checksum_crc32c.is_some());
+            }
+            ChecksumLocation::TrailingBytes => {
+                assert_eq!(head.size, 3 + TRAILING_CHECKSUM_LEN as u64);
+                assert_eq!(head.checksum.checksum_crc32c, None);
+            }
+        }
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data), "trailing checksum should be stripped");
+
+        // Replace the content of the block, keeping its metadata and checksum.
+        let block_metadata = BlockMetadata::new(0, 0, &cache_key, source_bucket, crc32c::checksum(b"Foo"));
+        let mut object_metadata = block_metadata.to_object_metadata();
+        let mut tampered_data = b"Boo".to_vec();
+        if checksum_location == ChecksumLocation::TrailingBytes {
+            object_metadata.insert(CHECKSUM_LOCATION_HEADER.to_string(), TRAILING.to_string());
+            tampered_data.extend_from_slice(&trailer);
+        }
+        let mut tampered = MockObject::from_bytes(&tampered_data, ETag::for_tests());
+        tampered.set_object_metadata(object_metadata);
+        tampered.set_checksum(head.checksum);
+        client.add_object(&object_key, tampered);
+        let result = cache.get_block(&cache_key, 0, 0, 3).await;
+        match checksum_location {
+            // The block is returned with the stored checksum, which its content does not match.
+            ChecksumLocation::Metadata => {
+                let block = result
+                    .expect("cache should be accessible")
+                    .expect("block should be found");
+                block.into_bytes().expect_err("tampered block should fail validation");
+            }
+            ChecksumLocation::TrailingBytes => {
+                let err = result.expect_err("tampered block should fail validation");
+                assert!(matches!(err, DataCacheError::InvalidBlockChecksum), "{err:?}");
+            }
+        }
+    }
+
+    #[test_case(false; "strict")]
+    #[test_case(true; "reordered")]
+    #[tokio::test]
+    async fn test_reorder_chunks(reorder_chunks: bool) {
+        let bucket = "test-bucket";
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 256,
+            enable_backpressure: true,
+            initial_read_window_size: 2048,
+            ..Default::default()
+        }));
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            reorder_chunks,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(
+                cache_key.clone(),
+                0,
+                0,
+                ChecksummedBytes::new(data.clone().into()),
+                data.len(),
+            )
+            .await
+            .expect("put should succeed");
+
+        client.reverse_parts_of_next_read(&get_s3_key(&cache.prefix, &cache_key, 0));
+        let result = cache.get_block(&cache_key, 0, 0, data.len()).await;
+        if reorder_chunks {
+            let block = result
+                .expect("cache should be accessible")
+                .expect("block should be a hit");
+            assert_eq!(block.into_bytes().unwrap(), Bytes::from(data));
+        } else {
+            let err = result.expect_err("chunks out of order should fail the read");
+            assert!(matches!(err, DataCacheError::InvalidBlockOffset), "{err:?}");
+        }
+    }
+
+    #[test_case(ChecksumMismatchPolicy::Error; "error")]
+    #[test_case(ChecksumMismatchPolicy::Miss; "miss")]
+    #[test_case(ChecksumMismatchPolicy::MissAndDelete; "miss and delete")]
+    #[tokio::test]
+    async fn test_checksum_mismatch_policy(checksum_mismatch_policy: ChecksumMismatchPolicy) {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            checksum_mismatch_policy,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()), "valid block should be a hit");
+
+        // Replace the content of the block, keeping its metadata and checksum.
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let params = HeadObjectParams::new().checksum_mode(Some(ChecksumMode::Enabled));
+        let head = client
+            .head_object(bucket, &object_key, &params)
+            .await
+            .expect("block should be stored");
+        let block_metadata = BlockMetadata::new(0, 0, &cache_key, source_bucket, crc32c::checksum(b"Foo"));
+        let mut tampered = MockObject::from_bytes(b"Boo", ETag::for_tests());
+        tampered.set_object_metadata(block_metadata.to_object_metadata());
+        tampered.set_checksum(head.checksum);
+        client.add_object(&object_key, tampered);
+
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        match checksum_mismatch_policy {
+            ChecksumMismatchPolicy::Error => {
+                let block = block.expect("block should be found");
+                block.into_bytes().expect_err("tampered block should fail validation");
+            }
+            ChecksumMismatchPolicy::Miss | ChecksumMismatchPolicy::MissAndDelete => {
+                assert!(block.is_none(), "tampered block should be a miss");
+            }
+        }
+        assert_eq!(
+            client.contains_key(&object_key),
+            checksum_mismatch_policy != ChecksumMismatchPolicy::MissAndDelete
+        );
+    }
+
+    #[tokio::test]
+    async fn test_object_metadata() {
+        let bucket = "test-bucket";
+        let cache = ExpressDataCache::try_new(
+            new_mock_client(bucket),
+            Default::default(),
+            "unique source description",
+            bucket,
+        )
+        .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let other_etag = ObjectId::new("a".into(), ETag::from_str("\"other-etag\"").unwrap());
+        let other_key = ObjectId::new("b".into(), ETag::for_tests());
+        assert_eq!(
+            cache.get_object_metadata(&cache_key).await.expect("get should succeed"),
+            None
+        );
+
+        let metadata = HashMap::from([
+            ("owner".to_string(), "analytics".to_string()),
+            ("content-language".to_string(), "en".to_string()),
+        ]);
+        cache
+            .put_object_metadata(&cache_key, &metadata)
+            .await
+            .expect("put should succeed");
+        let cached = cache.get_object_metadata(&cache_key).await.expect("get should succeed");
+        assert_eq!(cached, Some(metadata));
+        for other in [&other_etag, &other_key] {
+            let cached = cache.get_object_metadata(other).await.expect("get should succeed");
+            assert_eq!(cached, None, "metadata should not be found for {other:?}");
+        }
+
+        // The metadata object is not mistaken for a block.
+        let present = cache
+            .present_blocks(&cache_key, 0..1)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(present, vec![false]);
+    }
+
+    #[tokio::test]
+    async fn test_block_listing_ttl() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_listing_ttl: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        let clock = MockClock::new(OffsetDateTime::now_utc());
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid")
+            .with_clock(clock.clone());
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        let list_counter = client.new_counter(Operation::ListObjectsV2);
+        for _ in 0..2 {
+            let present = cache
+                .present_blocks(&cache_key, 0..2)
+                .await
+                .expect("listing should succeed");
+            assert_eq!(present, vec![true, false]);
+        }
+        assert_eq!(list_counter.count(), 1, "the listing should be reused");
+
+        cache
+            .put_block(
+                cache_key.clone(),
+                1,
+                cache.config.block_size,
+                data.clone(),
+                2 * data.len(),
+            )
+            .await
+            .expect("put should succeed");
+        let present = cache
+            .present_blocks(&cache_key, 0..2)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(present, vec![true, true], "a put should invalidate the listing");
+        assert_eq!(list_counter.count(), 2);
+
+        // Blocks written by others are seen once the listing expires.
+        client.remove_object(&get_s3_key(&cache.prefix, &cache_key, 1));
+        clock.advance(Duration::from_secs(9));
+        let present = cache
+            .present_blocks(&cache_key, 0..2)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(present, vec![true, true]);
+        clock.advance(Duration::from_secs(1));
+        let present = cache
+            .present_blocks(&cache_key, 0..2)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(present, vec![true, false]);
+        assert_eq!(list_counter.count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_present_blocks() {
+        let bucket = "test-bucket";
+        let block_size = 1024;
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_size,
+            max_object_size: 8 * 1024,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        let object_size = 6 * block_size as usize;
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let other_key = ObjectId::new("b".into(), ETag::for_tests());
+        let data = ChecksummedBytes::new(Bytes::from(vec![1u8; block_size as usize]));
+        for block_idx in [0, 2, 3, 5] {
+            cache
+                .put_block(
+                    cache_key.clone(),
+                    block_idx,
+                    block_idx * block_size,
+                    data.clone(),
+                    object_size,
+                )
+                .await
+                .expect("put should succeed");
+        }
+        cache
+            .put_block(other_key, 1, block_size, data.clone(), object_size)
+            .await
+            .expect("put should succeed");
+
+        let list_counter = client.new_counter(Operation::ListObjectsV2);
+        let head_counter = client.new_counter(Operation::HeadObject);
+        let present = cache
+            .present_blocks(&cache_key, 1..5)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(present, vec![false, true, true, false]);
+        assert_eq!(list_counter.count(), 1);
+        assert_eq!(head_counter.count(), 0);
+
+        let present = cache
+            .present_blocks(&cache_key, 4..8)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(present, vec![false, true, false, false]);
+        let present = cache
+            .present_blocks(&cache_key, 3..3)
+            .await
+            .expect("listing should succeed");
+        assert!(present.is_empty());
+
+        // Ranges are clamped to the blocks of an object of the maximum size.
+        let present = cache
+            .present_blocks(&cache_key, 2..u64::MAX)
+            .await
+            .expect("listing should succeed");
+        assert_eq!(present, vec![true, true, false, true, false, false]);
+        let present = cache
+            .present_blocks(&cache_key, 10..u64::MAX)
+            .await
+            .expect("listing should succeed");
+        assert!(present.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_export_import_object() {
+        let bucket = "test-bucket";
+        let block_size = 1024;
+        let config = || ExpressDataCacheConfig {
+            block_size,
+            max_object_size: 4096,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(new_mock_client(bucket), config(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        let object_data: Vec<u8> = (0..2500u32).map(|i| i as u8).collect();
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        for (block_idx, chunk) in object_data.chunks(block_size as usize).enumerate() {
+            let block_idx = block_idx as BlockIndex;
+            cache
+                .put_block(
+                    cache_key.clone(),
+                    block_idx,
+                    block_idx * block_size,
+                    ChecksummedBytes::new(Bytes::copy_from_slice(chunk)),
+                    object_data.len(),
+                )
+                .await
+                .expect("put should succeed");
+        }
+
+        let mut archive = Vec::new();
+        let written = cache
+            .export_object(&cache_key, &mut archive)
+            .await
+            .expect("export should succeed");
+        assert_eq!(written, archive.len() as u64);
+        assert_eq!(
+            archive.len(),
+            EXPORT_MAGIC.len() + 3 * EXPORT_RECORD_HEADER_LEN + object_data.len()
+        );
+
+        let client = new_mock_client(bucket);
+        let fresh_cache = ExpressDataCache::try_new(client.clone(), config(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        let imported = fresh_cache
+            .import_object(&cache_key, object_data.len(), archive.as_slice())
+            .await
+            .expect("import should succeed");
+        assert_eq!(imported, 3);
+        assert_eq!(client.object_count(), 3);
+        for (block_idx, chunk) in object_data.chunks(block_size as usize).enumerate() {
+            let block_idx = block_idx as BlockIndex;
+            let block = fresh_cache
+                .get_block(&cache_key, block_idx, block_idx * block_size, object_data.len())
+                .await
+                .expect("cache should be accessible")
+                .expect("block should be imported");
+            assert_eq!(block.into_bytes().unwrap(), Bytes::copy_from_slice(chunk));
+        }
+
+        // Flip a byte of the data of the first block.
+        archive[EXPORT_MAGIC.len() + EXPORT_RECORD_HEADER_LEN] ^= 1;
+        let result = fresh_cache
+            .import_object(&cache_key, object_data.len(), archive.as_slice())
+            .await;
+        assert!(matches!(result, Err(DataCacheError::InvalidBlockChecksum)));
+    }
+
+    async fn cache_get(
+        cache: &ExpressDataCache<Arc<MockClient>>,
+        cache_key: &ObjectId,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        cache.get_block(cache_key, 0, 0, 1).await
+    }
+
+    #[tokio::test]
+    async fn test_shared_rate_limit() {
+        // The limiter is shared by every cache of the process, so the bucket is not used by other tests.
+        let bucket = "rate-limited-bucket";
+        let requests_per_second = 100;
+        let requests_per_cache = 10;
+        let new_cache = |source: &str| {
+            let config = ExpressDataCacheConfig {
+                shared_requests_per_second: Some(requests_per_second),
+                ..Default::default()
+            };
+            ExpressDataCache::try_new(new_mock_client(bucket), config, source, bucket)
+                .expect("cache config should be valid")
+        };
+        let first_cache = new_cache("first source");
+        let second_cache = new_cache("second source");
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let start = Instant::now();
+        let (first_results, second_results) = futures::join!(
+            futures::future::join_all((0..requests_per_cache).map(|_| cache_get(&first_cache, &cache_key))),
+            futures::future::join_all((0..requests_per_cache).map(|_| cache_get(&second_cache, &cache_key))),
+        );
+        let elapsed = start.elapsed();
+        for result in first_results.into_iter().chain(second_results) {
+            assert!(matches!(result, Ok(None)), "empty cache should miss, got {result:?}");
+        }
+
+        // The first request is sent immediately, the others are spaced by the shared limit.
+        let expected = Duration::from_secs(1) * (2 * requests_per_cache - 1) / requests_per_second;
+        assert!(
+            elapsed >= expected,
+            "requests of both caches should share the limit, took {elapsed:?}, expected at least {expected:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_replica_failover() {
+        let replica_bucket = "replica-bucket";
+        let client = new_mock_client(replica_bucket);
+        let put_single_counter = client.new_counter(Operation::PutObjectSingle);
+
+        // The mock client only serves the replica bucket, so the cache bucket appears unavailable.
+        let replica_cache = ExpressDataCache::try_new(
+            client.clone(),
+            Default::default(),
+            "unique source description",
+            replica_bucket,
+        )
+        .expect("cache config should be valid");
+        let config = ExpressDataCacheConfig {
+            read_replica_bucket_name: Some(replica_bucket.to_string()),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(
+            client.clone(),
+            config,
+            "unique source description",
+            "unavailable-bucket",
+        )
+        .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        replica_cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(put_single_counter.count(), 1);
+
+        let entry = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("read should fail over to the replica")
+            .expect("cache entry should be returned");
+        assert_eq!(data, entry);
+
+        // Writes are skipped while failed over.
+        let other_cache_key = ObjectId::new("b".into(), ETag::for_tests());
+        cache
+            .put_block(other_cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should be a no-op");
+        assert_eq!(put_single_counter.count(), 1);
+        assert_eq!(client.object_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_block_checksum() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let get_counter = client.new_counter(Operation::GetObject);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let checksum = cache
+            .block_checksum(&cache_key, 0)
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(checksum, None);
+
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("cache should be accessible");
+        let checksum = cache
+            .block_checksum(&cache_key, 0)
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(checksum, Some(crc32c::checksum(b"Foo")));
+        assert_eq!(get_counter.count(), 0, "block content should not be downloaded");
+    }
+
+    #[test_case(99, false; "below threshold")]
+    #[test_case(100, true; "at threshold")]
+    #[tokio::test]
+    async fn test_min_cacheable_block_size(len: usize, expect_cached: bool) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            min_cacheable_block_size: 100,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new(vec![0u8; len].into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        let entry = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(entry.is_some(), expect_cached);
+        assert_eq!(client.object_count(), if expect_cached { 1 } else { 0 });
+    }
+
+    #[test_case(false; "stored")]
+    #[test_case(true; "skipped")]
+    #[tokio::test]
+    async fn test_empty_block(skip_empty_blocks: bool) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            skip_empty_blocks,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new(Bytes::new());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), 0)
+            .await
+            .expect("put should succeed");
+        assert_eq!(client.object_count(), if skip_empty_blocks { 0 } else { 1 });
+
+        let entry = cache
+            .get_block(&cache_key, 0, 0, 0)
+            .await
+            .expect("cache should be accessible");
+        if skip_empty_blocks {
+            assert!(entry.is_none(), "skipped empty block should be a miss");
+        } else {
+            assert_eq!(entry, Some(data));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compact_object() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let block_size = 1024;
+        let config = || ExpressDataCacheConfig {
+            block_size,
+            mpu_threshold_bytes: Some(4096),
+            compacted_blocks: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config(), "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let object_data: Vec<u8> = (0..6 * block_size - 100).map(|i| (i % 251) as u8).collect();
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        for (block_idx, chunk) in object_data.chunks(block_size as usize).enumerate() {
+            let block_idx = block_idx as BlockIndex;
+            cache
+                .put_block(
+                    cache_key.clone(),
+                    block_idx,
+                    block_idx * block_size,
+                    ChecksummedBytes::new(Bytes::copy_from_slice(chunk)),
+                    object_data.len(),
+                )
+                .await
+                .expect("put should succeed");
+        }
+        assert_eq!(client.object_count(), 6);
+
+        cache
+            .compact_object(&cache_key)
+            .await
+            .expect("compaction should succeed");
+        for block_idx in 0..6 {
+            assert!(!client.contains_key(&get_s3_key(&cache.prefix, &cache_key, block_idx)));
+        }
+        assert_eq!(
+            client.object_count(),
+            3,
+            "blocks should be rewritten as two packs and an index"
+        );
+
+        for (block_idx, chunk) in object_data.chunks(block_size as usize).enumerate() {
+            let block_idx = block_idx as BlockIndex;
+            let block = cache
+                .get_block(&cache_key, block_idx, block_idx * block_size, object_data.len())
+                .await
+                .expect("cache should be accessible")
+                .expect("packed block should be a hit");
+            assert_eq!(block.into_bytes().unwrap(), Bytes::copy_from_slice(chunk));
+        }
+
+        let config = ExpressDataCacheConfig {
+            compacted_blocks: false,
+            ..config()
+        };
+        let other_cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        let block = other_cache
+            .get_block(&cache_key, 0, 0, object_data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none(), "packed blocks should only be read when enabled");
+    }
+
+    #[tokio::test]
+    async fn test_max_cached_objects() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            max_cached_objects: Some(2),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let keys: Vec<_> = ["a", "b", "c"]
+            .into_iter()
+            .map(|key| ObjectId::new(key.into(), ETag::for_tests()))
+            .collect();
+        for cache_key in &keys[..2] {
+            cache
+                .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+                .await
+                .expect("put should succeed");
+        }
+        // Reading "a" makes "b" the least recently used object.
+        let block = cache
+            .get_block(&keys[0], 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_some());
+
+        cache
+            .put_block(keys[2].clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert!(client.contains_key(&get_s3_key(&cache.prefix, &keys[0], 0)));
+        assert!(
+            !client.contains_key(&get_s3_key(&cache.prefix, &keys[1], 0)),
+            "LRU object should be evicted"
+        );
+        assert!(client.contains_key(&get_s3_key(&cache.prefix, &keys[2], 0)));
+
+        // Writes for a tracked object do not evict others.
+        cache
+            .put_block(keys[0].clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(client.object_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sparse_holes() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let block_size = 1024;
+        let config = ExpressDataCacheConfig {
+            block_size,
+            sparse_holes: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        let get_counter = client.new_counter(Operation::GetObject);
+
+        let object_size = 3000;
+        let blocks = [vec![0u8; 1024], vec![1u8; 1024], vec![0u8; 952]];
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        for (block_idx, data) in blocks.iter().enumerate() {
+            let block_idx = block_idx as BlockIndex;
+            cache
+                .put_block(
+                    cache_key.clone(),
+                    block_idx,
+                    block_idx * block_size,
+                    ChecksummedBytes::new(data.clone().into()),
+                    object_size,
+                )
+                .await
+                .expect("put should succeed");
+        }
+        assert!(!client.contains_key(&get_s3_key(&cache.prefix, &cache_key, 0)));
+        assert!(client.contains_key(&get_s3_key(&cache.prefix, &cache_key, 1)));
+        assert!(!client.contains_key(&get_s3_key(&cache.prefix, &cache_key, 2)));
+        assert_eq!(
+            client.object_count(),
+            3,
+            "only the non-zero block and the hole markers should be stored"
+        );
+        assert_eq!(get_counter.count(), 0, "holes should be recorded without reading");
+
+        for (block_idx, data) in blocks.iter().enumerate() {
+            let block_idx = block_idx as BlockIndex;
+            let block = cache
+                .get_block(&cache_key, block_idx, block_idx * block_size, object_size)
+                .await
+                .expect("cache should be accessible")
+                .expect("block should be a hit");
+            assert_eq!(block.into_bytes().unwrap(), Bytes::from(data.clone()));
+        }
+
+        let other_etag = ObjectId::new("a".into(), ETag::from_str("\"other-etag\"").unwrap());
+        let block = cache
+            .get_block(&other_etag, 0, 0, object_size)
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none(), "holes should not be shared between object versions");
+
+        // A marker for a hole longer than the block is rejected rather than served.
+        let hole = Hole {
+            source_key: "a".to_string(),
+            etag: cache_key.etag().as_str().to_string(),
+            len: 1 << 40,
+        };
+        let marker_key = hole_key(KeyHasher::STANDARD, &cache.prefix, &cache_key, 0);
+        client.add_object(
+            &marker_key,
+            MockObject::from_bytes(&serde_json::to_vec(&hole).unwrap(), ETag::for_tests()),
+        );
+        let err = cache
+            .get_block(&cache_key, 0, 0, object_size)
+            .await
+            .expect_err("hole of the wrong length should fail");
+        assert!(matches!(err, DataCacheError::InvalidBlockContent), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_read_compatible_cache_version() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            compatible_cache_versions: vec!["V3-alpha".to_string()],
+            ..Default::default()
+        };
+        let block_size = config.block_size;
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        // Write a block as the older version would.
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let (data, checksum) = ChecksummedBytes::new("Foo".into()).into_inner().unwrap();
+        let old_prefix = build_prefix_for_version(
+            "V3-alpha",
+            KeyFormat::Standard,
+            KeyEncoding::LowerHex,
+            KeyHashAlgorithm::Sha256,
+            None,
+            None,
+            source_bucket,
+            block_size,
+        );
+        let old_key = get_s3_key(&old_prefix, &cache_key, 0);
+        let block_metadata = BlockMetadata::new_inner("V3-alpha", 0, 0, &cache_key, source_bucket, checksum, false);
+        client
+            .put_object_single(bucket, &old_key, &block_metadata.to_put_object_params(), data.clone())
+            .await
+            .unwrap();
+
+        let (received_data, _) = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed")
+            .expect("block written by the compatible version should be found")
+            .into_inner()
+            .unwrap();
+        assert_eq!(received_data, data);
+
+        let incompatible_cache = ExpressDataCache::try_new(client.clone(), Default::default(), source_bucket, bucket)
+            .expect("cache config should be valid");
+        let entry = incompatible_cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed");
+        assert!(entry.is_none(), "older versions should not be read unless configured");
+
+        // New blocks are only written for the current version.
+        let new_data = ChecksummedBytes::new("Bar".into());
+        let new_cache_key = ObjectId::new("b".into(), ETag::for_tests());
+        cache
+            .put_block(new_cache_key.clone(), 0, 0, new_data.clone(), new_data.len())
+            .await
+            .expect("put should succeed");
+        assert!(client.contains_key(&get_s3_key(&cache.prefix, &new_cache_key, 0)));
+        assert!(!client.contains_key(&get_s3_key(&old_prefix, &new_cache_key, 0)));
+    }
+
+    #[test]
+    fn test_source_description_normalizer() {
+        fn strip_session(description: &str) -> String {
+            description.split(';').next().unwrap().to_owned()
+        }
+
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let new_cache = |source_description: &str, normalize: bool| {
+            let config = ExpressDataCacheConfig {
+                source_description_normalizer: normalize.then_some(strip_session as fn(&str) -> String),
+                ..Default::default()
+            };
+            ExpressDataCache::try_new(client.clone(), config, source_description, bucket)
+                .expect("cache config should be valid")
+        };
+
+        let cache_a = new_cache("source-bucket;session=1", true);
+        let cache_b = new_cache("source-bucket;session=2", true);
+        assert_eq!(cache_a.prefix, cache_b.prefix);
+        assert_eq!(
+            cache_a.prefix,
+            build_prefix("source-bucket", ExpressDataCacheConfig::default().block_size)
+        );
+
+        let cache_c = new_cache("source-bucket;session=1", false);
+        let cache_d = new_cache("source-bucket;session=2", false);
+        assert_ne!(cache_c.prefix, cache_d.prefix);
+    }
+
+    #[test]
+    fn test_invalid_offset_metric() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let data = ChecksummedBytes::new("Foo".into());
+
+        let (_, metrics) = with_captured_metrics(|| {
+            futures::executor::block_on(async {
+                let err = cache
+                    .get_block(&cache_key, 1, 0, data.len())
+                    .await
+                    .expect_err("get with invalid offset should fail");
+                assert!(matches!(err, DataCacheError::InvalidBlockOffset));
+                let err = cache
+                    .put_block(cache_key.clone(), 1, 0, data.clone(), data.len())
+                    .await
+                    .expect_err("put with invalid offset should fail");
+                assert!(matches!(err, DataCacheError::InvalidBlockOffset));
+                cache
+                    .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+                    .await
+                    .expect("put with valid offset should succeed");
+            })
+        });
+        assert_eq!(metrics.counter("express_data_cache.invalid_offset"), 2);
+    }
+
+    #[test]
+    fn test_would_hit() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let data = ChecksummedBytes::new("Foo".into());
+        futures::executor::block_on(cache.put_block(cache_key.clone(), 0, 0, data.clone(), data.len()))
+            .expect("put should succeed");
+
+        let (_, metrics) = with_captured_metrics(|| {
+            futures::executor::block_on(async {
+                assert!(cache
+                    .would_hit(&cache_key, 0)
+                    .await
+                    .expect("cache should be accessible"));
+                assert!(!cache
+                    .would_hit(&cache_key, 1)
+                    .await
+                    .expect("cache should be accessible"));
+            })
+        });
+        assert_eq!(metrics.counter("express_data_cache.block_hit"), 0);
+        assert_eq!(metrics.histogram_max("express_data_cache.read_duration_us"), None);
+        assert_eq!(cache.hit_ratio(), 0.0);
+
+        let block = futures::executor::block_on(cache.get_block(&cache_key, 0, 0, data.len()))
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+        assert_eq!(cache.hit_ratio(), 1.0);
+    }
+
+    #[test]
+    fn test_window_increments_metric() {
+        let bucket = "test-bucket";
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 256,
+            enable_backpressure: true,
+            initial_read_window_size: 256,
+            ..Default::default()
+        }));
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        let data = ChecksummedBytes::new(vec![0u8; 1024].into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+
+        let (_, metrics) = with_captured_metrics(|| {
+            futures::executor::block_on(async {
+                cache
+                    .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+                    .await
+                    .expect("put should succeed");
+                let block = cache
+                    .get_block(&cache_key, 0, 0, data.len())
+                    .await
+                    .expect("cache should be accessible");
+                assert_eq!(block, Some(data.clone()));
+            })
+        });
+        // One increment to start the request, then one for each of the 4 chunks.
+        assert_eq!(metrics.histogram_max("express_cache.get.window_increments"), Some(5));
+    }
+
+    #[test]
+    fn test_cold_and_warm_get_metrics() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        futures::executor::block_on(cache.put_block(cache_key.clone(), 0, 0, data.clone(), data.len()))
+            .expect("put should succeed");
+
+        for (expect_cold, description) in [(true, "first"), (false, "second")] {
+            let (block, metrics) = with_captured_metrics(|| {
+                futures::executor::block_on(cache.get_block(&cache_key, 0, 0, data.len()))
+                    .expect("cache should be accessible")
+            });
+            assert_eq!(block, Some(data.clone()));
+            assert_eq!(
+                metrics.histogram_max("express_cache.cold_get.duration_us").is_some(),
+                expect_cold,
+                "{description} get"
+            );
+            assert_eq!(
+                metrics.histogram_max("express_cache.warm_get.duration_us").is_some(),
+                !expect_cold,
+                "{description} get"
+            );
+        }
+    }
+
+    #[test_case(8 * 1024 * 1024, None, false, FillCostEstimate { requests: 10, uncompressed_bytes: 10 * 1024 * 1024, stored_bytes: 10 * 1024 * 1024 }; "single put")]
+    #[cfg_attr(feature = "compression", test_case(8 * 1024 * 1024, Some(BlockCompression::default()), false, FillCostEstimate { requests: 10, uncompressed_bytes: 10 * 1024 * 1024, stored_bytes: 5 * 1024 * 1024 }; "compressed"))]
+    #[test_case(8 * 1024 * 1024, None, true, FillCostEstimate { requests: 30, uncompressed_bytes: 10 * 1024 * 1024, stored_bytes: 10 * 1024 * 1024 }; "with manifest")]
+    #[cfg_attr(feature = "compression", test_case(256 * 1024, Some(BlockCompression::default()), false, FillCostEstimate { requests: 60, uncompressed_bytes: 10 * 1024 * 1024, stored_bytes: 10 * 1024 * 1024 }; "multipart"))]
+    fn test_estimate_fill_cost(
+        part_size: usize,
+        compression: Option<BlockCompression>,
+        object_manifest: bool,
+        expected: FillCostEstimate,
+    ) {
+        let bucket = "test-bucket";
+        let client = MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size,
+            ..Default::default()
+        });
+        let config = ExpressDataCacheConfig {
+            block_size: 1024 * 1024,
+            compression,
+            object_manifest,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        assert_eq!(cache.estimate_fill_cost(10), expected);
+        assert_eq!(cache.estimate_fill_cost(0), FillCostEstimate::default());
+    }
+
+    #[tokio::test]
+    async fn test_block_retention() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_retention: Some(BlockRetention {
+                mode: ObjectLockMode::Governance,
+                period: Duration::from_secs(3600),
+            }),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let written_at = OffsetDateTime::now_utc();
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let retention = client
+            .get_object_lock_retention(&object_key)
+            .unwrap()
+            .expect("block should have a retention");
+        assert_eq!(retention.mode, ObjectLockMode::Governance);
+        assert!(retention.retain_until_date >= written_at + Duration::from_secs(3600));
+
+        let err = cache
+            .invalidate_object(cache_key.key())
+            .await
+            .expect_err("locked block should not be deleted");
+        assert!(
+            format!("{:#}", anyhow::Error::from(err)).contains("Object Lock"),
+            "error should report the Object Lock"
+        );
+        assert!(client.contains_key(&object_key));
+    }
+
+    #[tokio::test]
+    async fn test_suggested_prefetch() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            max_object_size: 16 * 1024,
+            prefetch_hint_blocks: 3,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let other_key = ObjectId::new("b".into(), ETag::for_tests());
+        assert!(cache.suggested_prefetch(&cache_key).is_empty());
+
+        for block_idx in 2..5 {
+            cache
+                .get_block(&cache_key, block_idx, block_idx * 1024, 16 * 1024)
+                .await
+                .expect("get should succeed");
+        }
+        assert_eq!(cache.suggested_prefetch(&cache_key), vec![5, 6, 7]);
+        assert!(cache.suggested_prefetch(&other_key).is_empty());
+
+        // A random access breaks the sequential run.
+        cache
+            .get_block(&cache_key, 9, 9 * 1024, 16 * 1024)
+            .await
+            .expect("get should succeed");
+        assert!(cache.suggested_prefetch(&cache_key).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_suggested_prefetch_disabled() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        for block_idx in 0..3 {
+            cache
+                .get_block(&cache_key, block_idx, block_idx * 1024 * 1024, 1024)
+                .await
+                .expect("get should succeed");
+        }
+        assert!(cache.suggested_prefetch(&cache_key).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_object() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            max_object_size: 16 * 1024,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        for block_idx in [0, 1, 2, 4] {
+            let data = ChecksummedBytes::new(vec![block_idx as u8; 1024].into());
+            cache
+                .put_block(cache_key.clone(), block_idx, block_idx * 1024, data, 16 * 1024)
+                .await
+                .expect("put should succeed");
+        }
+        let other_key = ObjectId::new("b".into(), ETag::for_tests());
+        let data = ChecksummedBytes::new(vec![0u8; 1024].into());
+        cache
+            .put_block(other_key, 0, 0, data, 16 * 1024)
+            .await
+            .expect("put should succeed");
+
+        // Replace the content of block 1, keeping the metadata and checksum of the original.
+        let checksum = crc32c::checksum(&[1u8; 1024]);
+        let block_metadata = BlockMetadata::new(1, 1024, &cache_key, source_bucket, checksum);
+        let mut tampered = MockObject::from_bytes(&[9u8; 1024], ETag::for_tests());
+        tampered.set_object_metadata(block_metadata.to_object_metadata());
+        let mut stored_checksum = Checksum::empty();
+        stored_checksum.checksum_crc32c = Some(crc32c_to_base64(&checksum));
+        tampered.set_checksum(stored_checksum);
+        client.add_object(&get_s3_key(&cache.prefix, &cache_key, 1), tampered);
+
+        let report = cache.verify_object(&cache_key).await.expect("verify should succeed");
+        assert_eq!(
+            report,
+            VerifyReport {
+                verified: 3,
+                mismatched: vec![1],
+                missing: vec![3],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_manifest() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            max_object_size: 16 * 1024,
+            object_manifest: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "source-bucket", bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let report = cache.verify_manifest(&cache_key).await.expect("verify should succeed");
+        assert_eq!(report, None, "no manifest before blocks are written");
+
+        for block_idx in 0..4 {
+            let data = ChecksummedBytes::new(vec![block_idx as u8; 1024].into());
+            cache
+                .put_block(cache_key.clone(), block_idx, block_idx * 1024, data, 16 * 1024)
+                .await
+                .expect("put should succeed");
+        }
+        let report = cache.verify_manifest(&cache_key).await.expect("verify should succeed");
+        assert_eq!(
+            report,
+            Some(VerifyReport {
+                verified: 4,
+                mismatched: vec![],
+                missing: vec![],
+            })
+        );
+
+        // Replace block 1 with different content and remove block 3.
+        client.add_object(
+            &get_s3_key(&cache.prefix, &cache_key, 1),
+            MockObject::from_bytes(&[9u8; 1024], ETag::for_tests()),
+        );
+        client.remove_object(&get_s3_key(&cache.prefix, &cache_key, 3));
+        let get_counter = client.new_counter(Operation::GetObject);
+        let report = cache.verify_manifest(&cache_key).await.expect("verify should succeed");
+        assert_eq!(
+            report,
+            Some(VerifyReport {
+                verified: 2,
+                mismatched: vec![1],
+                missing: vec![3],
+            })
+        );
+        assert_eq!(get_counter.count(), 1, "only the manifest should be downloaded");
+    }
+
+    #[tokio::test]
+    async fn test_short_key_format() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            key_format: KeyFormat::Short,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+        let standard_cache = ExpressDataCache::try_new(client.clone(), Default::default(), source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()));
+
+        let block = standard_cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none(), "blocks should not be shared between key formats");
+
+        let short_key = &cache.debug_keys(&cache_key, 0..1)[0];
+        let standard_key = &standard_cache.debug_keys(&cache_key, 0..1)[0];
+        assert!(client.contains_key(short_key));
+        assert_eq!(short_key.len(), 3 * 32 + 3 + 10);
+        assert_eq!(standard_key.len(), 3 * 64 + 3 + 10);
+    }
+
+    #[test_case(KeyEncoding::LowerHex; "lower hex")]
+    #[test_case(KeyEncoding::UpperHex; "upper hex")]
+    #[test_case(KeyEncoding::Base32; "base32")]
+    #[tokio::test]
+    async fn test_key_encoding(key_encoding: KeyEncoding) {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            key_encoding,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let key = get_s3_key(&build_prefix(source_bucket, cache.config.block_size), &cache_key, 0);
+        let expected_key = match key_encoding {
+            KeyEncoding::LowerHex => key,
+            KeyEncoding::UpperHex => key.to_ascii_uppercase(),
+            KeyEncoding::Base32 => key
+                .split('/')
+                .map(|component| match hex::decode(component) {
+                    Ok(hash) if component.len() == 64 => base32_encode(&hash),
+                    _ => component.to_owned(),
+                })
+                .collect::<Vec<_>>()
+                .join("/"),
+        };
+        let object_key = &cache.debug_keys(&cache_key, 0..1)[0];
+        assert_eq!(object_key, &expected_key);
+
+        let data = ChecksummedBytes::new("Foo".into());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert!(client.contains_key(object_key));
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()));
+
+        for other_encoding in [KeyEncoding::LowerHex, KeyEncoding::UpperHex, KeyEncoding::Base32] {
+            if other_encoding == key_encoding {
+                continue;
+            }
+            let config = ExpressDataCacheConfig {
+                key_encoding: other_encoding,
+                ..Default::default()
+            };
+            let other_cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+                .expect("cache config should be valid");
+            let block = other_cache
+                .get_block(&cache_key, 0, 0, data.len())
+                .await
+                .expect("cache should be accessible");
+            assert!(block.is_none(), "blocks should not be shared between key encodings");
+        }
+    }
+
+    #[test]
+    fn test_base32_encode() {
+        // Test vectors of RFC 4648, in lowercase and without padding
+        for (input, expected) in [
+            ("", ""),
+            ("f", "my"),
+            ("fo", "mzxq"),
+            ("foo", "mzxw6"),
+            ("foob", "mzxw6yq"),
+            ("fooba", "mzxw6ytb"),
+            ("foobar", "mzxw6ytboi"),
+        ] {
+            assert_eq!(base32_encode(input.as_bytes()), expected, "input {input:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenant_id() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let new_cache = |tenant_id: Option<&str>| {
+            let config = ExpressDataCacheConfig {
+                tenant_id: tenant_id.map(str::to_owned),
+                ..Default::default()
+            };
+            ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+                .expect("cache config should be valid")
+        };
+        let tenant_a = new_cache(Some("111111111111"));
+        let tenant_b = new_cache(Some("222222222222"));
+        let no_tenant = new_cache(None);
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let keys: Vec<_> = [&tenant_a, &tenant_b, &no_tenant]
+            .iter()
+            .map(|cache| cache.debug_keys(&cache_key, 0..1).remove(0))
+            .collect();
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(keys[0], keys[2]);
+        assert_ne!(keys[1], keys[2]);
+
+        let data = ChecksummedBytes::new("Foo".into());
+        tenant_a
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let block = tenant_a
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()));
+        for other in [&tenant_b, &no_tenant] {
+            let block = other
+                .get_block(&cache_key, 0, 0, data.len())
+                .await
+                .expect("cache should be accessible");
+            assert!(block.is_none(), "blocks of another tenant should not be read");
+        }
+    }
+
+    #[test]
+    fn test_region_in_prefix() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let new_cache = |cache_region: &str, region_in_prefix| {
+            let config = ExpressDataCacheConfig {
+                region_in_prefix,
+                ..Default::default()
+            };
+            ExpressDataCache::try_new(
+                new_mock_client_in_region(bucket, cache_region),
+                config,
+                source_bucket,
+                bucket,
+            )
+            .expect("cache config should be valid")
+        };
+
+        let west = new_cache("us-west-2", true);
+        let east = new_cache("us-east-1", true);
+        assert_ne!(west.prefix, east.prefix);
+        assert_eq!(west.prefix, new_cache("us-west-2", true).prefix);
+
+        let west = new_cache("us-west-2", false);
+        let east = new_cache("us-east-1", false);
+        assert_eq!(west.prefix, east.prefix);
+        assert_eq!(west.prefix, build_prefix(source_bucket, west.config.block_size));
+
+        // A client without region leaves the prefix unchanged.
+        let config = ExpressDataCacheConfig {
+            region_in_prefix: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(new_mock_client(bucket), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+        assert_eq!(cache.prefix, west.prefix);
+    }
+
+    #[test_case(KeyHashAlgorithm::Sha256, KeyHashAlgorithm::Sha256; "default")]
+    #[test_case(KeyHashAlgorithm::Sha256, KeyHashAlgorithm::Crc64nvme; "fast block keys")]
+    #[test_case(KeyHashAlgorithm::Crc64nvme, KeyHashAlgorithm::Sha256; "fast prefix")]
+    #[tokio::test]
+    async fn test_key_hash_algorithms(
+        prefix_hash_algorithm: KeyHashAlgorithm,
+        block_key_hash_algorithm: KeyHashAlgorithm,
+    ) {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            prefix_hash_algorithm,
+            block_key_hash_algorithm,
+            ..Default::default()
+        };
+        let block_size = config.block_size;
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let hash_len = |algorithm| match algorithm {
+            KeyHashAlgorithm::Sha256 => 64,
+            KeyHashAlgorithm::Crc64nvme => 16,
+        };
+        let expected_prefix = if prefix_hash_algorithm == KeyHashAlgorithm::Sha256 {
+            build_prefix(source_bucket, block_size)
+        } else {
+            build_prefix_for_version(
+                CACHE_VERSION,
+                KeyFormat::Standard,
+                KeyEncoding::LowerHex,
+                prefix_hash_algorithm,
+                None,
+                None,
+                source_bucket,
+                block_size,
+            )
+        };
+        assert_eq!(cache.prefix, expected_prefix);
+        assert_eq!(cache.prefix.len(), hash_len(prefix_hash_algorithm));
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let object_key = block_key(cache.key_hasher(), &cache.prefix, &cache_key, 0);
+        let components: Vec<_> = object_key.split('/').collect();
+        assert_eq!(components.len(), 4);
+        assert_eq!(components[0], cache.prefix);
+        assert_eq!(components[1].len(), hash_len(block_key_hash_algorithm));
+        assert_eq!(components[2].len(), hash_len(block_key_hash_algorithm));
+
+        let data = ChecksummedBytes::new("Foo".into());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert!(client.contains_key(&object_key));
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+    }
+
+    #[tokio::test]
+    async fn test_key_salt() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let new_cache = |key_salt: Option<&str>| {
+            let config = ExpressDataCacheConfig {
+                key_salt: key_salt.map(KeySalt::new),
+                ..Default::default()
+            };
+            ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+                .expect("cache config should be valid")
+        };
+        let salted = new_cache(Some("secret"));
+        let other_salt = new_cache(Some("other secret"));
+        let unsalted = new_cache(None);
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let keys: Vec<_> = [&salted, &other_salt, &unsalted]
+            .iter()
+            .map(|cache| cache.debug_keys(&cache_key, 0..1).remove(0))
+            .collect();
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(keys[0], keys[2]);
+        assert_ne!(keys[1], keys[2]);
+        assert_eq!(
+            keys[2],
+            get_s3_key(&unsalted.prefix, &cache_key, 0),
+            "unsalted keys should not change"
+        );
+
+        let data = ChecksummedBytes::new("Foo".into());
+        salted
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let block = new_cache(Some("secret"))
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()), "block should be read with the same salt");
+        for other in [&other_salt, &unsalted] {
+            let block = other
+                .get_block(&cache_key, 0, 0, data.len())
+                .await
+                .expect("cache should be accessible");
+            assert!(block.is_none(), "block should not be read without the matching salt");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_crc64nvme_checksum() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            checksum_algorithm: BlockChecksumAlgorithm::Crc64nvme,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let params = HeadObjectParams::new().checksum_mode(Some(ChecksumMode::Enabled));
+        let stored_checksum = client
+            .head_object(bucket, &object_key, &params)
+            .await
+            .expect("block should be stored")
+            .checksum;
+        assert_eq!(
+            stored_checksum.checksum_crc64nvme,
+            Some(crc64nvme_to_base64(&crc64nvme::checksum(b"Foo")))
+        );
+        assert_eq!(stored_checksum.checksum_crc32c, None);
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+
+        // Replace the content of the block, keeping its metadata and checksum.
+        let block_metadata = BlockMetadata::new(0, 0, &cache_key, source_bucket, crc32c::checksum(b"Foo"));
+        let mut tampered = MockObject::from_bytes(b"Bar", ETag::for_tests());
+        tampered.set_object_metadata(block_metadata.to_object_metadata());
+        tampered.set_checksum(stored_checksum);
+        client.add_object(&object_key, tampered);
+        let err = cache
+            .get_block(&cache_key, 0, 0, 3)
+            .await
+            .expect_err("tampered block should fail validation");
+        assert!(matches!(err, DataCacheError::InvalidBlockChecksum), "{err:?}");
+    }
+
+    #[cfg(feature = "compression")]
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut encoder = flate2::GzBuilder::new()
+            .filename("block")
+            .write(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[cfg(feature = "compression")]
+    #[test_case(|data| zstd::bulk::compress(data, 3).unwrap(); "zstd")]
+    #[test_case(gzip; "gzip")]
+    #[test_case(|data| data.to_vec(); "raw")]
+    #[tokio::test]
+    async fn test_detect_compression_magic(encode: fn(&[u8]) -> Vec<u8>) {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let new_cache = |detect_compression_magic| {
+            let config = ExpressDataCacheConfig {
+                detect_compression_magic,
+                ..Default::default()
+            };
+            ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+                .expect("cache config should be valid")
+        };
+        let cache = new_cache(true);
+
+        // Write the block as another tool would, without compression metadata.
+        let data = b"Foo".repeat(100);
+        let body = encode(&data);
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let block_metadata = BlockMetadata::new(0, 0, &cache_key, source_bucket, crc32c::checksum(&body));
+        cache
+            .make_put_object_request(
+                block_metadata.to_put_object_params(),
+                &get_s3_key(&cache.prefix, &cache_key, 0),
+                body.clone(),
+            )
+            .await
+            .expect("put should succeed");
+
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible")
+            .expect("block should be cached");
+        assert_eq!(block.into_bytes().unwrap(), Bytes::from(data.clone()));
+
+        let block = new_cache(false)
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible")
+            .expect("block should be cached");
+        assert_eq!(
+            block.into_bytes().unwrap(),
+            Bytes::from(body),
+            "block should be read as is without detection"
+        );
+    }
+
+    #[test_case(None; "uncompressed")]
+    #[cfg_attr(feature = "compression", test_case(Some(BlockCompression::default()); "compressed"))]
+    #[tokio::test]
+    async fn test_get_block_stream(compression: Option<BlockCompression>) {
+        let bucket = "test-bucket";
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 256,
+            enable_backpressure: true,
+            initial_read_window_size: 256,
+            ..Default::default()
+        }));
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            compression: compression.clone(),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "source-bucket", bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+
+        let stream = cache.get_block_stream(&cache_key, 0);
+        pin_mut!(stream);
+        assert!(stream.next().await.is_none(), "missing block should yield no chunks");
+
+        // Write the block with a single PutObject request, which `put_block` would not use with this part size.
+        let data: Vec<u8> = (0..1024).map(|i| (i % 251) as u8).collect();
+        let (body, headers) = match &compression {
+            Some(compression) => compression.compress(&data).expect("compression should succeed"),
+            None => (Bytes::from(data.clone()), HashMap::new()),
+        };
+        let block_metadata = BlockMetadata::new(0, 0, &cache_key, "source-bucket", crc32c::checksum(&body));
+        let mut params = block_metadata.to_put_object_params();
+        params.object_metadata.extend(headers);
+        cache
+            .make_put_object_request(params, &get_s3_key(&cache.prefix, &cache_key, 0), body)
+            .await
+            .expect("put should succeed");
+
+        let stream = cache.get_block_stream(&cache_key, 0);
+        pin_mut!(stream);
+        let mut assembled = Vec::new();
+        let mut chunks = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.expect("block should be valid");
+            assembled.extend_from_slice(&chunk);
+            chunks += 1;
+        }
+        assert_eq!(assembled, data);
+        if compression.is_none() {
+            assert_eq!(chunks, 4, "chunks should be yielded as they are received");
+        } else {
+            assert_eq!(chunks, 1, "compressed blocks are yielded at once");
+        }
+    }
+
+    #[test_case(1024; "trailer in its own chunk")]
+    #[test_case(1022; "trailer across chunks")]
+    #[tokio::test]
+    async fn test_get_block_stream_trailing_checksum(len: usize) {
+        let bucket = "test-bucket";
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 256,
+            enable_backpressure: true,
+            initial_read_window_size: 256,
+            ..Default::default()
+        }));
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            checksum_location: ChecksumLocation::TrailingBytes,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "source-bucket", bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+
+        let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        // The checksum of other data is stored for the corrupted block.
+        let mut other_data = data.clone();
+        other_data[0] ^= 1;
+        for (checksum, valid) in [(crc32c::checksum(&data), true), (crc32c::checksum(&other_data), false)] {
+            let block_metadata = BlockMetadata::new(0, 0, &cache_key, "source-bucket", checksum);
+            let mut params = block_metadata.to_put_object_params().checksum(None);
+            params
+                .object_metadata
+                .insert(CHECKSUM_LOCATION_HEADER.to_string(), TRAILING.to_string());
+            let mut body = data.clone();
+            body.extend_from_slice(&checksum.value().to_be_bytes());
+            cache
+                .make_put_object_request(params, &object_key, body)
+                .await
+                .expect("put should succeed");
+
+            let mut items: Vec<_> = cache.get_block_stream(&cache_key, 0).collect().await;
+            if valid {
+                let mut assembled = Vec::new();
+                for chunk in items {
+                    assembled.extend_from_slice(&chunk.expect("block should be valid"));
+                }
+                assert_eq!(assembled, data, "trailing checksum should not be yielded");
+            } else {
+                assert!(
+                    matches!(items.pop(), Some(Err(DataCacheError::InvalidBlockChecksum))),
+                    "stream should end with the validation error"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_hold_back_trailer() {
+        let mut trailer = Bytes::new();
+        let mut released = Vec::new();
+        for chunk in [&b"ab"[..], b"cdefg", b"h", b"", b"ijk"] {
+            for data in hold_back_trailer(&mut trailer, Bytes::copy_from_slice(chunk)) {
+                released.extend_from_slice(&data);
+            }
+        }
+        assert_eq!(released, b"abcdefg");
+        assert_eq!(&trailer[..], b"hijk");
+    }
+
+    #[tokio::test]
+    async fn test_get_block_stream_invalid_block() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "source-bucket", bucket)
+            .expect("cache config should be valid");
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, ChecksummedBytes::new("Foo".into()), 3)
+            .await
+            .expect("put should succeed");
+        // Replace the block with one that has the metadata of another object.
+        let other_key = ObjectId::new("b".into(), ETag::for_tests());
+        let checksum = crc32c::checksum(b"Bar");
+        let block_metadata = BlockMetadata::new(0, 0, &other_key, "source-bucket", checksum);
+        let mut tampered = MockObject::from_bytes(b"Bar", ETag::for_tests());
+        tampered.set_object_metadata(block_metadata.to_object_metadata());
+        let mut stored_checksum = Checksum::empty();
+        stored_checksum.checksum_crc32c = Some(crc32c_to_base64(&checksum));
+        tampered.set_checksum(stored_checksum);
+        client.add_object(&get_s3_key(&cache.prefix, &cache_key, 0), tampered);
+
+        let items: Vec<_> = cache.get_block_stream(&cache_key, 0).collect().await;
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0].as_ref().expect("data should be yielded"),
+            &Bytes::from_static(b"Bar")
+        );
+        assert!(
+            matches!(items[1], Err(DataCacheError::InvalidBlockHeader(_))),
+            "stream should end with the validation error"
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compression_level() {
+        let data: Vec<u8> = (0..4096u64)
+            .flat_map(|i| format!("block {} offset {}\n", i % 100, (i * 7919) % 1000).into_bytes())
+            .collect();
+        let compress = |level| {
+            let compression = BlockCompression {
+                dictionary: None,
+                level,
+                ..Default::default()
+            };
+            let (compressed, headers) = compression.compress(&data).expect("compression should succeed");
+            // Decompression does not depend on the level.
+            let decompressed = decompress_block(&headers, &compressed, None, data.len())
+                .expect("decompression should succeed")
+                .expect("block should be compressed");
+            assert_eq!(decompressed[..], data[..]);
+            compressed.len()
+        };
+        assert!(compress(19) < compress(1));
+    }
+
+    #[test]
+    fn test_debug_keys() {
+        let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client, Default::default(), source_bucket, bucket)
+            .expect("cache config should be valid");
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let prefix = build_prefix(source_bucket, ExpressDataCacheConfig::default().block_size);
+        let expected: Vec<_> = (3..7)
+            .map(|block_idx| get_s3_key(&prefix, &cache_key, block_idx))
+            .collect();
+        assert_eq!(cache.debug_keys(&cache_key, 3..7), expected);
+        assert!(cache.debug_keys(&cache_key, 0..0).is_empty());
+    }
+
+    /// Hook tracking the maximum number of concurrent DeleteObjects requests, and failing the
+    /// batches containing `failing_key`.
+    #[derive(Default)]
+    struct DeleteTracking {
+        failing_key: Option<String>,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl RequestHook<MockClientError> for DeleteTracking {
+        async fn before(&self, request: &HookedRequest<'_>) -> Result<(), MockClientError> {
+            let HookedRequest::DeleteObjects { keys, .. } = request else {
+                return Ok(());
+            };
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            if keys.iter().any(|key| self.failing_key.as_ref() == Some(key)) {
+                return Err(MockClientError("delete failed".into()));
+            }
+            Ok(())
+        }
+    }
+
+    /// Hook holding each GetObject request until a message is received on `gate`.
+    struct GatedGet {
+        gate: async_channel::Receiver<()>,
+    }
+
+    #[async_trait]
+    impl RequestHook<MockClientError> for GatedGet {
+        async fn before(&self, request: &HookedRequest<'_>) -> Result<(), MockClientError> {
+            if let HookedRequest::GetObject { .. } = request {
+                self.gate.recv().await.expect("gate should not be closed");
+            }
+            Ok(())
+        }
+    }
+
+    /// Hook recording the parameters of each GetObject and single PutObject request.
+    #[derive(Default)]
+    struct ParamsRecording {
+        get_params: Mutex<Vec<GetObjectParams>>,
+        put_single_params: Mutex<Vec<PutObjectSingleParams>>,
+    }
+
+    #[async_trait]
+    impl RequestHook<MockClientError> for ParamsRecording {
+        async fn before(&self, request: &HookedRequest<'_>) -> Result<(), MockClientError> {
+            match request {
+                HookedRequest::GetObject { params, .. } => self.get_params.lock().unwrap().push((*params).clone()),
+                HookedRequest::PutObjectSingle { params, .. } => {
+                    self.put_single_params.lock().unwrap().push((*params).clone())
+                }
+                _ => {}
+            }
+            Ok(())
+        }
+    }
+
+    /// Hook tracking the maximum number of concurrent single PutObject requests.
+    #[derive(Default)]
+    struct PutTracking {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl RequestHook<MockClientError> for PutTracking {
+        async fn before(&self, request: &HookedRequest<'_>) -> Result<(), MockClientError> {
+            if let HookedRequest::PutObjectSingle { .. } = request {
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inflight() {
+        let bucket = "test-bucket";
+        let (gate_sender, gate) = async_channel::unbounded();
+        let client = HookClient::new(new_mock_client(bucket), GatedGet { gate });
+        let cache = Arc::new(
+            ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+                .expect("cache config should be valid"),
+        );
+        assert_eq!(cache.inflight(), InflightCounts::default());
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(cache.inflight().puts, 0);
+
+        let get = tokio::spawn({
+            let cache = cache.clone();
+            let cache_key = cache_key.clone();
+            let len = data.len();
+            async move { cache.get_block(&cache_key, 0, 0, len).await }
+        });
+        while cache.inflight().gets == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(cache.inflight(), InflightCounts { gets: 1, puts: 0 });
+
+        gate_sender.send(()).await.unwrap();
+        let block = get.await.unwrap().expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+        assert_eq!(cache.inflight(), InflightCounts::default());
+    }
+
+    #[test]
+    fn test_cancelled_operations_do_not_leak() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_size: 2048,
+            // Write blocks with multipart uploads, which must not be left in progress.
+            mpu_threshold_bytes: Some(1000),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(
+            HookClient::new(client.clone(), YieldBeforeRequest),
+            config,
+            "unique source description",
+            bucket,
+        )
+        .expect("cache config should be valid");
+        let data = ChecksummedBytes::new(vec![7u8; 2048].into());
+        let cache_key = |iteration: usize| ObjectId::new(format!("object-{iteration}"), ETag::for_tests());
+
+        run_with_cancellation(
+            0x5eed,
+            200,
+            8,
+            |iteration| cache.put_block(cache_key(iteration), 0, 0, data.clone(), data.len()),
+            |iteration, completed| {
+                let object_key = get_s3_key(&cache.prefix, &cache_key(iteration), 0);
+                assert!(
+                    !client.is_upload_in_progress(&object_key),
+                    "cancelled put should not leave an upload in progress"
+                );
+                assert_eq!(client.contains_key(&object_key), completed);
+                assert_eq!(cache.inflight(), InflightCounts::default());
+            },
+        );
+
+        let written = ObjectId::new("written".into(), ETag::for_tests());
+        futures::executor::block_on(cache.put_block(written.clone(), 0, 0, data.clone(), data.len()))
+            .expect("put should succeed");
+        run_with_cancellation(
+            0x5eed,
+            200,
+            8,
+            |_| cache.get_block(&written, 0, 0, data.len()),
+            |_, _| {
+                assert_eq!(cache.inflight(), InflightCounts::default());
+                assert_eq!(
+                    cache.inflight_get_bytes(),
+                    0,
+                    "cancelled get should release its buffers"
+                );
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn test_max_inflight_get_bytes() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            max_inflight_get_bytes: Some(1024),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new(Bytes::from(vec![7u8; 1000]));
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed");
+        assert_eq!(block, Some(data.clone()));
+        assert_eq!(
+            cache.inflight_get_bytes(),
+            0,
+            "buffers should be released once the get completes"
+        );
+
+        // Hold buffers of in-flight gets past the ceiling.
+        let get_counter = client.new_counter(Operation::GetObject);
+        let mut buffered = BufferedBytesGuard::new(&cache.inflight_get_bytes);
+        buffered.add(1024);
+        assert_eq!(cache.inflight_get_bytes(), 1024);
+        let blocks = futures::future::join_all((0..4).map(|_| cache.get_block(&cache_key, 0, 0, data.len()))).await;
+        for block in blocks {
+            assert!(
+                block.expect("get should succeed").is_none(),
+                "get above the ceiling should miss"
+            );
+        }
+        assert_eq!(
+            get_counter.count(),
+            0,
+            "gets above the ceiling should not reach the bucket"
+        );
+
+        drop(buffered);
+        assert_eq!(cache.inflight_get_bytes(), 0);
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed");
+        assert_eq!(block, Some(data));
+        assert_eq!(get_counter.count(), 1);
+    }
+
+    #[test_case(None; "client default")]
+    #[test_case(Some(4 * 1024 * 1024); "configured")]
+    #[tokio::test]
+    async fn test_initial_read_window(initial_read_window: Option<usize>) {
+        let bucket = "test-bucket";
+        let client = HookClient::new(new_mock_client(bucket), ParamsRecording::default());
+        let config = ExpressDataCacheConfig {
+            initial_read_window,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed");
+        assert_eq!(block, Some(data));
+
+        let get_params = cache.client.hook.get_params.lock().unwrap();
+        assert_eq!(get_params.len(), 1);
+        assert_eq!(get_params[0].initial_read_window, initial_read_window);
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id() {
+        let bucket = "test-bucket";
+        let header = "x-correlation-id";
+        let client = HookClient::new(new_mock_client(bucket), ParamsRecording::default());
+        let config = ExpressDataCacheConfig {
+            correlation_id_header: Some(header.to_string()),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        with_correlation_id("request-1", async {
+            cache
+                .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+                .await
+                .expect("put should succeed");
+            let block = cache
+                .get_block(&cache_key, 0, 0, data.len())
+                .await
+                .expect("get should succeed");
+            assert_eq!(block, Some(data.clone()));
+        })
+        .await;
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed");
+        assert_eq!(block, Some(data));
+
+        let expected = vec![(header.to_string(), "request-1".to_string())];
+        let put_params = cache.client.hook.put_single_params.lock().unwrap();
+        assert_eq!(put_params.len(), 1);
+        assert_eq!(put_params[0].custom_headers, expected);
+        let get_params = cache.client.hook.get_params.lock().unwrap();
+        assert_eq!(get_params.len(), 2);
+        assert_eq!(get_params[0].custom_headers, expected);
+        assert!(
+            get_params[1].custom_headers.is_empty(),
+            "requests outside of the scope should have no correlation ID"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id_read_through() {
+        let bucket = "test-bucket";
+        let origin_bucket = "origin-bucket";
+        let header = "x-correlation-id";
+        let origin_client = new_mock_client(origin_bucket);
+        origin_client.add_object("a", MockObject::from_bytes(b"Foo", ETag::for_tests()));
+        let client = Arc::new(
+            HookClient::new(new_mock_client(bucket), ParamsRecording::default())
+                .with_bucket_client(origin_bucket, origin_client),
+        );
+        let config = ExpressDataCacheConfig {
+            correlation_id_header: Some(header.to_string()),
+            ..Default::default()
+        };
+        let origin = OriginSource {
+            client: client.clone(),
+            bucket: origin_bucket.to_owned(),
+        };
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let cache = ExpressDataCache::try_new(client.clone(), config, origin_bucket, bucket)
+            .expect("cache config should be valid")
+            .with_origin(origin, runtime);
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let block = with_correlation_id("request-1", cache.get_block_read_through(&cache_key, 0, 0, 3))
+            .await
+            .expect("block should be read from origin");
+        assert!(block.is_some());
+
+        // The block is written to the cache in a spawned task, which keeps the correlation ID.
+        let mut retries = 50;
+        while client.hook.put_single_params.lock().unwrap().is_empty() && retries > 0 {
+            async_io::Timer::after(Duration::from_millis(20)).await;
+            retries -= 1;
+        }
+        let expected = vec![(header.to_string(), "request-1".to_string())];
+        let put_params = client.hook.put_single_params.lock().unwrap();
+        assert!(!put_params.is_empty(), "block should be written to the cache");
+        assert!(put_params.iter().all(|params| params.custom_headers == expected));
+    }
+
+    #[tokio::test]
+    async fn test_acl() {
+        let bucket = "test-bucket";
+        let client = HookClient::new(new_mock_client(bucket), ParamsRecording::default());
+        let config = ExpressDataCacheConfig {
+            acl: Some("bucket-owner-full-control".to_string()),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        let put_params = cache.client.hook.put_single_params.lock().unwrap();
+        assert_eq!(put_params.len(), 1);
+        assert_eq!(put_params[0].acl.as_deref(), Some("bucket-owner-full-control"));
+    }
+
+    #[tokio::test]
+    async fn test_key_too_long() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let mut cache =
+            ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+                .expect("cache config should be valid");
+        cache.prefix = "prefix/".repeat(150);
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let key_len = get_s3_key(&cache.prefix, &cache_key, 0).len();
+        assert!(key_len > MAX_KEY_LEN);
+        let err = cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect_err("put with a key above the S3 limit should fail");
+        match err {
+            DataCacheError::InvalidCacheConfig(message) => {
+                assert!(message.contains(&key_len.to_string()), "unexpected message: {message}")
+            }
+            err => panic!("unexpected error: {err:?}"),
+        }
+        assert_eq!(client.object_count(), 0);
+    }
+
+    #[test_case(None, RegionMismatchPolicy::Error, true; "same region")]
+    #[test_case(Some("us-east-1"), RegionMismatchPolicy::Warn, true; "mismatch with warning")]
+    #[test_case(Some("us-east-1"), RegionMismatchPolicy::Error, false; "mismatch with error")]
+    fn test_region_mismatch_policy(
+        source_region: Option<&str>,
+        region_mismatch_policy: RegionMismatchPolicy,
+        expect_ok: bool,
+    ) {
+        let bucket = "test-bucket";
+        let config = ExpressDataCacheConfig {
+            region_mismatch_policy,
+            ..Default::default()
+        };
+        let cache_client = new_mock_client_in_region(bucket, "us-west-2");
+        let source_client = new_mock_client_in_region("source-bucket", source_region.unwrap_or("us-west-2"));
+        let expect_mismatch = source_region.is_some();
+        let (result, metrics) = with_captured_metrics(|| {
+            ExpressDataCache::try_new_with_source_client(
+                cache_client,
+                &source_client,
+                config,
+                "unique source description",
+                bucket,
+            )
+        });
+        assert_eq!(
+            metrics.counter("express_data_cache.region_mismatch"),
+            expect_mismatch as u64
+        );
+        match result {
+            Ok(_) => assert!(expect_ok, "construction should fail"),
+            Err(DataCacheError::InvalidCacheConfig(message)) => {
+                assert!(!expect_ok, "construction should succeed");
+                assert!(message.contains("us-east-1"), "unexpected message: {message}");
+            }
+            Err(err) => panic!("unexpected error: {err:?}"),
+        }
+    }
+
+    #[test_case("cache--usw2-az1--x-s3", BucketNamePolicy::Error, true; "directory bucket")]
+    #[test_case("test-bucket", BucketNamePolicy::Ignore, true; "general purpose bucket ignored")]
+    #[test_case("test-bucket", BucketNamePolicy::Warn, true; "general purpose bucket with warning")]
+    #[test_case("test-bucket", BucketNamePolicy::Error, false; "general purpose bucket with error")]
+    fn test_bucket_name_policy(bucket: &str, bucket_name_policy: BucketNamePolicy, expect_ok: bool) {
+        let config = ExpressDataCacheConfig {
+            bucket_name_policy,
+            ..Default::default()
+        };
+        let result = ExpressDataCache::try_new(new_mock_client(bucket), config, "unique source description", bucket);
+        match result {
+            Ok(_) => assert!(expect_ok, "construction should fail"),
+            Err(DataCacheError::InvalidCacheConfig(message)) => {
+                assert!(!expect_ok, "construction should succeed");
+                assert!(message.contains(bucket), "unexpected message: {message}");
+            }
+            Err(err) => panic!("unexpected error: {err:?}"),
+        }
+    }
+
+    #[test_case("cache--usw2-az1--x-s3", true; "availability zone")]
+    #[test_case("my-cache--usw2-lax1-az1--x-s3", true; "local zone")]
+    #[test_case("test-bucket", false; "general purpose bucket")]
+    #[test_case("cache--x-s3", false; "missing zone id")]
+    #[test_case("cache--usw2--x-s3", false; "missing availability zone")]
+    #[test_case("--usw2-az1--x-s3", false; "missing base name")]
+    #[test_case("Cache--usw2-az1--x-s3", false; "uppercase")]
+    fn test_is_directory_bucket_name(bucket_name: &str, expected: bool) {
+        assert_eq!(is_directory_bucket_name(bucket_name), expected);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_put_concurrency() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let mut put_single_failures = HashMap::new();
+        for attempt in 1..=2 {
+            put_single_failures.insert(attempt, MockClientError("SlowDown".to_owned().into()).into());
+        }
+        let failure_client = countdown_failure_client(
+            client,
+            CountdownFailureConfig {
+                put_single_failures,
+                ..Default::default()
+            },
+        );
+        let config = ExpressDataCacheConfig {
+            adaptive_put_concurrency: Some(AdaptiveConcurrency {
+                initial_limit: 8,
+                min_limit: 1,
+                max_limit: 8,
+                decrease_factor: 0.5,
+            }),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(failure_client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        assert_eq!(cache.put_concurrency_limit(), Some(8));
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let mut results = Vec::new();
+        for idx in 0..100 {
+            let cache_key = ObjectId::new(format!("key-{idx}"), ETag::for_tests());
+            let result = cache.put_block(cache_key, 0, 0, data.clone(), data.len()).await;
+            results.push((result.is_ok(), cache.put_concurrency_limit()));
+        }
+        assert_eq!(results[0], (false, Some(4)));
+        assert_eq!(results[1], (false, Some(2)));
+        // The limit increases by one once as many puts as the limit succeeded.
+        assert_eq!(results[2], (true, Some(2)));
+        assert_eq!(results[3], (true, Some(3)));
+        assert!(
+            results[2..].iter().all(|(ok, _)| *ok),
+            "puts should succeed once errors stop"
+        );
+        assert_eq!(
+            cache.put_concurrency_limit(),
+            Some(8),
+            "limit should recover up to the maximum"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_block_with_priority() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            max_concurrent_gets: Some(1),
+            ..Default::default()
+        };
+        // Yielding before each request keeps the first lookup in progress while the others are queued.
+        let cache = ExpressDataCache::try_new(
+            HookClient::new(client.clone(), YieldBeforeRequest),
+            config,
+            "unique source description",
+            bucket,
+        )
+        .expect("cache config should be valid");
+        let data = ChecksummedBytes::new("Foo".into());
+        let keys: Vec<_> = (0..4)
+            .map(|i| ObjectId::new(format!("key{i}"), ETag::for_tests()))
+            .collect();
+        for key in &keys {
+            cache
+                .put_block(key.clone(), 0, 0, data.clone(), data.len())
+                .await
+                .expect("put should succeed");
+        }
+
+        let served = Mutex::new(Vec::new());
+        let get = |name: &'static str, key: &ObjectId, priority: i32| {
+            let cache = &cache;
+            let served = &served;
+            let key = key.clone();
+            let data = &data;
+            async move {
+                let block = cache
+                    .get_block_with_priority(&key, 0, 0, data.len(), priority)
+                    .await
+                    .expect("get should succeed");
+                assert_eq!(block.as_ref(), Some(data));
+                served.lock().unwrap().push(name);
+            }
+        };
+        tokio::join!(
+            get("first", &keys[0], DEFAULT_GET_PRIORITY),
+            get("low 1", &keys[1], -1),
+            get("low 2", &keys[2], -1),
+            get("high", &keys[3], 1),
+        );
+        assert_eq!(*served.lock().unwrap(), ["first", "high", "low 1", "low 2"]);
+    }
+
+    #[test]
+    fn test_max_block_size() {
+        let bucket = "test-bucket";
+        let config = ExpressDataCacheConfig {
+            block_size: MAX_OBJECT_SIZE,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(new_mock_client(bucket), config, "unique source description", bucket)
+            .expect("maximum block size should be accepted");
+        assert_eq!(cache.max_block_size(), MAX_OBJECT_SIZE);
+
+        let config = ExpressDataCacheConfig {
+            block_size: MAX_OBJECT_SIZE + 1,
+            ..Default::default()
+        };
+        let result = ExpressDataCache::try_new(new_mock_client(bucket), config, "unique source description", bucket);
+        assert!(matches!(result, Err(DataCacheError::InvalidCacheConfig(_))));
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test_case(Some(BlockCompression::default()), false; "compression")]
+    #[test_case(None, true; "compression magic")]
+    fn test_compression_requires_feature(compression: Option<BlockCompression>, detect_compression_magic: bool) {
+        let bucket = "test-bucket";
+        let config = ExpressDataCacheConfig {
+            compression,
+            detect_compression_magic,
+            ..Default::default()
+        };
+        let result = ExpressDataCache::try_new(new_mock_client(bucket), config, "unique source description", bucket);
+        assert!(matches!(result, Err(DataCacheError::InvalidCacheConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_deduplicate_blocks() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let put_counter = client.new_counter(Operation::PutObjectSingle);
+        let config = ExpressDataCacheConfig {
+            deduplicate_blocks: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let shared = ChecksummedBytes::new("Foo".into());
+        let other = ChecksummedBytes::new("Bar".into());
+        let key_1 = ObjectId::new("a".into(), ETag::for_tests());
+        let key_2 = ObjectId::new("b".into(), ETag::for_tests());
+        cache
+            .put_block(key_1.clone(), 0, 0, shared.clone(), shared.len())
+            .await
+            .expect("put should succeed");
+        // The content and the reference of the block.
+        assert_eq!(client.object_count(), 2);
+        assert_eq!(put_counter.count(), 2);
+
+        cache
+            .put_block(key_2.clone(), 0, 0, shared.clone(), shared.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(client.object_count(), 3, "only a reference should be added");
+        assert_eq!(put_counter.count(), 3);
+        cache
+            .put_block(key_2.clone(), 1, 1024 * 1024, other.clone(), 1024 * 1024 + other.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(client.object_count(), 5);
+
+        for key in [&key_1, &key_2] {
+            let block = cache
+                .get_block(key, 0, 0, shared.len())
+                .await
+                .expect("get should succeed");
+            assert_eq!(block, Some(shared.clone()));
+        }
+        let block = cache
+            .get_block(&key_2, 1, 1024 * 1024, 1024 * 1024 + other.len())
+            .await
+            .expect("get should succeed");
+        assert_eq!(block, Some(other));
+        let block = cache
+            .get_block(&key_1, 1, 1024 * 1024, 1024 * 1024 + shared.len())
+            .await
+            .expect("get should succeed");
+        assert!(block.is_none(), "block never written should miss");
+    }
+
+    #[tokio::test]
+    async fn test_deduplicated_content() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            deduplicate_blocks: true,
+            key_salt: Some(KeySalt::new("secret")),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let salted_key = content_key(cache.key_hasher(), &cache.prefix, b"Foo");
+        assert!(
+            client.contains_key(&salted_key),
+            "content should be stored under the salted key"
+        );
+        assert!(!client.contains_key(&content_key(KeyHasher::STANDARD, &cache.prefix, b"Foo")));
+
+        // A reference to content larger than a block is not downloaded.
+        let reference = BlockReference {
+            source_key: "a".to_string(),
+            etag: cache_key.etag().as_str().to_string(),
+            content_key: salted_key,
+            len: 1 << 40,
+            crc32c: 0,
+        };
+        client.add_object(
+            &block_reference_key(cache.key_hasher(), &cache.prefix, &cache_key, 0),
+            MockObject::from_bytes(&serde_json::to_vec(&reference).unwrap(), ETag::for_tests()),
+        );
+        let err = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect_err("oversized content should fail");
+        assert!(matches!(err, DataCacheError::BlockTooLarge(1024)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let object_count = client.object_count();
+
+        let put_single_counter = client.new_counter(Operation::PutObjectSingle);
+        let put_mpu_counter = client.new_counter(Operation::PutObject);
+        let get_counter = client.new_counter(Operation::GetObject);
+        let config = ExpressDataCacheConfig {
+            read_only: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        let other_key = ObjectId::new("b".into(), ETag::for_tests());
+        cache
+            .put_block(other_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed in read-only mode");
+        assert_eq!(put_single_counter.count(), 0);
+        assert_eq!(put_mpu_counter.count(), 0);
+        assert_eq!(client.object_count(), object_count);
+
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed");
+        assert_eq!(block, Some(data.clone()));
+        let block = cache
+            .get_block(&other_key, 0, 0, data.len())
+            .await
+            .expect("get should succeed");
+        assert!(block.is_none(), "skipped write should not be cached");
+        assert_eq!(get_counter.count(), 2);
+    }
+
+    #[test_case(false; "lenient")]
+    #[test_case(true; "strict")]
+    #[tokio::test]
+    async fn test_block_deleted_during_read(error_on_deleted_during_read: bool) {
+        let bucket = "test-bucket";
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 256,
+            enable_backpressure: true,
+            initial_read_window_size: 256,
+            ..Default::default()
+        }));
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            error_on_deleted_during_read,
+            ..Default::default()
         };
-        metrics::histogram!("express_data_cache.write_duration_us", "type" => result_type)
-            .record(start.elapsed().as_micros() as f64);
-        result
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new(vec![0u8; 1024].into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(client.object_count(), 1);
+
+        client.remove_object_during_read(&get_s3_key(&cache.prefix, &cache_key, 0), 1);
+        let result = cache.get_block(&cache_key, 0, 0, data.len()).await;
+        assert_eq!(client.object_count(), 0, "block should be deleted during the read");
+        if error_on_deleted_during_read {
+            assert!(matches!(result, Err(DataCacheError::IoFailure(_))), "{result:?}");
+        } else {
+            assert!(matches!(result, Ok(None)), "{result:?}");
+        }
     }
 
-    fn block_size(&self) -> u64 {
-        self.config.block_size
+    async fn put_blocks(cache: &ExpressDataCache<impl ObjectClient + Send + Sync + 'static>, count: usize) {
+        let data = ChecksummedBytes::new("Foo".into());
+        for i in 0..count {
+            let cache_key = ObjectId::new(format!("key{i}"), ETag::for_tests());
+            cache
+                .put_block(cache_key, 0, 0, data.clone(), data.len())
+                .await
+                .expect("put should succeed");
+        }
     }
-}
 
-/// Metadata about the cached object to ensure that the object we've retrieved is the one we were
-/// wanting to get (and avoid collisions with the key).
-/// On miss, bypass the cache and go to the main data source.
-#[cfg_attr(test, derive(proptest_derive::Arbitrary))]
-#[derive(Clone, Debug, PartialEq, Eq)]
-struct BlockMetadata {
-    block_idx: BlockIndex,
-    block_offset: u64,
-    etag: String,
-    source_key: String,
-    source_bucket_name: String,
-    data_checksum: u32,
-    header_checksum: u32,
-}
+    #[tokio::test]
+    async fn test_conformance() {
+        let bucket = "test-bucket";
+        let client = MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 512,
+            enable_backpressure: true,
+            initial_read_window_size: 512,
+            ..Default::default()
+        });
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            max_object_size: 2048,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        run_data_cache_conformance(&cache).await;
+    }
 
-impl BlockMetadata {
-    pub fn new(
-        block_idx: BlockIndex,
-        block_offset: u64,
-        cache_key: &ObjectId,
-        source_bucket_name: &str,
-        data_checksum: Crc32c,
-    ) -> Self {
-        let header_checksum =
-            Self::get_header_checksum(block_idx, block_offset, cache_key, source_bucket_name, data_checksum).value();
-        Self {
-            block_idx,
-            block_offset,
-            etag: cache_key.etag().as_str().to_string(),
-            source_key: cache_key.key().to_string(),
-            source_bucket_name: source_bucket_name.to_string(),
-            data_checksum: data_checksum.value(),
-            header_checksum,
-        }
+    #[tokio::test]
+    async fn test_clear_bounded_concurrency() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            delete_concurrency: 4,
+            ..Default::default()
+        };
+        let tracking_client = Arc::new(HookClient::new(client.clone(), DeleteTracking::default()));
+        let cache = ExpressDataCache::try_new(tracking_client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        cache.verify_cache_valid().await.expect("cache should be valid");
+        put_blocks(&cache, 2500).await;
+        assert_eq!(client.object_count(), 2501);
+        let delete_objects_counter = client.new_counter(Operation::DeleteObjects);
+        let delete_object_counter = client.new_counter(Operation::DeleteObject);
+
+        let deleted = cache.clear().await.expect("clear should succeed");
+        assert_eq!(deleted, 2500);
+        assert_eq!(client.object_count(), 1, "only the cache metadata should remain");
+        assert_eq!(
+            delete_objects_counter.count(),
+            3,
+            "blocks should be deleted in three batches"
+        );
+        assert_eq!(delete_object_counter.count(), 0);
+        let max_in_flight = tracking_client.hook.max_in_flight.load(Ordering::SeqCst);
+        assert!(
+            max_in_flight <= 4,
+            "{max_in_flight} concurrent deletes exceed the limit"
+        );
     }
 
-    /// Build parameters to be used when running a PutObject for this block
-    pub fn to_put_object_params(&self) -> PutObjectSingleParams {
-        // Convert to object metadata that is HTTP header safe (ASCII only)
-        let source_key_encoded = Base64::encode_string(self.source_key.as_bytes());
-        let object_metadata = HashMap::from([
-            ("cache-version".to_string(), CACHE_VERSION.to_string()),
-            ("block-idx".to_string(), format!("{}", self.block_idx)),
-            ("block-offset".to_string(), format!("{}", self.block_offset)),
-            ("etag".to_string(), self.etag.clone()),
-            ("source-key".to_string(), source_key_encoded),
-            ("source-bucket-name".to_string(), self.source_bucket_name.clone()),
-            ("header-checksum".to_string(), format!("{}", self.header_checksum)),
-        ]);
+    #[tokio::test]
+    async fn test_clear_continues_after_failed_delete() {
+        let bucket = "test-bucket";
+        let source_description = "unique source description";
+        let client = new_mock_client(bucket);
+        let prefix = build_prefix(source_description, ExpressDataCacheConfig::default().block_size);
+        let locked_key = get_s3_key(&prefix, &ObjectId::new("key3".into(), ETag::for_tests()), 0);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), source_description, bucket)
+            .expect("cache config should be valid");
+        put_blocks(&cache, 10).await;
+        let mut locked_object = MockObject::constant(1, 1, ETag::for_tests());
+        locked_object.set_object_lock_retention(Some(ObjectLockRetention {
+            mode: ObjectLockMode::Compliance,
+            retain_until_date: OffsetDateTime::now_utc() + Duration::from_secs(3600),
+        }));
+        client.add_object(&locked_key, locked_object);
 
-        PutObjectSingleParams::new()
-            .object_metadata(object_metadata)
-            .checksum(Some(UploadChecksum::Crc32c(Crc32c::new(self.data_checksum))))
+        let err = cache.clear().await.expect_err("clear should report the failed delete");
+        let DataCacheError::IoFailure(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert!(
+            format!("{err:#}").contains("failed to delete 1 blocks, deleted 9"),
+            "unexpected error: {err:#}"
+        );
+        assert_eq!(client.object_count(), 1);
+        assert!(client.contains_key(&locked_key));
     }
 
-    /// Validate the object metadata headers received match this BlockMetadata object.
-    pub fn validate_object_metadata(&self, headers: &HashMap<String, String>) -> DataCacheResult<()> {
-        self.validate_header(headers, "cache-version", |version| version == CACHE_VERSION)?;
-        self.validate_header(headers, "block-idx", |block_idx| {
-            block_idx.parse() == Ok(self.block_idx)
-        })?;
-        self.validate_header(headers, "block-offset", |block_offset| {
-            block_offset.parse() == Ok(self.block_offset)
-        })?;
-        self.validate_header(headers, "etag", |etag| etag == self.etag)?;
-        self.validate_header(headers, "source-key", |source_key| {
-            source_key == Base64::encode_string(self.source_key.as_bytes())
-        })?;
-        self.validate_header(headers, "source-bucket-name", |source_bucket_name| {
-            source_bucket_name == self.source_bucket_name
-        })?;
-        self.validate_header(headers, "header-checksum", |header_checksum| {
-            header_checksum.parse() == Ok(self.header_checksum)
-        })?;
+    #[tokio::test]
+    async fn test_clear_continues_after_failed_batch() {
+        let bucket = "test-bucket";
+        let source_description = "unique source description";
+        let client = new_mock_client(bucket);
+        let prefix = build_prefix(source_description, ExpressDataCacheConfig::default().block_size);
+        let failing_key = get_s3_key(&prefix, &ObjectId::new("key3".into(), ETag::for_tests()), 0);
+        let hook = DeleteTracking {
+            failing_key: Some(failing_key.clone()),
+            ..Default::default()
+        };
+        let tracking_client = Arc::new(HookClient::new(client.clone(), hook));
+        let cache = ExpressDataCache::try_new(tracking_client, Default::default(), source_description, bucket)
+            .expect("cache config should be valid");
+        put_blocks(&cache, DELETE_BATCH_SIZE + 10).await;
 
-        Ok(())
+        let err = cache.clear().await.expect_err("clear should report the failed batch");
+        let DataCacheError::IoFailure(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        // Only the blocks of the failed batch remain.
+        let remaining = client.object_count();
+        assert!(remaining < DELETE_BATCH_SIZE + 10, "other batches should be deleted");
+        assert!(client.contains_key(&failing_key));
+        let expected = format!(
+            "failed to delete {remaining} blocks, deleted {}",
+            DELETE_BATCH_SIZE + 10 - remaining
+        );
+        assert!(format!("{err:#}").contains(&expected), "unexpected error: {err:#}");
     }
 
-    fn validate_header<F: Fn(&str) -> bool>(
-        &self,
-        headers: &HashMap<String, String>,
-        header: &str,
-        is_valid: F,
-    ) -> DataCacheResult<()> {
-        let value = headers
-            .get(header)
-            .ok_or(DataCacheError::InvalidBlockHeader(header.to_string()))?;
-        is_valid(value)
-            .then_some(())
-            .ok_or(DataCacheError::InvalidBlockHeader(header.to_string()))
+    #[tokio::test]
+    async fn test_invalidate_object() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key_1 = ObjectId::new("a".into(), ETag::from_str("etag-1").unwrap());
+        let cache_key_2 = ObjectId::new("a".into(), ETag::from_str("etag-2").unwrap());
+        let other_cache_key = ObjectId::new("b".into(), ETag::from_str("etag-1").unwrap());
+        for cache_key in [&cache_key_1, &cache_key_2, &other_cache_key] {
+            cache
+                .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+                .await
+                .expect("cache should be accessible");
+        }
+
+        let deleted = cache.invalidate_object("a").await.expect("invalidation should succeed");
+        assert_eq!(deleted, 2);
+        for cache_key in [&cache_key_1, &cache_key_2] {
+            let entry = cache
+                .get_block(cache_key, 0, 0, data.len())
+                .await
+                .expect("cache should be accessible");
+            assert!(entry.is_none(), "block should have been invalidated");
+        }
+        let entry = cache
+            .get_block(&other_cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(entry.is_some(), "blocks of other objects should not be invalidated");
     }
 
-    fn get_header_checksum(
-        block_idx: BlockIndex,
-        block_offset: u64,
-        cache_key: &ObjectId,
-        source_bucket_name: &str,
-        data_checksum: Crc32c,
-    ) -> Crc32c {
-        let mut hasher = crc32c::Hasher::new();
-        hasher.update(CACHE_VERSION.as_bytes());
-        hasher.update(&block_idx.to_be_bytes());
-        hasher.update(&block_offset.to_be_bytes());
-        hasher.update(cache_key.etag().as_str().as_bytes());
-        hasher.update(cache_key.key().as_bytes());
-        hasher.update(source_bucket_name.as_bytes());
-        hasher.update(&data_checksum.value().to_be_bytes());
-        hasher.finalize()
+    #[tokio::test]
+    async fn test_delete_by_subtree() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let list_counter = client.new_counter(Operation::ListObjectsV2);
+        let config = ExpressDataCacheConfig {
+            delete_by_subtree: true,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        cache.verify_cache_valid().await.expect("cache should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_keys = [
+            ObjectId::new("a".into(), ETag::from_str("etag-1").unwrap()),
+            ObjectId::new("a".into(), ETag::from_str("etag-2").unwrap()),
+            ObjectId::new("b".into(), ETag::from_str("etag-1").unwrap()),
+            ObjectId::new("c".into(), ETag::from_str("etag-1").unwrap()),
+        ];
+        for cache_key in &cache_keys {
+            cache
+                .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+                .await
+                .expect("put should succeed");
+        }
+
+        // One delimited listing of the versions of "a", then one listing per version.
+        let deleted = cache.invalidate_object("a").await.expect("invalidation should succeed");
+        assert_eq!(deleted, 2);
+        assert_eq!(list_counter.count(), 3);
+        for cache_key in &cache_keys[..2] {
+            assert!(!client.contains_key(&get_s3_key(&cache.prefix, cache_key, 0)));
+        }
+        assert_eq!(client.object_count(), 3);
+
+        // One delimited listing of the objects "b" and "c", then one listing per object.
+        let deleted = cache.clear().await.expect("clear should succeed");
+        assert_eq!(deleted, 2);
+        assert_eq!(list_counter.count(), 3 + 3);
+        assert_eq!(client.object_count(), 1, "only the cache metadata should remain");
     }
-}
 
-/// Get the prefix for objects we'll be creating in S3
-pub fn build_prefix(source_bucket_name: &str, block_size: u64) -> String {
-    hex::encode(
-        Sha256::new()
-            .chain_update(CACHE_VERSION.as_bytes())
-            .chain_update(block_size.to_be_bytes())
-            .chain_update(source_bucket_name.as_bytes())
-            .finalize(),
-    )
-}
+    #[tokio::test]
+    async fn test_invalidate_object_multiple_batches() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let list_counter = client.new_counter(Operation::ListObjectsV2);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
 
-/// Get the S3 key this block should be written to or read from.
-pub fn get_s3_key(prefix: &str, cache_key: &ObjectId, block_idx: BlockIndex) -> String {
-    let hashed_cache_key = hex::encode(
-        Sha256::new()
-            .chain_update(cache_key.key())
-            .chain_update(cache_key.etag().as_str())
-            .finalize(),
-    );
-    format!("{}/{}/{:010}", prefix, hashed_cache_key, block_idx)
-}
+        let block_count = 2 * DELETE_BATCH_SIZE + 10;
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        for block_idx in 0..block_count as u64 {
+            let object_key = get_s3_key(&cache.prefix, &cache_key, block_idx);
+            client.add_object(&object_key, MockObject::constant(1, 1, ETag::for_tests()));
+        }
+        let other_cache_key = ObjectId::new("b".into(), ETag::for_tests());
+        let data = ChecksummedBytes::new("Foo".into());
+        cache
+            .put_block(other_cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("cache should be accessible");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::checksums::ChecksummedBytes;
-    use crate::sync::Arc;
-    use proptest::{prop_assert, proptest};
+        let delete_objects_counter = client.new_counter(Operation::DeleteObjects);
 
-    use mountpoint_s3_client::failure_client::{countdown_failure_client, CountdownFailureConfig};
-    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockClientError};
-    use mountpoint_s3_client::types::ETag;
-    use test_case::test_case;
+        let deleted = cache.invalidate_object("a").await.expect("invalidation should succeed");
+        assert_eq!(deleted, block_count as u64);
+        assert_eq!(list_counter.count(), 3, "blocks should be listed in three pages");
+        assert_eq!(
+            delete_objects_counter.count(),
+            3,
+            "blocks should be deleted in three batches"
+        );
+        assert_eq!(client.object_count(), 1);
+        for block_idx in [0, DELETE_BATCH_SIZE as u64, block_count as u64 - 1] {
+            let object_key = get_s3_key(&cache.prefix, &cache_key, block_idx);
+            assert!(
+                !client.contains_key(&object_key),
+                "block {block_idx} should have been invalidated"
+            );
+        }
+        let entry = cache
+            .get_block(&other_cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(entry.is_some(), "blocks of other objects should not be invalidated");
+    }
 
-    #[test_case(1024, 512 * 1024; "block_size smaller than part_size")]
-    #[test_case(8 * 1024 * 1024, 512 * 1024; "block_size larger than part_size")]
+    #[test_case(None; "default limit")]
+    #[test_case(Some(2048); "configured limit")]
     #[tokio::test]
-    async fn test_put_get(part_size: usize, block_size: u64) {
+    async fn test_get_block_too_large(max_block_download_size: Option<u64>) {
         let bucket = "test-bucket";
+        let source_bucket = "source-bucket";
+        let block_size = 1024;
         let config = MockClientConfig {
             bucket: bucket.to_string(),
-            part_size,
+            part_size: 1024,
             enable_backpressure: true,
-            initial_read_window_size: part_size,
+            initial_read_window_size: 1024,
             ..Default::default()
         };
         let client = Arc::new(MockClient::new(config));
-
         let config = ExpressDataCacheConfig {
             block_size,
+            max_block_download_size,
             ..Default::default()
         };
-        let cache = ExpressDataCache::new(client, config, "unique source description", bucket);
+        let cache = ExpressDataCache::try_new(client.clone(), config, source_bucket, bucket)
+            .expect("cache config should be valid");
 
-        let data_1 = ChecksummedBytes::new("Foo".into());
-        let data_2 = ChecksummedBytes::new("Bar".into());
-        let data_3 = ChecksummedBytes::new("a".repeat(block_size as usize).into());
+        // Emulate an object in the cache bucket much larger than the limit.
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let object_key = get_s3_key(&build_prefix(source_bucket, block_size), &cache_key, 0);
+        client.add_object(&object_key, MockObject::constant(0u8, 64 * 1024, ETag::for_tests()));
 
-        let object_1_size = data_1.len() + data_3.len();
-        let object_2_size = data_2.len();
+        let limit = max_block_download_size.unwrap_or(block_size);
+        let err = cache
+            .get_block(&cache_key, 0, 0, 1024)
+            .await
+            .expect_err("oversized block should not be read");
+        assert!(matches!(err, DataCacheError::BlockTooLarge(max) if max == limit));
+    }
 
-        let cache_key_1 = ObjectId::new("a".into(), ETag::for_tests());
-        let cache_key_2 = ObjectId::new(
-            "longkey_".repeat(128), // 1024 bytes, max length for S3 keys
-            ETag::for_tests(),
-        );
+    #[tokio::test]
+    async fn test_hit_ratio() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            hit_ratio_window: 4,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
+        assert_eq!(cache.hit_ratio(), 0.0);
 
-        let block = cache
-            .get_block(&cache_key_1, 0, 0, object_1_size)
+        let data = ChecksummedBytes::new("Foo".into());
+        let cached_key = ObjectId::new("a".into(), ETag::for_tests());
+        let missing_key = ObjectId::new("b".into(), ETag::for_tests());
+        cache
+            .put_block(cached_key.clone(), 0, 0, data.clone(), data.len())
             .await
             .expect("cache should be accessible");
-        assert!(
-            block.is_none(),
-            "no entry should be available to return but got {:?}",
-            block,
+
+        // Sequence: miss, hit, hit, miss, miss, hit. The window only retains the last 4.
+        let lookups = [
+            (&missing_key, 0.0),
+            (&cached_key, 0.5),
+            (&cached_key, 2.0 / 3.0),
+            (&missing_key, 0.5),
+            (&missing_key, 0.5),
+            (&cached_key, 0.5),
+        ];
+        for (cache_key, expected_ratio) in lookups {
+            cache
+                .get_block(cache_key, 0, 0, data.len())
+                .await
+                .expect("cache should be accessible");
+            assert_eq!(cache.hit_ratio(), expected_ratio);
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    fn new_compressed_cache(
+        client: Arc<MockClient>,
+        bucket: &str,
+        dictionary: Option<CompressionDictionary>,
+    ) -> ExpressDataCache<Arc<MockClient>> {
+        let config = ExpressDataCacheConfig {
+            compression: Some(BlockCompression {
+                dictionary,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        ExpressDataCache::try_new(client, config, "unique source description", bucket)
+            .expect("cache config should be valid")
+    }
+
+    #[test_case(Some(16); "retained copy")]
+    #[test_case(None; "no retained copy")]
+    #[tokio::test]
+    async fn test_stale_on_error(stale_on_error_blocks: Option<usize>) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let mut get_failures = HashMap::new();
+        get_failures.insert(
+            2,
+            Err(ObjectClientError::ClientError(MockClientError("network error".into()))),
+        );
+        let failure_client = countdown_failure_client(
+            client,
+            CountdownFailureConfig {
+                get_failures,
+                ..Default::default()
+            },
         );
+        let config = ExpressDataCacheConfig {
+            stale_on_error_blocks,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(failure_client, config, "unique source description", bucket)
+            .expect("cache config should be valid");
 
-        // PUT and GET, OK?
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
         cache
-            .put_block(cache_key_1.clone(), 0, 0, data_1.clone(), object_1_size)
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
             .await
-            .expect("cache should be accessible");
-        let entry = cache
-            .get_block(&cache_key_1, 0, 0, object_1_size)
+            .expect("put should succeed");
+        let block = cache
+            .get_block_detailed(&cache_key, 0, 0, data.len())
             .await
             .expect("cache should be accessible")
             .expect("cache entry should be returned");
-        assert!(entry.validate().is_ok(), "CRC32C should match");
-        assert_eq!(
-            data_1, entry,
-            "cache entry returned should match original bytes after put"
-        );
+        assert!(!block.stale, "block read from the cache bucket should not be stale");
 
-        // PUT AND GET block for a second key, OK?
+        // The second GetObject request fails.
+        let result = cache.get_block_detailed(&cache_key, 0, 0, data.len()).await;
+        if stale_on_error_blocks.is_some() {
+            let block = result
+                .expect("retained copy should be served")
+                .expect("cache entry should be returned");
+            assert!(block.stale, "retained copy should be flagged as stale");
+            assert_eq!(block.bytes, data);
+        } else {
+            assert!(matches!(result, Err(DataCacheError::IoFailure(_))), "{result:?}");
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_get_block_detailed() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = new_compressed_cache(client.clone(), bucket, None);
+
+        let data = ChecksummedBytes::new("Foo Bar Baz ".repeat(1024).into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
         cache
-            .put_block(cache_key_2.clone(), 0, 0, data_2.clone(), object_2_size)
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
             .await
             .expect("cache should be accessible");
-        let entry = cache
-            .get_block(&cache_key_2, 0, 0, object_2_size)
+
+        let block = cache
+            .get_block_detailed(&cache_key, 0, 0, data.len())
             .await
             .expect("cache should be accessible")
             .expect("cache entry should be returned");
-        assert!(entry.validate().is_ok(), "CRC32C should match");
-        assert_eq!(
-            data_2, entry,
-            "cache entry returned should match original bytes after put"
+        assert_eq!(block.bytes, data);
+        assert!(
+            block.stored_size < data.len(),
+            "compressible block should be stored compressed, got {} bytes",
+            block.stored_size
         );
+    }
 
-        // PUT AND GET a second block in a cache entry, OK?
+    #[cfg(feature = "compression")]
+    #[test_case(None; "without dictionary")]
+    #[test_case(Some(CompressionDictionary::new(1, "Foo Bar Baz ".repeat(64))); "with dictionary")]
+    #[tokio::test]
+    async fn test_put_get_compressed(dictionary: Option<CompressionDictionary>) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let cache = new_compressed_cache(client.clone(), bucket, dictionary);
+
+        let data = ChecksummedBytes::new("Foo Bar Baz ".repeat(1024).into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
         cache
-            .put_block(cache_key_1.clone(), 1, block_size, data_3.clone(), object_1_size)
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
             .await
             .expect("cache should be accessible");
+
         let entry = cache
-            .get_block(&cache_key_1, 1, block_size, object_1_size)
+            .get_block(&cache_key, 0, 0, data.len())
             .await
             .expect("cache should be accessible")
             .expect("cache entry should be returned");
         assert!(entry.validate().is_ok(), "CRC32C should match");
         assert_eq!(
-            data_3, entry,
+            data, entry,
             "cache entry returned should match original bytes after put"
         );
 
-        // Entry 1's first block still intact
-        let entry = cache
-            .get_block(&cache_key_1, 0, 0, object_1_size)
+        // Compressed blocks can be read without compression enabled, unless they require a dictionary.
+        let uncompressed_cache =
+            ExpressDataCache::try_new(client, Default::default(), "unique source description", bucket)
+                .expect("cache config should be valid");
+        let entry = uncompressed_cache
+            .get_block(&cache_key, 0, 0, data.len())
             .await
             .expect("cache should be accessible")
             .expect("cache entry should be returned");
-        assert!(entry.validate().is_ok(), "CRC32C should match");
-        assert_eq!(
-            data_1, entry,
-            "cache entry returned should match original bytes after put"
-        );
+        assert_eq!(data, entry);
     }
 
+    #[cfg(feature = "compression")]
+    #[test_case(random_bytes(64 * 1024), None; "high entropy")]
+    #[test_case("Foo Bar Baz ".repeat(1024).into_bytes(), Some(LZ4); "low entropy")]
+    #[test_case((0..1024u64).flat_map(|i| format!("block {} offset {}\n", i % 100, (i * 7919) % 1000).into_bytes()).collect(), Some(ZSTD); "medium entropy")]
     #[tokio::test]
-    async fn large_object_bypassed() {
+    async fn test_compression_negotiation(data: Vec<u8>, expected_compression: Option<&str>) {
         let bucket = "test-bucket";
-        let config = MockClientConfig {
-            bucket: bucket.to_string(),
-            part_size: 8 * 1024 * 1024,
-            enable_backpressure: true,
-            initial_read_window_size: 8 * 1024 * 1024,
+        let client = new_mock_client(bucket);
+        let config = ExpressDataCacheConfig {
+            block_size: 1024 * 1024,
+            compression: Some(BlockCompression {
+                negotiation: Some(CompressionNegotiation::default()),
+                ..Default::default()
+            }),
             ..Default::default()
         };
-        let client = Arc::new(MockClient::new(config));
-        let cache = ExpressDataCache::new(client.clone(), Default::default(), "unique source description", bucket);
-        let data_1 = vec![0u8; 1024 * 1024 + 1];
-        let data_1 = ChecksummedBytes::new(data_1.into());
-        let cache_key_1 = ObjectId::new("a".into(), ETag::for_tests());
-        // PUT and GET for a large object should be no-op
+        let cache = ExpressDataCache::try_new(client.clone(), config, "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new(data.into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
         cache
-            .put_block(cache_key_1.clone(), 0, 0, data_1.clone(), data_1.len())
-            .await
-            .expect("cache should be accessible");
-        let get_result = cache
-            .get_block(&cache_key_1, 0, 0, data_1.len())
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
             .await
             .expect("cache should be accessible");
-        assert!(get_result.is_none());
-        assert_eq!(client.object_count(), 0, "cache must be empty");
-    }
-
-    #[tokio::test]
-    async fn test_get_validate_failure() {
-        let source_bucket = "source-bucket";
-        let bucket = "test-bucket";
-        let config = MockClientConfig {
-            bucket: bucket.to_string(),
-            part_size: 8 * 1024 * 1024,
-            enable_backpressure: true,
-            initial_read_window_size: 8 * 1024 * 1024,
-            ..Default::default()
-        };
-        let client = Arc::new(MockClient::new(config));
-        let cache = ExpressDataCache::new(client.clone(), Default::default(), source_bucket, bucket);
-
-        let data = ChecksummedBytes::new("Foo".into());
-        let data_2 = ChecksummedBytes::new("Bar".into());
-        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
-        let cache_key_non_existent = ObjectId::new("does-not-exist".into(), ETag::for_tests());
 
-        // Setup cache
-        let object_key = get_s3_key(
-            &build_prefix(source_bucket, ExpressDataCacheConfig::default().block_size),
-            &cache_key,
-            0,
+        let object_key = get_s3_key(&cache.prefix, &cache_key, 0);
+        let metadata = client
+            .get_object(bucket, &object_key, &GetObjectParams::new())
+            .await
+            .expect("block should be written")
+            .get_object_metadata();
+        assert_eq!(
+            metadata.get(COMPRESSION_HEADER).map(String::as_str),
+            expected_compression
         );
 
-        let (data, checksum) = data.into_inner().unwrap();
-        let block_metadata = BlockMetadata::new(0, 0, &cache_key, source_bucket, checksum);
-        let put_params = block_metadata.to_put_object_params();
-
-        let (data_2, checksum_2) = data_2.into_inner().unwrap();
-        let block_metadata_2 = BlockMetadata::new(0, 0, &cache_key, source_bucket, checksum_2);
-        let put_params_2 = block_metadata_2.to_put_object_params();
-
-        // Store with correct metadata and expect a successful get_block
-        client
-            .put_object_single(bucket, &object_key, &put_params, data.clone())
-            .in_current_span()
-            .await
-            .unwrap();
-        let (received_data, _) = cache
+        let entry = cache
             .get_block(&cache_key, 0, 0, data.len())
             .await
-            .expect("get should succeed with intact metadata")
-            .expect("block should be non-empty")
-            .into_inner()
-            .expect("block should be valid");
-        assert_eq!(received_data, data);
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert_eq!(data, entry);
 
-        // Remove the checksum when writing.
-        client
-            .put_object_single(bucket, &object_key, &put_params.clone().checksum(None), data.clone())
-            .in_current_span()
-            .await
-            .unwrap();
-        let err = cache
+        // Blocks can be read regardless of the negotiation.
+        let other_cache = new_compressed_cache(client, bucket, None);
+        let entry = other_cache
             .get_block(&cache_key, 0, 0, data.len())
             .await
-            .expect_err("cache should return error if checksum isn't present");
-        assert!(matches!(err, DataCacheError::InvalidBlockChecksum));
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert_eq!(data, entry);
+    }
 
-        // Remove the object metadata when writing.
-        client
-            .put_object_single(
-                bucket,
-                &object_key,
-                &put_params.clone().object_metadata(HashMap::new()),
-                data.clone(),
-            )
-            .in_current_span()
-            .await
-            .unwrap();
-        let err = cache
-            .get_block(&cache_key, 0, 0, data.len())
-            .await
-            .expect_err("cache should return error if object metadata isn't present");
-        assert!(matches!(err, DataCacheError::InvalidBlockHeader(_)));
+    #[cfg(feature = "compression")]
+    fn random_bytes(len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        StdRng::seed_from_u64(0x196).fill(&mut data[..]);
+        data
+    }
 
-        // Emulate corrupt data by writing 'incorrect' data.
-        client
-            .put_object_single(bucket, &object_key, &put_params.clone(), data_2.clone())
-            .in_current_span()
-            .await
-            .expect_err("should fail to write as checksum incorrect");
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_decompress_lz4_over_capacity() {
+        let data = "Foo".repeat(100);
+        let compressed = lz4_flex::compress_prepend_size(data.as_bytes());
+        let headers = HashMap::from([(COMPRESSION_HEADER.to_string(), LZ4.to_string())]);
+        let decompressed = decompress_block(&headers, &compressed, None, data.len())
+            .expect("decompression should succeed")
+            .expect("block should be compressed");
+        assert_eq!(decompressed, data.as_bytes());
+        let result = decompress_block(&headers, &compressed, None, data.len() - 1);
+        assert!(matches!(result, Err(DataCacheError::InvalidBlockContent)));
+    }
 
-        // Write data with object metadata header for different object
-        client
-            .put_object_single(
-                bucket,
-                &object_key,
-                &put_params.clone().checksum(put_params_2.checksum.clone()),
-                data_2.clone(),
-            )
-            .in_current_span()
-            .await
-            .unwrap();
-        let err = cache
-            .get_block(&cache_key, 0, 0, data_2.len())
-            .await
-            .expect_err("cache should return error if object metadata doesn't match data");
-        assert!(matches!(err, DataCacheError::InvalidBlockHeader(_)));
+    #[cfg(feature = "compression")]
+    #[test_case(None; "no dictionary configured")]
+    #[test_case(Some(CompressionDictionary::new(2, "Other content")); "different dictionary configured")]
+    #[tokio::test]
+    async fn test_get_compressed_missing_dictionary(read_dictionary: Option<CompressionDictionary>) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let dictionary = CompressionDictionary::new(1, "Foo Bar Baz ".repeat(64));
+        let cache = new_compressed_cache(client.clone(), bucket, Some(dictionary));
 
-        // Write data with object metadata header for object from a different bucket
-        let mut corrupted_metadata = block_metadata.clone();
-        corrupted_metadata.source_bucket_name = bucket.to_owned();
-        client
-            .put_object_single(
-                bucket,
-                &object_key,
-                &corrupted_metadata.to_put_object_params(),
-                data.clone(),
-            )
-            .in_current_span()
+        let data = ChecksummedBytes::new("Foo Bar Baz ".repeat(1024).into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
             .await
-            .unwrap();
+            .expect("cache should be accessible");
+
+        let cache = new_compressed_cache(client, bucket, read_dictionary);
         let err = cache
             .get_block(&cache_key, 0, 0, data.len())
             .await
-            .expect_err("cache should return error if source bucket does not match");
-        assert!(matches!(err, DataCacheError::InvalidBlockHeader(_)));
-
-        // Get data that's not been written yet
-        let result = cache
-            .get_block(&cache_key_non_existent, 0, 0, data.len())
-            .await
-            .expect("cache should return None if data is not present");
-        assert_eq!(result, None);
+            .expect_err("block should not be readable without its dictionary");
+        assert!(matches!(err, DataCacheError::InvalidBlockHeader(header) if header == COMPRESSION_DICTIONARY_HEADER));
     }
 
     #[tokio::test]
@@ -742,7 +9149,8 @@ mod tests {
             ..Default::default()
         };
         let client = Arc::new(MockClient::new(config));
-        let cache = ExpressDataCache::new(client.clone(), Default::default(), source_bucket, bucket);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), source_bucket, bucket)
+            .expect("cache config should be valid");
 
         cache.verify_cache_valid().await.expect("cache should work");
     }
@@ -771,7 +9179,8 @@ mod tests {
             },
         ));
 
-        let cache = ExpressDataCache::new(failure_client, Default::default(), source_bucket, bucket);
+        let cache = ExpressDataCache::try_new(failure_client, Default::default(), source_bucket, bucket)
+            .expect("cache config should be valid");
 
         cache
             .verify_cache_valid()
@@ -779,6 +9188,147 @@ mod tests {
             .expect_err("cache should not report valid if cannot write");
     }
 
+    #[test_case(false; "default classification")]
+    #[test_case(true; "reclassified as transient")]
+    #[tokio::test]
+    async fn test_retry_classifier(reclassify: bool) {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid")
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        let mut get_failures = HashMap::new();
+        get_failures.insert(1, Err(ObjectClientError::ServiceError(GetObjectError::NoSuchBucket)));
+        let failure_client = Arc::new(countdown_failure_client(
+            client.clone(),
+            CountdownFailureConfig {
+                get_failures,
+                ..Default::default()
+            },
+        ));
+        let mut cache =
+            ExpressDataCache::try_new(failure_client, Default::default(), "unique source description", bucket)
+                .expect("cache config should be valid");
+        if reclassify {
+            cache = cache.with_retry_classifier(|err| match err {
+                ObjectClientError::ServiceError(GetObjectError::NoSuchBucket) => RetryDecision::Transient,
+                _ => RetryDecision::Permanent,
+            });
+        }
+
+        let get_counter = client.new_counter(Operation::GetObject);
+        let result = cache.get_block(&cache_key, 0, 0, data.len()).await;
+        if reclassify {
+            let block = result.expect("request should be retried");
+            assert_eq!(block, Some(data));
+            assert_eq!(get_counter.count(), 1, "retry should reach the bucket");
+        } else {
+            result.expect_err("error should not be retried");
+            assert_eq!(get_counter.count(), 0);
+        }
+    }
+
+    #[test_case(1, false; "no mid-stream retry")]
+    #[test_case(2, true; "mid-stream retry")]
+    #[tokio::test]
+    async fn test_mid_stream_retry(max_mid_stream_attempts: u32, expect_retry: bool) {
+        let bucket = "test-bucket";
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 256,
+            enable_backpressure: true,
+            initial_read_window_size: 256,
+            ..Default::default()
+        }));
+        let config = ExpressDataCacheConfig {
+            block_size: 1024,
+            max_mid_stream_attempts,
+            ..Default::default()
+        };
+        let data = ChecksummedBytes::new((0..1024).map(|i| i as u8).collect::<Vec<_>>().into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let write_config = ExpressDataCacheConfig {
+            block_size: 1024,
+            ..Default::default()
+        };
+        ExpressDataCache::try_new(client.clone(), write_config, "unique source description", bucket)
+            .expect("cache config should be valid")
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        // The first request fails after two of the four chunks of the block were received.
+        let mut get_failures = HashMap::new();
+        get_failures.insert(1, Ok((3, MockClientError("connection reset".into()))));
+        let failure_client = Arc::new(countdown_failure_client(
+            client.clone(),
+            CountdownFailureConfig {
+                get_failures,
+                ..Default::default()
+            },
+        ));
+        let cache = ExpressDataCache::try_new(failure_client, config, "unique source description", bucket)
+            .expect("cache config should be valid")
+            .with_retry_classifier(|err| match err {
+                ObjectClientError::ClientError(_) => RetryDecision::Transient,
+                _ => RetryDecision::Permanent,
+            });
+
+        let get_counter = client.new_counter(Operation::GetObject);
+        let result = cache.get_block(&cache_key, 0, 0, data.len()).await;
+        if expect_retry {
+            let block = result.expect("request should be reissued");
+            assert_eq!(block, Some(data), "block should be read again from its start");
+            assert_eq!(get_counter.count(), 2);
+        } else {
+            assert!(matches!(result, Err(DataCacheError::IoFailure(_))), "{result:?}");
+            assert_eq!(get_counter.count(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_deadline() {
+        let bucket = "test-bucket";
+        let client = new_mock_client(bucket);
+        // The last attempt would succeed, if it was made.
+        let mut get_failures = HashMap::new();
+        for attempt in 1..MAX_GET_ATTEMPTS as usize {
+            get_failures.insert(
+                attempt,
+                Err(ObjectClientError::ServiceError(GetObjectError::NoSuchBucket)),
+            );
+        }
+        let failure_client = Arc::new(countdown_failure_client(
+            client.clone(),
+            CountdownFailureConfig {
+                get_failures,
+                ..Default::default()
+            },
+        ));
+        let config = ExpressDataCacheConfig {
+            retry_deadline: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(failure_client, config, "unique source description", bucket)
+            .expect("cache config should be valid")
+            .with_retry_classifier(|_| {
+                // Make each failed attempt take longer than half of the deadline.
+                std::thread::sleep(Duration::from_millis(60));
+                RetryDecision::Transient
+            });
+
+        let get_counter = client.new_counter(Operation::GetObject);
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let result = cache.get_block(&cache_key, 0, 0, 3).await;
+        assert!(matches!(result, Err(DataCacheError::IoFailure(_))), "{result:?}");
+        assert_eq!(get_counter.count(), 0, "no attempt should be made after the deadline");
+    }
+
     proptest! {
         #[test]
         fn proptest_creates_small_s3_keys(key: String, etag: String, block_idx: BlockIndex, source_description: String, block_size: u64) {
@@ -788,6 +9338,17 @@ mod tests {
             prop_assert!(get_s3_key(&prefix, &cache_key, block_idx).len() <= 1024);
         }
 
+        #[test]
+        fn proptest_short_key_format_length(key: String, etag: String, block_idx: BlockIndex, source_description: String, block_size: u64) {
+            // The key length does not depend on the object key, however long its prefix
+            let cache_key = ObjectId::new(format!("{}{}", "long/user/prefix/".repeat(60), key), etag.into());
+            let prefix = build_prefix_for_version(CACHE_VERSION, KeyFormat::Short, KeyEncoding::LowerHex, KeyHashAlgorithm::Sha256, None, None, &source_description, block_size);
+            let hasher = KeyHasher { format: KeyFormat::Short, ..KeyHasher::STANDARD };
+            let s3_key = block_key(hasher, &prefix, &cache_key, block_idx);
+            prop_assert!(s3_key.len() <= 3 * 32 + 3 + 20);
+            prop_assert!(s3_key.len() < get_s3_key(&build_prefix(&source_description, block_size), &cache_key, block_idx).len());
+        }
+
         #[test]
         fn proptest_block_metadata_to_headers_s3_key_ascii_only(block_metadata: BlockMetadata) {
             // Validate that even with UTF keys, the source key is always ascii
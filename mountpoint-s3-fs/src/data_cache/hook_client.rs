@@ -0,0 +1,379 @@
+//! An [ObjectClient] that runs a hook before forwarding each request, to observe or delay the requests of a
+//! cache in tests.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use mountpoint_s3_client::error::{
+    CopyObjectError, DeleteObjectError, DeleteObjectsError, GetObjectAttributesError, GetObjectError, HeadObjectError,
+    ListObjectsError, ObjectClientError, PutObjectError,
+};
+use mountpoint_s3_client::types::{
+    BufferPoolUsageStats, CopyObjectParams, CopyObjectResult, DeleteObjectResult, DeleteObjectsResult,
+    GetObjectAttributesResult, GetObjectParams, HeadObjectParams, HeadObjectResult, ListObjectsResult, ObjectAttribute,
+    ObjectClientResult, PutObjectParams, PutObjectResult, PutObjectSingleParams,
+};
+use mountpoint_s3_client::ObjectClient;
+
+/// A request to a [HookClient], passed to its [RequestHook] before being forwarded.
+#[derive(Debug)]
+pub enum HookedRequest<'a> {
+    DeleteObject {
+        bucket: &'a str,
+        key: &'a str,
+    },
+    DeleteObjects {
+        bucket: &'a str,
+        keys: &'a [String],
+    },
+    CopyObject {
+        source_bucket: &'a str,
+        source_key: &'a str,
+        destination_bucket: &'a str,
+        destination_key: &'a str,
+        params: &'a CopyObjectParams,
+    },
+    GetObject {
+        bucket: &'a str,
+        key: &'a str,
+        params: &'a GetObjectParams,
+    },
+    ListObjects {
+        bucket: &'a str,
+        continuation_token: Option<&'a str>,
+        delimiter: &'a str,
+        max_keys: usize,
+        prefix: &'a str,
+    },
+    HeadObject {
+        bucket: &'a str,
+        key: &'a str,
+        params: &'a HeadObjectParams,
+    },
+    PutObject {
+        bucket: &'a str,
+        key: &'a str,
+        params: &'a PutObjectParams,
+    },
+    PutObjectSingle {
+        bucket: &'a str,
+        key: &'a str,
+        params: &'a PutObjectSingleParams,
+        contents: &'a [u8],
+    },
+    GetObjectAttributes {
+        bucket: &'a str,
+        key: &'a str,
+    },
+}
+
+impl<'a> HookedRequest<'a> {
+    /// The bucket the request is routed by, the source bucket for CopyObject.
+    pub fn bucket(&self) -> &'a str {
+        match self {
+            HookedRequest::DeleteObject { bucket, .. }
+            | HookedRequest::DeleteObjects { bucket, .. }
+            | HookedRequest::GetObject { bucket, .. }
+            | HookedRequest::ListObjects { bucket, .. }
+            | HookedRequest::HeadObject { bucket, .. }
+            | HookedRequest::PutObject { bucket, .. }
+            | HookedRequest::PutObjectSingle { bucket, .. }
+            | HookedRequest::GetObjectAttributes { bucket, .. } => bucket,
+            HookedRequest::CopyObject { source_bucket, .. } => source_bucket,
+        }
+    }
+}
+
+/// Hook of a [HookClient], run before each request is forwarded to the wrapped client.
+#[async_trait]
+pub trait RequestHook<ClientError> {
+    /// Runs before `request` is forwarded. Returning an error fails the request with
+    /// [ObjectClientError::ClientError] without forwarding it.
+    async fn before(&self, request: &HookedRequest<'_>) -> Result<(), ClientError>;
+}
+
+/// Hook forwarding all requests unchanged, for a [HookClient] only routing requests by bucket.
+#[async_trait]
+impl<ClientError: Send + Sync + 'static> RequestHook<ClientError> for () {
+    async fn before(&self, _request: &HookedRequest<'_>) -> Result<(), ClientError> {
+        Ok(())
+    }
+}
+
+/// A client running a [RequestHook] before forwarding each request to a wrapped client.
+///
+/// Requests are forwarded to [HookClient::client], or to the client registered for their bucket with
+/// [HookClient::with_bucket_client].
+pub struct HookClient<Client, Hook> {
+    pub client: Client,
+    pub hook: Hook,
+    bucket_clients: HashMap<String, Client>,
+}
+
+impl<Client, Hook> HookClient<Client, Hook> {
+    pub fn new(client: Client, hook: Hook) -> Self {
+        Self {
+            client,
+            hook,
+            bucket_clients: Default::default(),
+        }
+    }
+
+    /// Forward the requests for `bucket` to `client`.
+    pub fn with_bucket_client(mut self, bucket: &str, client: Client) -> Self {
+        self.bucket_clients.insert(bucket.to_owned(), client);
+        self
+    }
+
+    /// The client the requests for `bucket` are forwarded to.
+    pub fn client_for(&self, bucket: &str) -> &Client {
+        self.bucket_clients.get(bucket).unwrap_or(&self.client)
+    }
+
+    async fn before<E>(&self, request: &HookedRequest<'_>) -> Result<&Client, ObjectClientError<E, Client::ClientError>>
+    where
+        Client: ObjectClient,
+        Hook: RequestHook<Client::ClientError> + Sync,
+    {
+        self.hook
+            .before(request)
+            .await
+            .map_err(ObjectClientError::ClientError)?;
+        Ok(self.client_for(request.bucket()))
+    }
+}
+
+#[async_trait]
+impl<Client, Hook> ObjectClient for HookClient<Client, Hook>
+where
+    Client: ObjectClient + Send + Sync + 'static,
+    Hook: RequestHook<Client::ClientError> + Send + Sync,
+{
+    type GetObjectResponse = Client::GetObjectResponse;
+    type PutObjectRequest = Client::PutObjectRequest;
+    type ClientError = Client::ClientError;
+
+    fn read_part_size(&self) -> Option<usize> {
+        self.client.read_part_size()
+    }
+
+    fn write_part_size(&self) -> Option<usize> {
+        self.client.write_part_size()
+    }
+
+    fn initial_read_window_size(&self) -> Option<usize> {
+        self.client.initial_read_window_size()
+    }
+
+    fn mem_usage_stats(&self) -> Option<BufferPoolUsageStats> {
+        self.client.mem_usage_stats()
+    }
+
+    fn region(&self) -> Option<&str> {
+        self.client.region()
+    }
+
+    async fn delete_object(
+        &self,
+        bucket: &str,
+        key: &str,
+    ) -> ObjectClientResult<DeleteObjectResult, DeleteObjectError, Self::ClientError> {
+        let request = HookedRequest::DeleteObject { bucket, key };
+        let client = self.before::<DeleteObjectError>(&request).await?;
+        client.delete_object(bucket, key).await
+    }
+
+    async fn delete_objects(
+        &self,
+        bucket: &str,
+        keys: &[String],
+    ) -> ObjectClientResult<DeleteObjectsResult, DeleteObjectsError, Self::ClientError> {
+        let request = HookedRequest::DeleteObjects { bucket, keys };
+        let client = self.before::<DeleteObjectsError>(&request).await?;
+        client.delete_objects(bucket, keys).await
+    }
+
+    async fn copy_object(
+        &self,
+        source_bucket: &str,
+        source_key: &str,
+        destination_bucket: &str,
+        destination_key: &str,
+        params: &CopyObjectParams,
+    ) -> ObjectClientResult<CopyObjectResult, CopyObjectError, Self::ClientError> {
+        let request = HookedRequest::CopyObject {
+            source_bucket,
+            source_key,
+            destination_bucket,
+            destination_key,
+            params,
+        };
+        let client = self.before::<CopyObjectError>(&request).await?;
+        client
+            .copy_object(source_bucket, source_key, destination_bucket, destination_key, params)
+            .await
+    }
+
+    async fn get_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &GetObjectParams,
+    ) -> ObjectClientResult<Self::GetObjectResponse, GetObjectError, Self::ClientError> {
+        let request = HookedRequest::GetObject { bucket, key, params };
+        let client = self.before::<GetObjectError>(&request).await?;
+        client.get_object(bucket, key, params).await
+    }
+
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        continuation_token: Option<&str>,
+        delimiter: &str,
+        max_keys: usize,
+        prefix: &str,
+    ) -> ObjectClientResult<ListObjectsResult, ListObjectsError, Self::ClientError> {
+        let request = HookedRequest::ListObjects {
+            bucket,
+            continuation_token,
+            delimiter,
+            max_keys,
+            prefix,
+        };
+        let client = self.before::<ListObjectsError>(&request).await?;
+        client
+            .list_objects(bucket, continuation_token, delimiter, max_keys, prefix)
+            .await
+    }
+
+    async fn head_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &HeadObjectParams,
+    ) -> ObjectClientResult<HeadObjectResult, HeadObjectError, Self::ClientError> {
+        let request = HookedRequest::HeadObject { bucket, key, params };
+        let client = self.before::<HeadObjectError>(&request).await?;
+        client.head_object(bucket, key, params).await
+    }
+
+    async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &PutObjectParams,
+    ) -> ObjectClientResult<Self::PutObjectRequest, PutObjectError, Self::ClientError> {
+        let request = HookedRequest::PutObject { bucket, key, params };
+        let client = self.before::<PutObjectError>(&request).await?;
+        client.put_object(bucket, key, params).await
+    }
+
+    async fn put_object_single<'a>(
+        &self,
+        bucket: &str,
+        key: &str,
+        params: &PutObjectSingleParams,
+        contents: impl AsRef<[u8]> + Send + 'a,
+    ) -> ObjectClientResult<PutObjectResult, PutObjectError, Self::ClientError> {
+        let client = {
+            let request = HookedRequest::PutObjectSingle {
+                bucket,
+                key,
+                params,
+                contents: contents.as_ref(),
+            };
+            self.before::<PutObjectError>(&request).await?
+        };
+        client.put_object_single(bucket, key, params, contents).await
+    }
+
+    async fn get_object_attributes(
+        &self,
+        bucket: &str,
+        key: &str,
+        max_parts: Option<usize>,
+        part_number_marker: Option<usize>,
+        object_attributes: &[ObjectAttribute],
+    ) -> ObjectClientResult<GetObjectAttributesResult, GetObjectAttributesError, Self::ClientError> {
+        let request = HookedRequest::GetObjectAttributes { bucket, key };
+        let client = self.before::<GetObjectAttributesError>(&request).await?;
+        client
+            .get_object_attributes(bucket, key, max_parts, part_number_marker, object_attributes)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, MockClientError, MockObject};
+    use mountpoint_s3_client::types::ETag;
+
+    fn new_mock_client(bucket: &str) -> MockClient {
+        MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 1024,
+            ..Default::default()
+        })
+    }
+
+    /// Hook recording the key of each request and failing those for `failing_key`.
+    #[derive(Default)]
+    struct RecordingHook {
+        keys: Mutex<Vec<String>>,
+        failing_key: &'static str,
+    }
+
+    #[async_trait]
+    impl RequestHook<MockClientError> for RecordingHook {
+        async fn before(&self, request: &HookedRequest<'_>) -> Result<(), MockClientError> {
+            let HookedRequest::GetObject { key, .. } = request else {
+                return Ok(());
+            };
+            self.keys.lock().unwrap().push(key.to_string());
+            if *key == self.failing_key {
+                return Err(MockClientError("injected failure".into()));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hook_client() {
+        let client = new_mock_client("test_bucket");
+        client.add_object("a", MockObject::constant(1u8, 10, ETag::for_tests()));
+        client.add_object("b", MockObject::constant(2u8, 10, ETag::for_tests()));
+        let hook = RecordingHook {
+            failing_key: "b",
+            ..Default::default()
+        };
+        let client = HookClient::new(client, hook);
+
+        client
+            .get_object("test_bucket", "a", &GetObjectParams::new())
+            .await
+            .expect("get should succeed");
+        let result = client.get_object("test_bucket", "b", &GetObjectParams::new()).await;
+        assert!(matches!(result, Err(ObjectClientError::ClientError(_))));
+        assert_eq!(*client.hook.keys.lock().unwrap(), ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_bucket_client() {
+        let client = new_mock_client("bucket");
+        let other_client = new_mock_client("other_bucket");
+        other_client.add_object("a", MockObject::constant(1u8, 10, ETag::for_tests()));
+        let client = HookClient::new(client, ()).with_bucket_client("other_bucket", other_client);
+
+        client
+            .head_object("other_bucket", "a", &HeadObjectParams::new())
+            .await
+            .expect("head should be sent to the client of the bucket");
+        client
+            .head_object("bucket", "a", &HeadObjectParams::new())
+            .await
+            .expect_err("head should be sent to the default client");
+    }
+}
@@ -2,13 +2,36 @@
 
 use std::collections::HashMap;
 use std::default::Default;
+use std::str::FromStr;
 
 use async_trait::async_trait;
+use mountpoint_s3_client::checksums::crc32c::Crc32c;
+use mountpoint_s3_client::types::ETag;
+use serde::{Deserialize, Serialize};
 
 use super::{BlockIndex, ChecksummedBytes, DataCache, DataCacheError, DataCacheResult};
 use crate::object::ObjectId;
 use crate::sync::RwLock;
 
+/// Largest block size of an [InMemoryDataCache], since every block is held in memory.
+const MAX_BLOCK_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Blocks of an [InMemoryDataCache], which can be serialized to restore them in another process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheState {
+    block_size: u64,
+    blocks: Vec<CachedBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedBlock {
+    key: String,
+    etag: String,
+    block_idx: BlockIndex,
+    data: Vec<u8>,
+    crc32c: u32,
+}
+
 /// Simple in-memory (RAM) implementation of [DataCache]. Recommended for use in testing only.
 pub struct InMemoryDataCache {
     data: RwLock<HashMap<ObjectId, HashMap<BlockIndex, ChecksummedBytes>>>,
@@ -17,13 +40,84 @@ pub struct InMemoryDataCache {
 
 impl InMemoryDataCache {
     /// Create a new instance of an [InMemoryDataCache] with the specified `block_size`.
+    ///
+    /// Panics if `block_size` is above [DataCache::max_block_size], see [InMemoryDataCache::try_new].
     pub fn new(block_size: u64) -> Self {
+        assert!(
+            block_size <= MAX_BLOCK_SIZE,
+            "block size {block_size} is above the maximum of {MAX_BLOCK_SIZE} bytes"
+        );
         InMemoryDataCache {
             data: Default::default(),
             block_size,
         }
     }
 
+    /// Create a new instance, failing if `block_size` is above [DataCache::max_block_size].
+    pub fn try_new(block_size: u64) -> DataCacheResult<Self> {
+        if block_size > MAX_BLOCK_SIZE {
+            return Err(DataCacheError::InvalidCacheConfig(format!(
+                "block size {block_size} is above the maximum of {MAX_BLOCK_SIZE} bytes"
+            )));
+        }
+        Ok(Self::new(block_size))
+    }
+
+    /// Copy the blocks of the cache with their checksums.
+    ///
+    /// Blocks not matching their checksum are left out.
+    pub fn snapshot(&self) -> CacheState {
+        let data = self.data.read().unwrap();
+        let blocks = data
+            .iter()
+            .flat_map(|(cache_key, blocks)| {
+                blocks.iter().filter_map(move |(block_idx, bytes)| {
+                    let Ok((data, checksum)) = bytes.clone().into_inner() else {
+                        tracing::warn!(
+                            ?cache_key,
+                            block_idx,
+                            "block left out of snapshot, checksum does not match"
+                        );
+                        return None;
+                    };
+                    Some(CachedBlock {
+                        key: cache_key.key().to_owned(),
+                        etag: cache_key.etag().as_str().to_owned(),
+                        block_idx: *block_idx,
+                        data: data.to_vec(),
+                        crc32c: checksum.value(),
+                    })
+                })
+            })
+            .collect();
+        CacheState {
+            block_size: self.block_size,
+            blocks,
+        }
+    }
+
+    /// Add the blocks of a [CacheState] taken with [InMemoryDataCache::snapshot], replacing blocks
+    /// already cached for the same object and index.
+    ///
+    /// Fails if the snapshot was taken from a cache with another block size.
+    pub fn restore(&self, state: CacheState) -> DataCacheResult<()> {
+        if state.block_size != self.block_size {
+            return Err(DataCacheError::InvalidCacheConfig(format!(
+                "snapshot block size {} does not match the block size {}",
+                state.block_size, self.block_size
+            )));
+        }
+        let mut data = self.data.write().unwrap();
+        for block in state.blocks {
+            let etag = ETag::from_str(&block.etag).map_err(|_| DataCacheError::InvalidBlockContent)?;
+            let bytes = ChecksummedBytes::new_from_inner_data(block.data.into(), Crc32c::new(block.crc32c));
+            data.entry(ObjectId::new(block.key, etag))
+                .or_default()
+                .insert(block.block_idx, bytes);
+        }
+        Ok(())
+    }
+
     /// Get number of caching blocks for the given cache key.
     pub fn block_count(&self, cache_key: &ObjectId) -> usize {
         let data = self.data.read().unwrap();
@@ -68,6 +162,14 @@ impl DataCache for InMemoryDataCache {
     fn block_size(&self) -> u64 {
         self.block_size
     }
+
+    fn max_block_size(&self) -> u64 {
+        MAX_BLOCK_SIZE
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "memory"
+    }
 }
 
 #[cfg(test)]
@@ -75,7 +177,76 @@ mod tests {
     use super::*;
 
     use bytes::Bytes;
-    use mountpoint_s3_client::types::ETag;
+
+    use crate::data_cache::conformance::run_data_cache_conformance;
+
+    #[test]
+    fn test_supports_ranged_reads() {
+        let cache = InMemoryDataCache::new(1024);
+        assert!(!cache.supports_ranged_reads());
+    }
+
+    #[test]
+    fn test_max_block_size() {
+        let cache = InMemoryDataCache::try_new(MAX_BLOCK_SIZE).expect("maximum block size should be accepted");
+        assert_eq!(cache.max_block_size(), MAX_BLOCK_SIZE);
+        let result = InMemoryDataCache::try_new(MAX_BLOCK_SIZE + 1);
+        assert!(matches!(result, Err(DataCacheError::InvalidCacheConfig(_))));
+    }
+
+    #[test]
+    #[should_panic(expected = "above the maximum")]
+    fn test_new_rejects_block_size_above_max() {
+        InMemoryDataCache::new(MAX_BLOCK_SIZE + 1);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_restore() {
+        let cache = InMemoryDataCache::new(1024);
+        let data_1 = ChecksummedBytes::new(Bytes::from_static(b"Hello world"));
+        let data_2 = ChecksummedBytes::new(Bytes::from_static(b"Foo bar"));
+        let cache_key_1 = ObjectId::new("a".into(), ETag::for_tests());
+        let cache_key_2 = ObjectId::new("b".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key_1.clone(), 0, 0, data_1.clone(), 2048)
+            .await
+            .expect("put should succeed");
+        cache
+            .put_block(cache_key_1.clone(), 1, 1024, data_2.clone(), 2048)
+            .await
+            .expect("put should succeed");
+        cache
+            .put_block(cache_key_2.clone(), 0, 0, data_2.clone(), data_2.len())
+            .await
+            .expect("put should succeed");
+
+        let serialized = serde_json::to_vec(&cache.snapshot()).expect("snapshot should serialize");
+        let state: CacheState = serde_json::from_slice(&serialized).expect("snapshot should deserialize");
+        let restored = InMemoryDataCache::new(1024);
+        restored.restore(state.clone()).expect("restore should succeed");
+        assert_eq!(restored.block_count(&cache_key_1), 2);
+        assert_eq!(restored.block_count(&cache_key_2), 1);
+        for (cache_key, block_idx, expected) in [
+            (&cache_key_1, 0, &data_1),
+            (&cache_key_1, 1, &data_2),
+            (&cache_key_2, 0, &data_2),
+        ] {
+            let block = restored
+                .get_block(cache_key, block_idx, block_idx * 1024, 2048)
+                .await
+                .expect("cache should be accessible")
+                .expect("restored block should be cached");
+            assert_eq!(block.into_bytes().unwrap(), expected.clone().into_bytes().unwrap());
+        }
+
+        let result = InMemoryDataCache::new(2048).restore(state);
+        assert!(matches!(result, Err(DataCacheError::InvalidCacheConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_conformance() {
+        run_data_cache_conformance(&InMemoryDataCache::new(1024)).await;
+    }
 
     #[tokio::test]
     async fn test_put_get() {
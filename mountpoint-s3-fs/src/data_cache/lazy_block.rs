@@ -0,0 +1,117 @@
+//! Module for blocks that are only fetched from a [DataCache] when accessed.
+
+use async_lock::OnceCell;
+
+use crate::object::ObjectId;
+
+use super::{BlockIndex, ChecksummedBytes, DataCache, DataCacheResult};
+
+/// Handle to a block in a [DataCache], which is fetched the first time it is accessed.
+///
+/// Useful when requesting many blocks speculatively: blocks that end up not being read
+/// never cost a request to the cache. Created with [DataCache::get_block_lazy].
+pub struct LazyBlock<'a, Cache: ?Sized> {
+    cache: &'a Cache,
+    cache_key: ObjectId,
+    block_idx: BlockIndex,
+    block_offset: u64,
+    object_size: usize,
+    block: OnceCell<Option<ChecksummedBytes>>,
+}
+
+impl<'a, Cache> LazyBlock<'a, Cache>
+where
+    Cache: DataCache + ?Sized,
+{
+    pub(super) fn new(
+        cache: &'a Cache,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+    ) -> Self {
+        Self {
+            cache,
+            cache_key,
+            block_idx,
+            block_offset,
+            object_size,
+            block: OnceCell::new(),
+        }
+    }
+
+    /// Index of the block within the object.
+    pub fn block_idx(&self) -> BlockIndex {
+        self.block_idx
+    }
+
+    /// Returns whether the block was already fetched from the cache.
+    pub fn is_loaded(&self) -> bool {
+        self.block.is_initialized()
+    }
+
+    /// Fetch the block from the cache, if not already fetched, and return it.
+    ///
+    /// Returns [None] if the block was not available in the cache. Errors are not retained,
+    /// so calling this again after a failure will retry the fetch.
+    pub async fn get(&self) -> DataCacheResult<Option<&ChecksummedBytes>> {
+        let block = self
+            .block
+            .get_or_try_init(|| {
+                self.cache
+                    .get_block(&self.cache_key, self.block_idx, self.block_offset, self.object_size)
+            })
+            .await?;
+        Ok(block.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, Operation};
+    use mountpoint_s3_client::types::ETag;
+
+    use crate::data_cache::ExpressDataCache;
+
+    #[tokio::test]
+    async fn test_lazy_block_fetched_on_access() {
+        let bucket = "test-bucket";
+        let client = MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * 1024 * 1024,
+            enable_backpressure: true,
+            initial_read_window_size: 8 * 1024 * 1024,
+            ..Default::default()
+        });
+        let get_counter = client.new_counter(Operation::GetObject);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+
+        let block = cache.get_block_lazy(cache_key.clone(), 0, 0, data.len());
+        let missing_block = cache.get_block_lazy(cache_key.clone(), 1, cache.block_size(), data.len());
+        assert!(!block.is_loaded());
+        assert_eq!(get_counter.count(), 0, "no request should be made before access");
+
+        let bytes = block.get().await.expect("cache should be accessible");
+        assert_eq!(bytes, Some(&data));
+        assert!(block.is_loaded());
+        assert_eq!(get_counter.count(), 1);
+
+        // Later accesses reuse the fetched block.
+        let bytes = block.get().await.expect("cache should be accessible");
+        assert_eq!(bytes, Some(&data));
+        assert_eq!(get_counter.count(), 1);
+
+        drop(missing_block);
+        assert_eq!(get_counter.count(), 1, "unused handles should not fetch the block");
+    }
+}
@@ -19,6 +19,9 @@ impl<DiskCache: DataCache, ExpressCache: DataCache, Runtime: Spawn>
     MultilevelDataCache<DiskCache, ExpressCache, Runtime>
 {
     /// Both the `disk_cache` and `express_cache` must be configured with the same `block_size`.
+    ///
+    /// Blocks served from the S3 Express cache are written to the disk cache in tasks spawned on `runtime`,
+    /// which can be any executor implementing [Spawn].
     pub fn new(disk_cache: Arc<DiskCache>, express_cache: ExpressCache, runtime: Runtime) -> Self {
         // The same blocks are written to both caches. The `block_size`-s must match.
         assert_eq!(
@@ -112,15 +115,28 @@ where
     fn block_size(&self) -> u64 {
         self.disk_cache.block_size()
     }
+
+    fn max_block_size(&self) -> u64 {
+        self.disk_cache
+            .max_block_size()
+            .min(self.express_cache.max_block_size())
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "multilevel"
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::checksums::ChecksummedBytes;
-    use crate::data_cache::{CacheLimit, DiskDataCache, DiskDataCacheConfig, ExpressDataCache};
+    use crate::data_cache::{
+        BlockSizeMismatchPolicy, CacheLimit, DiskDataCache, DiskDataCacheConfig, ExpressDataCache,
+    };
 
     use futures::executor::ThreadPool;
+    use futures::task::{FutureObj, SpawnError};
     use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig};
     use mountpoint_s3_client::types::ETag;
     use tempfile::TempDir;
@@ -136,6 +152,8 @@ mod tests {
             DiskDataCacheConfig {
                 block_size: BLOCK_SIZE,
                 limit: CacheLimit::Unbounded,
+                min_free_bytes: 0,
+                block_size_mismatch: BlockSizeMismatchPolicy::Fail,
             },
         );
         (cache_directory, Arc::new(cache))
@@ -151,10 +169,20 @@ mod tests {
             ..Default::default()
         };
         let client = MockClient::new(config);
-        let cache = ExpressDataCache::new(client.clone(), Default::default(), "unique source description", bucket);
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
         (client, cache)
     }
 
+    #[test]
+    fn test_backend_name() {
+        let (_cache_dir, disk_cache) = create_disk_cache();
+        let (_, express_cache) = create_express_cache();
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let cache = MultilevelDataCache::new(disk_cache, express_cache, runtime);
+        assert_eq!(cache.backend_name(), "multilevel");
+    }
+
     #[test_case(false, true; "get from local")]
     #[test_case(true, false; "get from express")]
     #[test_case(true, true; "both empty")]
@@ -253,6 +281,58 @@ mod tests {
         assert_eq!(client.object_count(), 0);
     }
 
+    /// Spawner which holds on to spawned tasks until they are explicitly run.
+    #[derive(Default)]
+    struct CapturingSpawner {
+        tasks: std::sync::Mutex<Vec<FutureObj<'static, ()>>>,
+    }
+
+    impl Spawn for CapturingSpawner {
+        fn spawn_obj(&self, future: FutureObj<'static, ()>) -> Result<(), SpawnError> {
+            self.tasks.lock().unwrap().push(future);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_background_put_uses_custom_spawner() {
+        let (_cache_dir, disk_cache) = create_disk_cache();
+        let (_, express_cache) = create_express_cache();
+
+        let data = ChecksummedBytes::new("Foo".into());
+        let object_size = data.len();
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        express_cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), object_size)
+            .await
+            .expect("put should succeed");
+
+        let cache = MultilevelDataCache::new(disk_cache.clone(), express_cache, CapturingSpawner::default());
+        cache
+            .get_block(&cache_key, 0, 0, object_size)
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+
+        // The local cache is only populated once the captured task runs.
+        let tasks = std::mem::take(&mut *cache.runtime.tasks.lock().unwrap());
+        assert_eq!(tasks.len(), 1);
+        assert!(disk_cache
+            .get_block(&cache_key, 0, 0, object_size)
+            .await
+            .expect("cache should be accessible")
+            .is_none());
+        for task in tasks {
+            task.await;
+        }
+        let entry = disk_cache
+            .get_block(&cache_key, 0, 0, object_size)
+            .await
+            .expect("cache should be accessible")
+            .expect("cache entry should be returned");
+        assert_eq!(data, entry);
+    }
+
     #[tokio::test]
     async fn test_get_from_local() {
         let (_cache_dir, disk_cache) = create_disk_cache();
@@ -0,0 +1,219 @@
+//! Module for the limits of the concurrent requests of [ExpressDataCache](super::ExpressDataCache).
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use futures::channel::oneshot;
+
+use crate::sync::{Arc, Mutex};
+
+use super::DEFAULT_GET_PRIORITY;
+
+/// Limits the number of concurrent operations, admitting the waiting operation with the highest priority
+/// when one completes.
+#[derive(Debug)]
+pub(super) struct PriorityGate {
+    state: Mutex<PriorityGateState>,
+    /// Counter incremented when an operation has to wait.
+    queued_metric: &'static str,
+}
+
+#[derive(Debug)]
+struct PriorityGateState {
+    limit: usize,
+    in_use: usize,
+    next_seq: u64,
+    waiters: BinaryHeap<PriorityGateWaiter>,
+}
+
+impl PriorityGateState {
+    /// Hand permits to the waiting operations while the limit allows it.
+    fn admit_waiters(&mut self, gate: &Arc<PriorityGate>) {
+        while self.in_use < self.limit {
+            let Some(waiter) = self.waiters.pop() else {
+                return;
+            };
+            match waiter.sender.send(PriorityGatePermit {
+                gate: Some(gate.clone()),
+            }) {
+                Ok(()) => self.in_use += 1,
+                // The waiting operation was cancelled, the permit goes to the next one.
+                Err(mut permit) => permit.gate = None,
+            }
+        }
+    }
+}
+
+/// Operation waiting for a [PriorityGate], ordered by priority then by arrival.
+#[derive(Debug)]
+struct PriorityGateWaiter {
+    priority: i32,
+    seq: u64,
+    sender: oneshot::Sender<PriorityGatePermit>,
+}
+
+impl Ord for PriorityGateWaiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority).then(other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for PriorityGateWaiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for PriorityGateWaiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PriorityGateWaiter {}
+
+impl PriorityGate {
+    pub(super) fn new(permits: usize, queued_metric: &'static str) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(PriorityGateState {
+                limit: permits.max(1),
+                in_use: 0,
+                next_seq: 0,
+                waiters: BinaryHeap::new(),
+            }),
+            queued_metric,
+        })
+    }
+
+    /// Change the number of operations admitted at a time. Operations in progress above a lower limit
+    /// complete, but no other is admitted until enough of them did.
+    fn set_limit(self: &Arc<Self>, limit: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.limit = limit.max(1);
+        state.admit_waiters(self);
+    }
+
+    /// Wait until the operation can start, returning a permit to hold until it completes.
+    pub(super) async fn acquire(self: &Arc<Self>, priority: i32) -> PriorityGatePermit {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            if state.in_use < state.limit {
+                state.in_use += 1;
+                return PriorityGatePermit {
+                    gate: Some(self.clone()),
+                };
+            }
+            let (sender, receiver) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiters.push(PriorityGateWaiter { priority, seq, sender });
+            receiver
+        };
+        metrics::counter!(self.queued_metric).increment(1);
+        // Senders are only dropped after this receiver was dropped, see [PriorityGatePermit::drop].
+        receiver.await.expect("permit should be sent to waiting operations")
+    }
+}
+
+/// Permit of a [PriorityGate], handed to the next waiting operation when dropped.
+#[derive(Debug)]
+pub(super) struct PriorityGatePermit {
+    gate: Option<Arc<PriorityGate>>,
+}
+
+impl Drop for PriorityGatePermit {
+    fn drop(&mut self) {
+        let Some(gate) = self.gate.take() else {
+            return;
+        };
+        let mut state = gate.state.lock().unwrap();
+        state.in_use -= 1;
+        state.admit_waiters(&gate);
+    }
+}
+
+/// Additive-increase/multiplicative-decrease (AIMD) control of the number of concurrent block writes,
+/// see [ExpressDataCacheConfig::adaptive_put_concurrency](super::ExpressDataCacheConfig::adaptive_put_concurrency).
+///
+/// The limit is multiplied by [AdaptiveConcurrency::decrease_factor] whenever a write fails to reach the
+/// cache bucket, e.g. when it is throttled, and increased by one once as many writes as the limit succeeded.
+#[derive(Clone, Debug)]
+pub struct AdaptiveConcurrency {
+    /// Limit of concurrent writes when the cache is created.
+    pub initial_limit: usize,
+    /// The limit is never decreased below this.
+    pub min_limit: usize,
+    /// The limit is never increased above this.
+    pub max_limit: usize,
+    /// Factor applied to the limit when a write fails, between 0 and 1.
+    pub decrease_factor: f64,
+}
+
+impl Default for AdaptiveConcurrency {
+    fn default() -> Self {
+        Self {
+            initial_limit: 16,
+            min_limit: 1,
+            max_limit: 64,
+            decrease_factor: 0.5,
+        }
+    }
+}
+
+/// Gate of the block writes with a limit adjusted by [AdaptiveConcurrency].
+#[derive(Debug)]
+pub(super) struct AdaptiveConcurrencyLimit {
+    config: AdaptiveConcurrency,
+    gate: Arc<PriorityGate>,
+    state: Mutex<AdaptiveConcurrencyState>,
+}
+
+#[derive(Debug)]
+struct AdaptiveConcurrencyState {
+    limit: usize,
+    /// Writes that succeeded since the limit last changed.
+    successes: usize,
+}
+
+impl AdaptiveConcurrencyLimit {
+    pub(super) fn new(config: AdaptiveConcurrency) -> Self {
+        let min_limit = config.min_limit.max(1);
+        let limit = config.initial_limit.clamp(min_limit, config.max_limit.max(min_limit));
+        metrics::gauge!("express_data_cache.put_concurrency_limit").set(limit as f64);
+        Self {
+            config,
+            gate: PriorityGate::new(limit, "express_data_cache.queued_puts"),
+            state: Mutex::new(AdaptiveConcurrencyState { limit, successes: 0 }),
+        }
+    }
+
+    pub(super) fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    pub(super) async fn acquire(&self) -> PriorityGatePermit {
+        self.gate.acquire(DEFAULT_GET_PRIORITY).await
+    }
+
+    /// Update the limit after a write completed.
+    pub(super) fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        let min_limit = self.config.min_limit.max(1);
+        let limit = if success {
+            state.successes += 1;
+            if state.successes < state.limit {
+                return;
+            }
+            (state.limit + 1).min(self.config.max_limit.max(min_limit))
+        } else {
+            metrics::counter!("express_data_cache.put_concurrency_decreases").increment(1);
+            ((state.limit as f64 * self.config.decrease_factor) as usize).max(min_limit)
+        };
+        state.successes = 0;
+        if limit != state.limit {
+            state.limit = limit;
+            self.gate.set_limit(limit);
+            metrics::gauge!("express_data_cache.put_concurrency_limit").set(limit as f64);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Module for the limits of the request rates to the cache buckets of [ExpressDataCache](super::ExpressDataCache),
+//! see [ExpressDataCacheConfig::shared_requests_per_second](super::ExpressDataCacheConfig::shared_requests_per_second).
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use crate::sync::{Arc, Mutex};
+
+/// Rate limiters of the cache buckets, shared by all caches in the process.
+static SHARED_RATE_LIMITERS: LazyLock<std::sync::Mutex<HashMap<String, Arc<RequestRateLimiter>>>> =
+    LazyLock::new(Default::default);
+
+/// Spaces requests evenly to stay under a number of requests per second.
+#[derive(Debug)]
+pub(super) struct RequestRateLimiter {
+    requests_per_second: u32,
+    interval: Duration,
+    /// Earliest time the next request can be sent.
+    next_slot: Mutex<Instant>,
+}
+
+impl RequestRateLimiter {
+    /// Get the limiter shared by the caches of `bucket_name`, creating it if needed.
+    pub(super) fn for_bucket(bucket_name: &str, requests_per_second: u32) -> Arc<Self> {
+        let mut limiters = SHARED_RATE_LIMITERS.lock().unwrap();
+        let limiter = limiters.entry(bucket_name.to_owned()).or_insert_with(|| {
+            let requests_per_second = requests_per_second.max(1);
+            Arc::new(Self {
+                requests_per_second,
+                interval: Duration::from_secs(1) / requests_per_second,
+                next_slot: Mutex::new(Instant::now()),
+            })
+        });
+        if limiter.requests_per_second != requests_per_second {
+            tracing::warn!(
+                bucket = bucket_name,
+                requests_per_second = limiter.requests_per_second,
+                ignored = requests_per_second,
+                "cache bucket already has a shared rate limit, keeping the existing limit"
+            );
+        }
+        limiter.clone()
+    }
+
+    /// Wait until a request can be sent.
+    pub(super) async fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        if slot > Instant::now() {
+            metrics::counter!("express_data_cache.rate_limited_requests").increment(1);
+            async_io::Timer::at(slot).await;
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Module for the retries of the requests of [ExpressDataCache](super::ExpressDataCache) to the cache bucket.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use mountpoint_s3_client::error::{GetObjectError, ObjectClientError};
+use mountpoint_s3_client::error_metadata::ProvideErrorMetadata;
+use mountpoint_s3_client::types::ObjectClientResult;
+
+/// Classifies a GetObject error from the cache bucket.
+pub(super) type RetryClassifier<ClientError> =
+    Box<dyn Fn(&ObjectClientError<GetObjectError, ClientError>) -> RetryDecision + Send + Sync>;
+
+/// Default maximum number of attempts for a GetObject request to the cache bucket, see
+/// [ExpressDataCacheConfig::max_get_attempts](super::ExpressDataCacheConfig::max_get_attempts).
+pub(super) const MAX_GET_ATTEMPTS: u32 = 3;
+
+/// Retry the GetObject `request` for `object_key` while it fails with errors classified as transient, up to
+/// `max_attempts` attempts, see [ExpressDataCacheConfig::retry_deadline](super::ExpressDataCacheConfig::retry_deadline)
+/// and [ExpressDataCacheConfig::max_retry_after](super::ExpressDataCacheConfig::max_retry_after).
+pub(super) async fn retry_get<T, C, Fut>(
+    object_key: &str,
+    max_attempts: u32,
+    retry_deadline: Option<Duration>,
+    max_retry_after: Option<Duration>,
+    classify: impl Fn(&ObjectClientError<GetObjectError, C>) -> RetryDecision,
+    mut request: impl FnMut() -> Fut,
+) -> ObjectClientResult<T, GetObjectError, C>
+where
+    C: std::error::Error + ProvideErrorMetadata,
+    Fut: Future<Output = ObjectClientResult<T, GetObjectError, C>>,
+{
+    let start = Instant::now();
+    let mut attempt = 1;
+    loop {
+        let err = match request().await {
+            Err(err) if attempt < max_attempts && classify(&err) == RetryDecision::Transient => err,
+            result => return result,
+        };
+        let delay = match (err.meta().retry_after, max_retry_after) {
+            (Some(retry_after), Some(max_retry_after)) if retry_after > max_retry_after => {
+                tracing::debug!(key = object_key, attempt, ?retry_after, "retry-after hint is too long");
+                metrics::counter!("express_data_cache.retry_after_exceeded").increment(1);
+                return Err(err);
+            }
+            (Some(retry_after), Some(_)) => retry_after,
+            _ => Duration::ZERO,
+        };
+        if retry_deadline.is_some_and(|deadline| start.elapsed() + delay >= deadline) {
+            tracing::debug!(key = object_key, attempt, error = ?err, "retry deadline exceeded");
+            metrics::counter!("express_data_cache.retry_deadline_exceeded").increment(1);
+            return Err(err);
+        }
+        tracing::debug!(key = object_key, attempt, error = ?err, ?delay, "retrying block request");
+        metrics::counter!("express_data_cache.get_retries").increment(1);
+        if !delay.is_zero() {
+            async_io::Timer::after(delay).await;
+        }
+        attempt += 1;
+    }
+}
+
+/// Whether a failed request to the cache bucket should be retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// The error may not occur again, so the request is retried.
+    Transient,
+    /// Retrying the request will fail in the same way.
+    Permanent,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use mountpoint_s3_client::error_metadata::ClientErrorMetadata;
+    use test_case::test_case;
+
+    use super::*;
+
+    /// Client error of a throttled request, with a `Retry-After` hint.
+    #[derive(Debug, thiserror::Error)]
+    #[error("request throttled")]
+    struct ThrottledClientError(Duration);
+
+    impl ProvideErrorMetadata for ThrottledClientError {
+        fn meta(&self) -> ClientErrorMetadata {
+            ClientErrorMetadata {
+                http_code: Some(503),
+                error_code: Some("SlowDown".to_owned()),
+                retry_after: Some(self.0),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[test_case(Some(Duration::from_secs(1)), true; "hint honored")]
+    #[test_case(Some(Duration::from_millis(100)), false; "hint too long")]
+    #[test_case(None, false; "hints ignored")]
+    #[tokio::test]
+    async fn test_retry_after(max_retry_after: Option<Duration>, expect_retry: bool) {
+        let retry_after = Duration::from_millis(200);
+        let attempts = AtomicUsize::new(0);
+        let classify = |err: &ObjectClientError<GetObjectError, ThrottledClientError>| {
+            // The default classification of the cache.
+            if max_retry_after.is_some() && err.meta().retry_after.is_some() {
+                RetryDecision::Transient
+            } else {
+                RetryDecision::Permanent
+            }
+        };
+        let start = Instant::now();
+        let result = retry_get("key", MAX_GET_ATTEMPTS, None, max_retry_after, classify, || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                Err(ObjectClientError::ClientError(ThrottledClientError(retry_after)))
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+        let elapsed = start.elapsed();
+        if expect_retry {
+            result.expect("request should be retried");
+            assert_eq!(attempts.load(Ordering::SeqCst), 2);
+            assert!(
+                elapsed >= retry_after,
+                "retry should wait for the hint, waited {elapsed:?}"
+            );
+            assert!(
+                elapsed < retry_after * 5,
+                "retry should not wait much longer than the hint, waited {elapsed:?}"
+            );
+        } else {
+            result.expect_err("request should not be retried");
+            assert_eq!(attempts.load(Ordering::SeqCst), 1);
+            assert!(
+                elapsed < retry_after,
+                "failure should not wait for the hint, waited {elapsed:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Module for a [DataCache] wrapper whose backend can be replaced at runtime.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::object::ObjectId;
+use crate::sync::RwLock;
+
+use super::{BlockIndex, ChecksummedBytes, DataCache, DataCacheResult};
+
+type SharedCache = Arc<dyn DataCache + Send + Sync>;
+
+/// A data cache forwarding operations to a backend that can be replaced with [SwappableDataCache::swap].
+///
+/// This allows failing over to another backend, e.g. from an [super::ExpressDataCache] to a
+/// [super::DiskDataCache], without restarting. Operations already in progress when the backend is
+/// swapped complete against the previous backend. The backends should have the same block size,
+/// since callers usually read it once.
+pub struct SwappableDataCache {
+    cache: RwLock<SharedCache>,
+}
+
+impl SwappableDataCache {
+    /// Create a new instance forwarding to `cache`.
+    pub fn new(cache: impl DataCache + Send + Sync + 'static) -> Self {
+        Self {
+            cache: RwLock::new(Arc::new(cache)),
+        }
+    }
+
+    /// Replace the backend with `cache`, returning the previous one.
+    pub fn swap(&self, cache: impl DataCache + Send + Sync + 'static) -> Arc<dyn DataCache + Send + Sync> {
+        let previous = std::mem::replace(&mut *self.cache.write().unwrap(), Arc::new(cache));
+        metrics::counter!("swappable_cache.swaps", "cache" => previous.backend_name()).increment(1);
+        previous
+    }
+
+    fn current(&self) -> SharedCache {
+        self.cache.read().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl DataCache for SwappableDataCache {
+    async fn get_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        self.current()
+            .get_block(cache_key, block_idx, block_offset, object_size)
+            .await
+    }
+
+    async fn put_block(
+        &self,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        bytes: ChecksummedBytes,
+        object_size: usize,
+    ) -> DataCacheResult<()> {
+        self.current()
+            .put_block(cache_key, block_idx, block_offset, bytes, object_size)
+            .await
+    }
+
+    fn block_size(&self) -> u64 {
+        self.current().block_size()
+    }
+
+    fn max_block_size(&self) -> u64 {
+        self.current().max_block_size()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.current().backend_name()
+    }
+
+    fn supports_ranged_reads(&self) -> bool {
+        self.current().supports_ranged_reads()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mountpoint_s3_client::types::ETag;
+
+    use crate::data_cache::InMemoryDataCache;
+
+    /// Cache which never holds any block.
+    struct NullDataCache {
+        block_size: u64,
+    }
+
+    #[async_trait]
+    impl DataCache for NullDataCache {
+        async fn get_block(
+            &self,
+            _cache_key: &ObjectId,
+            _block_idx: BlockIndex,
+            _block_offset: u64,
+            _object_size: usize,
+        ) -> DataCacheResult<Option<ChecksummedBytes>> {
+            Ok(None)
+        }
+
+        async fn put_block(
+            &self,
+            _cache_key: ObjectId,
+            _block_idx: BlockIndex,
+            _block_offset: u64,
+            _bytes: ChecksummedBytes,
+            _object_size: usize,
+        ) -> DataCacheResult<()> {
+            Ok(())
+        }
+
+        fn block_size(&self) -> u64 {
+            self.block_size
+        }
+
+        fn max_block_size(&self) -> u64 {
+            u64::MAX
+        }
+
+        fn backend_name(&self) -> &'static str {
+            "null"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_swap() {
+        let cache = SwappableDataCache::new(InMemoryDataCache::new(1024));
+        let data = ChecksummedBytes::new("Foo".into());
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data.clone()));
+        assert_eq!(cache.backend_name(), "memory");
+
+        let previous = cache.swap(NullDataCache { block_size: 1024 });
+        assert_eq!(previous.backend_name(), "memory");
+        assert_eq!(cache.backend_name(), "null");
+        assert_eq!(cache.block_size(), 1024);
+        let block = cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert!(block.is_none(), "blocks of the previous backend should not be served");
+
+        // The previous backend is left intact.
+        let block = previous
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+    }
+}
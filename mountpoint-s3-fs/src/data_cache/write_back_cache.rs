@@ -0,0 +1,267 @@
+//! Module for a [DataCache] wrapper that delays writes to coalesce repeated writes of the same block.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::task::{Spawn, SpawnExt};
+use tracing::warn;
+
+use crate::object::ObjectId;
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::Mutex;
+
+use super::{BlockIndex, ChecksummedBytes, DataCache, DataCacheError, DataCacheResult};
+
+type PendingWrites = Mutex<HashMap<(ObjectId, BlockIndex), PendingWrite>>;
+
+/// A data cache which holds written blocks for a debounce window before writing them to the wrapped cache.
+///
+/// Blocks written again within the window replace the pending write and restart the window, so only the
+/// latest value is written. Pending blocks are served by [DataCache::get_block] until they are written.
+/// Writes happen in tasks spawned on the runtime, and [WriteBackDataCache::flush] writes all pending blocks
+/// immediately. Pending blocks are lost if the cache is dropped without flushing.
+pub struct WriteBackDataCache<Cache, Runtime> {
+    cache: Arc<Cache>,
+    delay: Duration,
+    runtime: Runtime,
+    pending: Arc<PendingWrites>,
+    next_generation: AtomicU64,
+}
+
+struct PendingWrite {
+    /// Identifies the write that scheduled the block, so that only the latest write flushes it.
+    generation: u64,
+    block_offset: u64,
+    bytes: ChecksummedBytes,
+    object_size: usize,
+}
+
+impl<Cache, Runtime> WriteBackDataCache<Cache, Runtime>
+where
+    Cache: DataCache + Send + Sync + 'static,
+    Runtime: Spawn,
+{
+    /// Create a new instance writing blocks to `cache` once they were not written again for `delay`.
+    pub fn new(cache: Cache, delay: Duration, runtime: Runtime) -> Self {
+        Self {
+            cache: Arc::new(cache),
+            delay,
+            runtime,
+            pending: Default::default(),
+            next_generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Write all pending blocks to the wrapped cache without waiting for their debounce window to expire.
+    ///
+    /// Every pending block is written even if some writes fail. The first error is returned.
+    pub async fn flush(&self) -> DataCacheResult<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let mut result = Ok(());
+        for ((cache_key, block_idx), write) in pending {
+            let write_result = self
+                .cache
+                .put_block(cache_key, block_idx, write.block_offset, write.bytes, write.object_size)
+                .await;
+            if result.is_ok() {
+                result = write_result;
+            }
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<Cache, Runtime> DataCache for WriteBackDataCache<Cache, Runtime>
+where
+    Cache: DataCache + Send + Sync + 'static,
+    Runtime: Spawn + Send + Sync,
+{
+    async fn get_block(
+        &self,
+        cache_key: &ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        object_size: usize,
+    ) -> DataCacheResult<Option<ChecksummedBytes>> {
+        let pending_bytes = self
+            .pending
+            .lock()
+            .unwrap()
+            .get(&(cache_key.clone(), block_idx))
+            .filter(|write| write.block_offset == block_offset)
+            .map(|write| write.bytes.clone());
+        if let Some(bytes) = pending_bytes {
+            return Ok(Some(bytes));
+        }
+        self.cache
+            .get_block(cache_key, block_idx, block_offset, object_size)
+            .await
+    }
+
+    /// Schedules the block to be written once the debounce window expires.
+    async fn put_block(
+        &self,
+        cache_key: ObjectId,
+        block_idx: BlockIndex,
+        block_offset: u64,
+        bytes: ChecksummedBytes,
+        object_size: usize,
+    ) -> DataCacheResult<()> {
+        let generation = self.next_generation.fetch_add(1, Ordering::SeqCst);
+        let key = (cache_key, block_idx);
+        let write = PendingWrite {
+            generation,
+            block_offset,
+            bytes,
+            object_size,
+        };
+        if self.pending.lock().unwrap().insert(key.clone(), write).is_some() {
+            metrics::counter!("write_back_cache.coalesced_writes").increment(1);
+        }
+
+        let cache = self.cache.clone();
+        let pending = self.pending.clone();
+        let delay = self.delay;
+        self.runtime
+            .spawn(async move {
+                async_io::Timer::after(delay).await;
+                let write = {
+                    let mut pending = pending.lock().unwrap();
+                    match pending.get(&key) {
+                        Some(write) if write.generation == generation => pending.remove(&key),
+                        // Superseded by a later write, or already flushed.
+                        _ => None,
+                    }
+                };
+                let Some(write) = write else {
+                    return;
+                };
+                let (cache_key, block_idx) = key;
+                if let Err(error) = cache
+                    .put_block(
+                        cache_key.clone(),
+                        block_idx,
+                        write.block_offset,
+                        write.bytes,
+                        write.object_size,
+                    )
+                    .await
+                {
+                    warn!(?cache_key, block_idx, ?error, "failed to write back block");
+                }
+            })
+            .map_err(|err| DataCacheError::IoFailure(err.into()))
+    }
+
+    fn block_size(&self) -> u64 {
+        self.cache.block_size()
+    }
+
+    fn max_block_size(&self) -> u64 {
+        self.cache.max_block_size()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.cache.backend_name()
+    }
+
+    fn supports_ranged_reads(&self) -> bool {
+        self.cache.supports_ranged_reads()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::ThreadPool;
+    use mountpoint_s3_client::mock_client::{MockClient, MockClientConfig, Operation};
+    use mountpoint_s3_client::types::ETag;
+
+    use crate::data_cache::ExpressDataCache;
+
+    fn new_express_cache() -> (Arc<MockClient>, ExpressDataCache<Arc<MockClient>>) {
+        let bucket = "test-bucket";
+        let client = Arc::new(MockClient::new(MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * 1024 * 1024,
+            enable_backpressure: true,
+            initial_read_window_size: 8 * 1024 * 1024,
+            ..Default::default()
+        }));
+        let cache = ExpressDataCache::try_new(client.clone(), Default::default(), "unique source description", bucket)
+            .expect("cache config should be valid");
+        (client, cache)
+    }
+
+    #[tokio::test]
+    async fn test_rapid_writes_coalesced() {
+        let (client, express_cache) = new_express_cache();
+        let put_counter = client.new_counter(Operation::PutObjectSingle);
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let cache = WriteBackDataCache::new(express_cache, Duration::from_millis(100), runtime);
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let first = ChecksummedBytes::new("Foo".into());
+        let second = ChecksummedBytes::new("Bar".into());
+        for data in [&first, &second] {
+            cache
+                .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+                .await
+                .expect("put should succeed");
+        }
+        let block = cache
+            .get_block(&cache_key, 0, 0, second.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(second.clone()), "pending block should be served");
+
+        let mut retries = 50;
+        while put_counter.count() == 0 && retries > 0 {
+            async_io::Timer::after(Duration::from_millis(20)).await;
+            retries -= 1;
+        }
+        // Leave time for an upload of the superseded value, which should not happen.
+        async_io::Timer::after(Duration::from_millis(200)).await;
+        assert_eq!(put_counter.count(), 1, "writes should be coalesced");
+
+        let block = cache
+            .cache
+            .get_block(&cache_key, 0, 0, second.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(second), "latest value should be written");
+    }
+
+    #[tokio::test]
+    async fn test_flush_writes_immediately() {
+        let (client, express_cache) = new_express_cache();
+        let put_counter = client.new_counter(Operation::PutObjectSingle);
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let cache = WriteBackDataCache::new(express_cache, Duration::from_secs(3600), runtime);
+
+        let cache_key = ObjectId::new("a".into(), ETag::for_tests());
+        let data = ChecksummedBytes::new("Foo".into());
+        cache
+            .put_block(cache_key.clone(), 0, 0, data.clone(), data.len())
+            .await
+            .expect("put should succeed");
+        assert_eq!(put_counter.count(), 0);
+
+        cache.flush().await.expect("flush should succeed");
+        assert_eq!(put_counter.count(), 1);
+        let block = cache
+            .cache
+            .get_block(&cache_key, 0, 0, data.len())
+            .await
+            .expect("cache should be accessible");
+        assert_eq!(block, Some(data));
+
+        // Nothing is left to write.
+        cache.flush().await.expect("flush should succeed");
+        assert_eq!(put_counter.count(), 1);
+    }
+}
@@ -193,6 +193,52 @@ impl Recorder for MetricsRecorder {
     }
 }
 
+/// Run `f` with a recorder for the metrics it emits on the current thread, for use in tests.
+#[cfg(test)]
+pub(crate) fn with_captured_metrics<T>(f: impl FnOnce() -> T) -> (T, CapturedMetrics) {
+    let sink = Arc::new(MetricsSink::new());
+    let recorder = MetricsRecorder { sink: sink.clone() };
+    let result = metrics::with_local_recorder(&recorder, f);
+    (result, CapturedMetrics { sink })
+}
+
+/// Metrics recorded by [with_captured_metrics].
+#[cfg(test)]
+pub(crate) struct CapturedMetrics {
+    sink: Arc<MetricsSink>,
+}
+
+#[cfg(test)]
+impl CapturedMetrics {
+    /// Sum of the increments of the counter `name` across all its labels.
+    pub(crate) fn counter(&self, name: &str) -> u64 {
+        self.sink
+            .metrics
+            .iter()
+            .filter(|entry| entry.key().name() == name)
+            .filter_map(|entry| match entry.value() {
+                Metric::Counter(inner) => Some(inner.sum.load(crate::sync::atomic::Ordering::SeqCst)),
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// Maximum value recorded in the histogram `name` across all its labels, if any.
+    ///
+    /// The recorded values are reset.
+    pub(crate) fn histogram_max(&self, name: &str) -> Option<u64> {
+        self.sink
+            .metrics
+            .iter()
+            .filter(|entry| entry.key().name() == name)
+            .filter_map(|entry| match entry.value() {
+                Metric::Histogram(inner) => inner.run_and_reset(|histogram| histogram.max()),
+                _ => None,
+            })
+            .max()
+    }
+}
+
 #[derive(Debug)]
 pub struct MetricsSinkHandle {
     shutdown: Sender<()>,
@@ -139,6 +139,7 @@ where
     Cache: DataCache + Send + Sync + 'static,
     Runtime: Spawn + Clone + Send + Sync + 'static,
 {
+    tracing::debug!(backend = cache.backend_name(), "using data cache");
     let part_stream = CachingPartStream::new(runtime, cache);
     Prefetcher::new(part_stream, prefetcher_config)
 }
@@ -174,7 +174,8 @@ where
                 ),
             }
             // If a block is uncached or reading it fails, fallback to S3 for the rest of the stream.
-            metrics::counter!("prefetch.blocks_served_from_cache").increment(block_index - block_range.start);
+            metrics::counter!("prefetch.blocks_served_from_cache", "cache" => self.cache.backend_name())
+                .increment(block_index - block_range.start);
             metrics::counter!("prefetch.blocks_requested_to_client").increment(block_range.end - block_index);
             return self
                 .get_from_client(
@@ -185,7 +186,8 @@ where
                 .await;
         }
         // We served the whole range from cache.
-        metrics::counter!("prefetch.blocks_served_from_cache").increment(block_range.end - block_range.start);
+        metrics::counter!("prefetch.blocks_served_from_cache", "cache" => self.cache.backend_name())
+            .increment(block_range.end - block_range.start);
     }
 
     async fn get_from_client(
@@ -408,7 +410,7 @@ mod tests {
     use test_case::test_case;
 
     use crate::{
-        data_cache::InMemoryDataCache,
+        data_cache::{ExpressDataCache, ExpressDataCacheConfig, InMemoryDataCache},
         mem_limiter::{MemoryLimiter, MINIMUM_MEM_LIMIT},
         object::ObjectId,
     };
@@ -557,6 +559,89 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cache_uses_dedicated_client() {
+        let key = "object";
+        let block_size = 1 * MB;
+        let object_size = 4 * MB;
+        let object = MockObject::ramp(0xaa, object_size, ETag::for_tests());
+        let id = ObjectId::new(key.to_owned(), object.etag());
+
+        // backpressure config
+        let initial_read_window_size = 1 * MB;
+        let max_read_window_size = 64 * MB;
+        let read_window_size_multiplier = 2;
+
+        let bucket = "test-bucket";
+        let config = MockClientConfig {
+            bucket: bucket.to_string(),
+            part_size: 8 * MB,
+            enable_backpressure: true,
+            initial_read_window_size,
+            ..Default::default()
+        };
+        let mock_client = Arc::new(MockClient::new(config));
+        let mem_limiter = Arc::new(MemoryLimiter::new(mock_client.clone(), MINIMUM_MEM_LIMIT));
+        mock_client.add_object(key, object.clone());
+
+        // The cache gets its own client, distinct from the one used for the mounted bucket.
+        let cache_bucket = "cache-bucket";
+        let cache_client_config = MockClientConfig {
+            bucket: cache_bucket.to_string(),
+            part_size: 8 * MB,
+            enable_backpressure: true,
+            initial_read_window_size: 8 * MB,
+            ..Default::default()
+        };
+        let cache_client = Arc::new(MockClient::new(cache_client_config));
+        let cache_config = ExpressDataCacheConfig {
+            block_size: block_size as u64,
+            max_object_size: object_size,
+            ..Default::default()
+        };
+        let cache = ExpressDataCache::try_new(cache_client.clone(), cache_config, bucket, cache_bucket)
+            .expect("cache config should be valid");
+
+        let runtime = ThreadPool::builder().pool_size(1).create().unwrap();
+        let stream = CachingPartStream::new(runtime, cache);
+        let range = RequestRange::new(object_size, 0, object_size);
+        let request_config = RequestTaskConfig {
+            bucket: bucket.to_owned(),
+            object_id: id.clone(),
+            range,
+            read_part_size: 8 * MB,
+            preferred_part_size: 256 * KB,
+            initial_read_window_size,
+            max_read_window_size,
+            read_window_size_multiplier,
+        };
+
+        // First request (from client), populating the cache through the cache client
+        let put_object_counter = mock_client.new_counter(Operation::PutObjectSingle);
+        let request_task = stream.spawn_get_object_request(&mock_client, request_config.clone(), mem_limiter.clone());
+        compare_read(&id, &object, request_task);
+
+        // Wait until all blocks are saved to the cache before spawning a new request
+        let expected_block_count = object_size.div_ceil(block_size);
+        while cache_client.object_count() < expected_block_count {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            put_object_counter.count(),
+            0,
+            "no cache writes should use the data client"
+        );
+        assert_eq!(mock_client.object_count(), 1);
+
+        // Second request (from cache), served through the cache client
+        let get_object_counter = mock_client.new_counter(Operation::GetObject);
+        let cache_get_object_counter = cache_client.new_counter(Operation::GetObject);
+        let request_task = stream.spawn_get_object_request(&mock_client, request_config, mem_limiter.clone());
+        compare_read(&id, &object, request_task);
+        assert_eq!(get_object_counter.count(), 0);
+        assert!(cache_get_object_counter.count() > 0);
+    }
+
     fn compare_read<Client: ObjectClient>(id: &ObjectId, object: &MockObject, mut request_task: RequestTask<Client>) {
         let mut offset = request_task.start_offset();
         let mut remaining = request_task.total_size();
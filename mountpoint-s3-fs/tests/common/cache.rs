@@ -135,4 +135,12 @@ impl<Cache: DataCache + Send + Sync + 'static> DataCache for CacheTestWrapper<Ca
     fn block_size(&self) -> u64 {
         self.inner.cache.block_size()
     }
+
+    fn max_block_size(&self) -> u64 {
+        self.inner.cache.max_block_size()
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.inner.cache.backend_name()
+    }
 }
@@ -1600,6 +1600,7 @@ async fn test_lookup_forbidden() {
                 http_code: Some(403), // here we assume that HeadObject failes with 403 code
                 error_code: None,
                 error_message: None,
+                retry_after: None,
             },
             error_code: Some(MOUNTPOINT_ERROR_CLIENT.to_string()),
             s3_bucket_name: Some(bucket.to_string()),
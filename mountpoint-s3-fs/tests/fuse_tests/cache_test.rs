@@ -41,12 +41,10 @@ async fn express_invalid_block_read() {
 
     // Mount the bucket
     let client = create_crt_client(CLIENT_PART_SIZE, CLIENT_PART_SIZE, Default::default());
-    let cache = CacheTestWrapper::new(ExpressDataCache::new(
-        client.clone(),
-        Default::default(),
-        &bucket,
-        &cache_bucket,
-    ));
+    let cache = CacheTestWrapper::new(
+        ExpressDataCache::try_new(client.clone(), Default::default(), &bucket, &cache_bucket)
+            .expect("cache config should be valid"),
+    );
     let (mount_point, _session) = mount_bucket(client.clone(), cache.clone(), &bucket, &prefix);
 
     // Put an object to the mounted bucket
@@ -103,7 +101,8 @@ fn express_cache_write_read(key_suffix: &str, key_size: usize, object_size: usiz
     let client = create_crt_client(CLIENT_PART_SIZE, CLIENT_PART_SIZE, Default::default());
     let bucket_name = get_standard_bucket();
     let express_bucket_name = get_express_bucket();
-    let cache = ExpressDataCache::new(client.clone(), Default::default(), &bucket_name, &express_bucket_name);
+    let cache = ExpressDataCache::try_new(client.clone(), Default::default(), &bucket_name, &express_bucket_name)
+        .expect("cache config should be valid");
 
     cache_write_read_base(
         client,
@@ -125,6 +124,8 @@ fn disk_cache_write_read(key_suffix: &str, key_size: usize, object_size: usize)
     let cache_config = DiskDataCacheConfig {
         block_size: CACHE_BLOCK_SIZE,
         limit: Default::default(),
+        min_free_bytes: 0,
+        block_size_mismatch: Default::default(),
     };
     let cache = DiskDataCache::new(cache_dir.path().to_path_buf(), cache_config);
 
@@ -159,7 +160,8 @@ fn express_cache_write_read_sse(sse_type: Option<String>, kms_key_id: Option<Str
         sse: ServerSideEncryption::new(sse_type.clone(), kms_key_id.clone()),
         ..Default::default()
     };
-    let cache = ExpressDataCache::new(client.clone(), config, &bucket_name, &cache_bucket);
+    let cache = ExpressDataCache::try_new(client.clone(), config, &bucket_name, &cache_bucket)
+        .expect("cache config should be valid");
 
     cache_write_read_base(
         client,
@@ -178,7 +180,8 @@ async fn express_cache_read_empty() {
     let client = create_crt_client(CLIENT_PART_SIZE, CLIENT_PART_SIZE, Default::default());
     let bucket_name = get_standard_bucket();
     let express_bucket_name = get_express_bucket();
-    let cache = ExpressDataCache::new(client, Default::default(), &bucket_name, &express_bucket_name);
+    let cache = ExpressDataCache::try_new(client, Default::default(), &bucket_name, &express_bucket_name)
+        .expect("cache config should be valid");
 
     cache_read_empty(cache, "express_cache_read_empty").await;
 }
@@ -189,6 +192,8 @@ async fn disk_cache_read_empty() {
     let cache_config = DiskDataCacheConfig {
         block_size: CACHE_BLOCK_SIZE,
         limit: Default::default(),
+        min_free_bytes: 0,
+        block_size_mismatch: Default::default(),
     };
     let cache = DiskDataCache::new(cache_dir.path().to_path_buf(), cache_config);
 
@@ -203,7 +208,8 @@ async fn express_cache_verify_fail_non_express() {
     let client = create_crt_client(CLIENT_PART_SIZE, CLIENT_PART_SIZE, Default::default());
     let bucket_name = get_standard_bucket();
     let cache_bucket_name = get_standard_bucket();
-    let cache = ExpressDataCache::new(client.clone(), Default::default(), &bucket_name, &cache_bucket_name);
+    let cache = ExpressDataCache::try_new(client.clone(), Default::default(), &bucket_name, &cache_bucket_name)
+        .expect("cache config should be valid");
     let err = cache
         .verify_cache_valid()
         .await
@@ -251,7 +257,8 @@ async fn express_cache_verify_fail_forbidden() {
         S3ClientAuthConfig::Provider(provider),
     );
 
-    let cache = ExpressDataCache::new(client.clone(), Default::default(), &bucket_name, &cache_bucket_name);
+    let cache = ExpressDataCache::try_new(client.clone(), Default::default(), &bucket_name, &cache_bucket_name)
+        .expect("cache config should be valid");
     let err = cache.verify_cache_valid().await.expect_err("cache must be write-able");
 
     if let DataCacheError::IoFailure(err) = err {
@@ -343,7 +350,8 @@ fn express_cache_expected_bucket_owner(cache_bucket: String, owner_checked: bool
     // Create cache and mount a bucket
     let bucket = get_standard_bucket();
     let prefix = get_test_prefix("express_expected_bucket_owner");
-    let cache = ExpressDataCache::new(client.clone(), Default::default(), &bucket, &cache_bucket);
+    let cache = ExpressDataCache::try_new(client.clone(), Default::default(), &bucket, &cache_bucket)
+        .expect("cache config should be valid");
     let cache_valid = block_on(cache.verify_cache_valid());
     if owner_checked && !owner_matches {
         match cache_valid {
@@ -79,6 +79,7 @@ async fn test_lookup_throttled_mock(head_object_throttled: bool, list_object_thr
                 http_code: Some(503),
                 error_code: None,
                 error_message: None,
+                retry_after: None,
             },
             error_code: Some(MOUNTPOINT_ERROR_CLIENT.to_string()),
             s3_bucket_name: Some(bucket.to_string()),
@@ -116,6 +117,7 @@ async fn test_lookup_unhandled_error_mock() {
                 http_code: Some(409),
                 error_code: None,
                 error_message: None,
+                retry_after: None,
             },
             error_code: Some(MOUNTPOINT_ERROR_CLIENT.to_string()),
             s3_bucket_name: Some(bucket.to_string()),
@@ -57,6 +57,7 @@ fn create_mock_client(
         unordered_list_seed: None,
         enable_backpressure: true,
         initial_read_window_size: 1024 * 1024 + 128 * 1024, // matching real MP
+        region: None,
     };
     let client = ThroughputMockClient::new(config, max_throughput_gbps as f64);
 